@@ -0,0 +1,431 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use argon2::Argon2;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use serde::{Deserialize, Serialize};
+use tracing_lite::{error, info, warn};
+use ulid::Ulid;
+
+use crate::backend::BackendError;
+use crate::cont::acc::Account;
+use crate::storage::{app_path, write_atomic};
+use crate::util::{Id, Tb};
+
+
+
+/// File (in the [config_dir](crate::storage::config_dir)) where the signed in sessions are remembered.
+const SESSION_FILE: &str = "session.json";
+
+
+/// Data needed to sign in (or sign up).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String
+}
+
+/// Authenticated account with the session token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthSession {
+    pub account: Account,
+    pub token: String
+}
+
+
+// MARK: StoredSession
+
+/// Session remembered on disk, used to sign in again without credentials.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredSession {
+    /// Account of the session (username can change, so it isn't the key).
+    #[serde(default)]
+    pub acc_id: Ulid,
+    pub username: String,
+    pub token: String
+}
+
+impl StoredSession {
+    /// Load all remembered sessions (in sign in order).
+    pub fn load_all() -> Vec<Self> {
+        let Ok(file) = File::open(app_path(SESSION_FILE)) else { return Vec::new() };
+        match serde_json::from_reader(BufReader::new(file)) {
+            Ok(sessions) => sessions,
+            Err(e) => { warn!("Failed to read stored sessions: {e}"); Vec::new() }
+        }
+    }
+
+    fn save_all(sessions: &[Self]) -> Option<()> {
+        if sessions.is_empty() {
+            if let Err(e) = fs::remove_file(app_path(SESSION_FILE)) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    error!("Failed to remove session file: {e}");
+                }
+            }
+            return Some(())
+        }
+        let json = match serde_json::to_vec_pretty(sessions) {
+            Ok(json) => json,
+            Err(e) => { error!("Failed to serialize sessions: {e}"); return None }
+        };
+        match write_atomic(&app_path(SESSION_FILE), &json) {
+            Ok(_) => Some(()),
+            Err(e) => { error!("Failed to save sessions: {e}"); None }
+        }
+    }
+
+    /// Remember the session (replacing older one of the same account in place).
+    pub fn save(&self) -> Option<()> {
+        let mut sessions = Self::load_all();
+        // -- Entries stored before `acc_id` was kept are matched by the token
+        sessions.retain(|s| s.acc_id == self.acc_id || s.token != self.token);
+        match sessions.iter_mut().find(|s| s.acc_id == self.acc_id) {
+            Some(older) => *older = self.clone(),
+            None => sessions.push(self.clone())
+        }
+        Self::save_all(&sessions)
+    }
+
+    /// Forget remembered session of the account.
+    pub fn forget(acc: &Ulid) {
+        let mut sessions = Self::load_all();
+        sessions.retain(|s| s.acc_id != *acc);
+        Self::save_all(&sessions);
+    }
+
+    /// Forget this session only (eg. expired one).
+    pub fn discard(&self) {
+        let mut sessions = Self::load_all();
+        sessions.retain(|s| s.token != self.token);
+        Self::save_all(&sessions);
+    }
+
+    /// Keep the username of the remembered session in sync with the renamed account.
+    pub fn rename(acc: &Ulid, username: &str) {
+        let mut sessions = Self::load_all();
+        let Some(stored) = sessions.iter_mut().find(|s| s.acc_id == *acc) else { return };
+        stored.username = username.to_string();
+        Self::save_all(&sessions);
+    }
+}
+
+
+// MARK: LocalAuthServer
+
+/// Stand-in auth server used by the [LocalBackend](crate::backend::LocalBackend) and tests.
+/// Passwords are stored as Argon2 PHC strings (each with its own salt).
+#[derive(Debug, Default)]
+pub struct LocalAuthServer {
+    users: RefCell<HashMap<String, LocalUser>>,
+    /// K: token, V: username.
+    tokens: RefCell<HashMap<String, String>>,
+    /// Optional file keeping users and tokens between runs.
+    store: Option<PathBuf>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LocalUser {
+    acc_id: Id,
+    username: String,
+    /// PHC string, eg. `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`.
+    pass_hash: String,
+    #[serde(default)]
+    av: Vec<u8>,
+    #[serde(default)]
+    status: Option<String>
+}
+
+impl LocalUser {
+    fn account(&self) -> Account {
+        Account {
+            acc_id: self.acc_id.clone(),
+            username: self.username.clone(),
+            av: Rc::new(self.av.clone())
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LocalAuthStore {
+    users: Vec<LocalUser>,
+    tokens: HashMap<String, String>
+}
+
+impl LocalAuthServer {
+    /// Create server persisting its state in the given file.
+    pub fn with_store(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let stored = match File::open(&path) {
+            Ok(f) => serde_json::from_reader::<_, LocalAuthStore>(BufReader::new(f)).unwrap_or_else(|e| {
+                warn!("Failed to read local auth store: {e}");
+                LocalAuthStore::default()
+            }),
+            Err(_) => LocalAuthStore::default()
+        };
+        Self {
+            users: RefCell::new(stored.users.into_iter().map(|u| (u.username.clone(), u)).collect()),
+            tokens: RefCell::new(stored.tokens),
+            store: Some(path)
+        }
+    }
+
+    fn persist(&self) {
+        let Some(path) = &self.store else { return };
+        let store = LocalAuthStore {
+            users: self.users.borrow().values().cloned().collect(),
+            tokens: self.tokens.borrow().clone()
+        };
+        match serde_json::to_vec_pretty(&store) {
+            Ok(json) => if let Err(e) = write_atomic(path, &json) {
+                error!("Failed to save local auth store: {e}");
+            },
+            Err(e) => error!("Failed to serialize local auth store: {e}")
+        }
+    }
+
+    /// Hash the password with a fresh random salt.
+    fn hash(password: &str) -> Result<String, BackendError> {
+        let salt = SaltString::generate(&mut OsRng);
+        match Argon2::default().hash_password(password.as_bytes(), &salt) {
+            Ok(hash) => Ok(hash.to_string()),
+            Err(e) => {
+                error!("Failed to hash password: {e}");
+                Err(BackendError::Rejected("backend.password_not_stored".into()))
+            }
+        }
+    }
+
+    /// Check the password against the stored PHC string.
+    fn verify(pass_hash: &str, password: &str) -> bool {
+        PasswordHash::new(pass_hash)
+            .is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+    }
+
+    fn new_session(&self, user: &LocalUser) -> AuthSession {
+        let token = Ulid::new().to_string();
+        self.tokens.borrow_mut().insert(token.clone(), user.username.clone());
+        self.persist();
+        AuthSession { account: user.account(), token }
+    }
+
+    /// Create new account and sign in.
+    pub fn register(&self, cred: &Credentials) -> Result<AuthSession, BackendError> {
+        let username = cred.username.trim();
+        if username.is_empty() {
+            return Err(BackendError::Rejected("backend.username_empty".into()))
+        }
+        if cred.password.len() < 4 {
+            return Err(BackendError::Rejected("backend.password_short".into()))
+        }
+        if self.users.borrow().contains_key(username) {
+            return Err(BackendError::Rejected("backend.username_taken".into()))
+        }
+        let user = LocalUser {
+            pass_hash: Self::hash(&cred.password)?,
+            acc_id: Id::new(Tb::Acc),
+            username: username.to_string(),
+            av: Vec::new(),
+            status: None
+        };
+        info!("local auth: registered {}", user.username);
+        self.users.borrow_mut().insert(user.username.clone(), user.clone());
+        Ok(self.new_session(&user))
+    }
+
+    /// Exchange credentials for the session.
+    pub fn login(&self, cred: &Credentials) -> Result<AuthSession, BackendError> {
+        let user = self.users.borrow().get(cred.username.trim()).cloned();
+        match user {
+            Some(user) if Self::verify(&user.pass_hash, &cred.password) => {
+                info!("local auth: {} signed in", user.username);
+                Ok(self.new_session(&user))
+            },
+            _ => Err(BackendError::Rejected("backend.invalid_credentials".into()))
+        }
+    }
+
+    /// Exchange stored token for the session.
+    pub fn resume(&self, token: &str) -> Result<AuthSession, BackendError> {
+        let username = self.tokens.borrow().get(token).cloned();
+        let user = username.and_then(|u| self.users.borrow().get(&u).cloned());
+        match user {
+            Some(user) => Ok(AuthSession { account: user.account(), token: token.to_string() }),
+            None => Err(BackendError::Rejected("backend.session_expired".into()))
+        }
+    }
+
+    /// Update profile (username and avatar) of the session account.
+    pub fn update_profile(&self, token: &str, acc: Account) -> Result<Account, BackendError> {
+        let username = acc.username.trim().to_string();
+        if username.is_empty() {
+            return Err(BackendError::Rejected("backend.username_empty".into()))
+        }
+        let Some(old_name) = self.tokens.borrow().get(token).cloned() else {
+            return Err(BackendError::Rejected("backend.session_expired".into()))
+        };
+        let mut users = self.users.borrow_mut();
+        let Some(mut user) = users.get(&old_name).cloned() else {
+            return Err(BackendError::Rejected("backend.session_expired".into()))
+        };
+        if user.acc_id != acc.acc_id {
+            return Err(BackendError::Rejected("backend.edit_other_account".into()))
+        }
+        if username != old_name && users.contains_key(&username) {
+            return Err(BackendError::Rejected("backend.username_taken".into()))
+        }
+        // -- Rename keeps all sessions of the account valid
+        users.remove(&old_name);
+        user.username = username;
+        user.av = acc.av.to_vec();
+        for name in self.tokens.borrow_mut().values_mut().filter(|name| **name == old_name) {
+            *name = user.username.clone();
+        }
+        let account = user.account();
+        info!("local auth: updated profile of {}", user.username);
+        users.insert(user.username.clone(), user);
+        drop(users);
+        self.persist();
+        Ok(account)
+    }
+
+    /// Status line of the session account.
+    pub fn status(&self, token: &str) -> Result<Option<String>, BackendError> {
+        let username = self.tokens.borrow().get(token).cloned();
+        match username.and_then(|u| self.users.borrow().get(&u).map(|user| user.status.clone())) {
+            Some(status) => Ok(status),
+            None => Err(BackendError::Rejected("backend.session_expired".into()))
+        }
+    }
+
+    /// Status lines of the given accounts (accounts without one are left out).
+    pub fn statuses(&self, accs: &[Id]) -> HashMap<Ulid, String> {
+        self.users
+            .borrow()
+            .values()
+            .filter(|user| accs.contains(&user.acc_id))
+            .filter_map(|user| Some((user.acc_id.id, user.status.clone()?)))
+            .collect()
+    }
+
+    /// Set (or clear with blank) status line of the session account.
+    pub fn set_status(&self, token: &str, status: Option<String>) -> Result<(), BackendError> {
+        let Some(username) = self.tokens.borrow().get(token).cloned() else {
+            return Err(BackendError::Rejected("backend.session_expired".into()))
+        };
+        let mut users = self.users.borrow_mut();
+        let Some(user) = users.get_mut(&username) else {
+            return Err(BackendError::Rejected("backend.session_expired".into()))
+        };
+        user.status = status.filter(|s| !s.trim().is_empty());
+        drop(users);
+        self.persist();
+        Ok(())
+    }
+
+    /// Invalidate the session token.
+    pub fn logout(&self, token: &str) -> Result<(), BackendError> {
+        self.tokens.borrow_mut().remove(token);
+        self.persist();
+        Ok(())
+    }
+}
+
+
+#[test]
+fn local_auth_test() {
+    let server = LocalAuthServer::default();
+    let cred = Credentials { username: "karol".into(), password: "secret".into() };
+    // -- Sign up
+    assert!(server.register(&Credentials { username: " ".into(), password: "secret".into() }).is_err());
+    assert!(server.register(&Credentials { username: "karol".into(), password: "abc".into() }).is_err());
+    let first = server.register(&cred).unwrap();
+    assert!(server.register(&cred).is_err());
+    // -- Sign in
+    assert!(server.login(&Credentials { username: "karol".into(), password: "wrong".into() }).is_err());
+    assert!(server.login(&Credentials { username: "nobody".into(), password: "secret".into() }).is_err());
+    let second = server.login(&cred).unwrap();
+    assert_eq!(first.account.acc_id, second.account.acc_id);
+    assert_ne!(first.token, second.token);
+    // -- Resume and sign out
+    assert_eq!(server.resume(&second.token).unwrap().account, second.account);
+    server.logout(&second.token).unwrap();
+    assert!(server.resume(&second.token).is_err());
+    assert!(server.resume(&first.token).is_ok());
+    // -- Profile
+    let other = server.register(&Credentials { username: "konrad".into(), password: "secret".into() }).unwrap();
+    let mut acc = first.account.clone();
+    acc.username = "konrad".into();
+    assert!(server.update_profile(&first.token, acc.clone()).is_err());
+    assert!(server.update_profile(&first.token, other.account.clone()).is_err());
+    acc.username = "karol2".into();
+    let updated = server.update_profile(&first.token, acc).unwrap();
+    assert_eq!(server.resume(&first.token).unwrap().account, updated);
+    // -- Status
+    assert_eq!(server.status(&first.token).unwrap(), None);
+    server.set_status(&first.token, Some("busy".into())).unwrap();
+    assert_eq!(server.status(&first.token).unwrap().as_deref(), Some("busy"));
+    server.set_status(&first.token, Some(" ".into())).unwrap();
+    assert_eq!(server.status(&first.token).unwrap(), None);
+    assert!(server.status(&second.token).is_err());
+    server.set_status(&other.token, Some("away".into())).unwrap();
+    let statuses = server.statuses(&[updated.acc_id.clone(), other.account.acc_id.clone()]);
+    assert_eq!(statuses, HashMap::from([(other.account.acc_id.id, "away".to_string())]));
+    assert!(server.login(&Credentials { username: "karol2".into(), password: "secret".into() }).is_ok());
+    assert!(server.login(&cred).is_err());
+}
+
+#[test]
+fn password_hash_test() {
+    let first = LocalAuthServer::hash("secret").unwrap();
+    let second = LocalAuthServer::hash("secret").unwrap();
+    assert!(first.starts_with("$argon2id$"));
+    // -- Each hash has its own salt
+    assert_ne!(first, second);
+    assert!(LocalAuthServer::verify(&first, "secret"));
+    assert!(LocalAuthServer::verify(&second, "secret"));
+    assert!(!LocalAuthServer::verify(&first, "Secret"));
+    assert!(!LocalAuthServer::verify("12345", "secret"));
+}
+
+#[test]
+fn local_auth_store_test() {
+    let dir = std::env::temp_dir().join(format!("chat_floem_auth_{}", crate::util::now_ms()));
+    let path = dir.join("local_auth.json");
+    let server = LocalAuthServer::with_store(&path);
+    let session = server.register(&Credentials { username: "karol".into(), password: "secret".into() }).unwrap();
+    let mut acc = session.account.clone();
+    acc.av = Rc::new(vec![1, 2, 3]);
+    server.update_profile(&session.token, acc).unwrap();
+    server.set_status(&session.token, Some("busy".into())).unwrap();
+    // -- Profile survives restart
+    let server = LocalAuthServer::with_store(&path);
+    let resumed = server.resume(&session.token).unwrap();
+    assert_eq!(*resumed.account.av, vec![1, 2, 3]);
+    assert_eq!(server.status(&session.token).unwrap().as_deref(), Some("busy"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn stored_session_rename_test() {
+    let server = LocalAuthServer::default();
+    let auth = server.register(&Credentials { username: "stored".into(), password: "secret".into() }).unwrap();
+    let acc_id = auth.account.acc_id.id;
+    let stored = || StoredSession::load_all().into_iter().find(|s| s.acc_id == acc_id);
+    StoredSession { acc_id, username: auth.account.username.clone(), token: auth.token.clone() }.save();
+    // -- Rename keeps the single remembered session of the account
+    let mut acc = auth.account.clone();
+    acc.username = "stored2".into();
+    let acc = server.update_profile(&auth.token, acc).unwrap();
+    StoredSession::rename(&acc.acc_id.id, &acc.username);
+    assert_eq!(stored().map(|s| s.username).as_deref(), Some("stored2"));
+    StoredSession { acc_id, username: acc.username.clone(), token: auth.token.clone() }.save();
+    assert_eq!(StoredSession::load_all().iter().filter(|s| s.acc_id == acc_id).count(), 1);
+    // -- Signed out account isn't resumed on the next start
+    StoredSession::forget(&acc_id);
+    assert_eq!(stored(), None);
+}
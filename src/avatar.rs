@@ -0,0 +1,303 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::rc::Rc;
+
+use image::imageops::FilterType;
+use image::{ImageFormat, Rgb, RgbImage};
+use tracing_lite::error;
+use ulid::Ulid;
+
+use crate::cont::acc::Account;
+use crate::i18n::t_with;
+
+
+
+/// Side (in px) of every stored avatar.
+pub const AVATAR_SIZE: u32 = 128;
+
+
+thread_local! {
+    /// Generated avatars (PNG), K: (id, initials).
+    static GENERATED: RefCell<HashMap<(Ulid, String), Rc<Vec<u8>>>> = RefCell::new(HashMap::new());
+}
+
+
+/// Part of the picture chosen as an avatar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AvatarCrop {
+    /// Zoom factor (`1.0` takes the largest possible square).
+    pub zoom: f32,
+    /// Horizontal position of the square center (`0.0` left, `1.0` right).
+    pub x: f32,
+    /// Vertical position of the square center (`0.0` top, `1.0` bottom).
+    pub y: f32
+}
+
+impl Default for AvatarCrop {
+    fn default() -> Self {
+        Self { zoom: 1.0, x: 0.5, y: 0.5 }
+    }
+}
+
+impl AvatarCrop {
+    pub const MAX_ZOOM: f32 = 4.0;
+
+    /// Zoom in (or out when `step` is negative) keeping zoom in its limits.
+    pub fn zoom_by(&mut self, step: f32) {
+        self.zoom = (self.zoom + step).clamp(1.0, Self::MAX_ZOOM);
+    }
+
+    /// Move the square center by the given fraction of the picture.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.x = (self.x + dx).clamp(0.0, 1.0);
+        self.y = (self.y + dy).clamp(0.0, 1.0);
+    }
+
+    /// Square (x, y, side) to cut out of the `width` x `height` picture.  
+    /// Square always stays inside the picture.
+    pub fn rect(&self, width: u32, height: u32) -> (u32, u32, u32) {
+        let side = ((width.min(height) as f32 / self.zoom.max(1.0)).round() as u32).clamp(1, width.min(height).max(1));
+        let place = |len: u32, center: f32| {
+            let max = len.saturating_sub(side);
+            ((center.clamp(0.0, 1.0) * len as f32 - side as f32 / 2.0).round().max(0.0) as u32).min(max)
+        };
+        (place(width, self.x), place(height, self.y), side)
+    }
+}
+
+
+/// Crop the picture to a square and resize it to [AVATAR_SIZE], returning PNG bytes.
+pub fn square_avatar(data: &[u8], crop: &AvatarCrop) -> Result<Vec<u8>, String> {
+    let img = image::load_from_memory(data).map_err(|e| t_with("avatar.unsupported", &[("error", &e)]))?;
+    let (x, y, side) = crop.rect(img.width(), img.height());
+    let avatar = img
+        .crop_imm(x, y, side, side)
+        .resize_exact(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3);
+    let mut png = Vec::new();
+    avatar
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| t_with("avatar.encode_failed", &[("error", &e)]))?;
+    Ok(png)
+}
+
+
+
+// MARK: generated
+
+/// Avatar of the account, or the one generated from its initials if it has none.
+pub fn avatar_of(acc: &Account) -> Rc<Vec<u8>> {
+    match acc.av.is_empty() {
+        true => initials_avatar(&acc.acc_id.id, &acc.username),
+        false => acc.av.clone()
+    }
+}
+
+/// Cached PNG avatar with `name` initials on the color derived from `id`.
+pub fn initials_avatar(id: &Ulid, name: &str) -> Rc<Vec<u8>> {
+    let key = (*id, initials(name));
+    if let Some(png) = GENERATED.with_borrow(|cache| cache.get(&key).cloned()) {
+        return png
+    }
+    let mut png = Vec::new();
+    if let Err(e) = render_initials(&key.1, avatar_color(id), AVATAR_SIZE)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png) {
+        error!("Unable to encode generated avatar: {e}");
+    }
+    let png = Rc::new(png);
+    GENERATED.with_borrow_mut(|cache| cache.insert(key, png.clone()));
+    png
+}
+
+/// Up to two uppercase initials: of the first two words, or the first letter only.
+pub fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Background color derived from the id (same id - same color).
+pub fn avatar_color(id: &Ulid) -> [u8; 3] {
+    // -- FNV-1a, stable between runs and platforms
+    let hash = id.0.to_be_bytes().iter().fold(0xcbf29ce484222325_u64, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x100000001b3)
+    });
+    hsl_to_rgb((hash % 360) as f32, 0.55, 0.45)
+}
+
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> [u8; 3] {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r, g, b) = match h as u32 / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x)
+    };
+    [r, g, b].map(|v| ((v + m) * 255.0).round() as u8)
+}
+
+/// Draw `text` centered with white 5x7 glyphs (scaled up) on the `bg` square.
+fn render_initials(text: &str, bg: [u8; 3], size: u32) -> RgbImage {
+    let mut img = RgbImage::from_pixel(size, size, Rgb(bg));
+    let glyphs = text.chars().map(glyph).collect::<Vec<_>>();
+    if glyphs.is_empty() {
+        return img
+    }
+    let text_w = glyphs.len() as u32 * (GLYPH_W + 1) - 1;
+    let scale = (size * 45 / 100 / GLYPH_H).min(size * 70 / 100 / text_w).max(1);
+    let x0 = size.saturating_sub(text_w * scale) / 2;
+    let y0 = size.saturating_sub(GLYPH_H * scale) / 2;
+    for (n, rows) in glyphs.iter().enumerate() {
+        let gx = x0 + n as u32 * (GLYPH_W + 1) * scale;
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..GLYPH_W {
+                if bits & (1 << (GLYPH_W - 1 - col)) == 0 { continue }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let (x, y) = (gx + col * scale + dx, y0 + row as u32 * scale + dy);
+                        if x < size && y < size {
+                            img.put_pixel(x, y, Rgb([255, 255, 255]));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    img
+}
+
+
+// MARK: font
+
+const GLYPH_W: u32 = 5;
+const GLYPH_H: u32 = 7;
+
+/// 5x7 bitmap of the char (rows from top, bit 4 is the leftmost column).
+fn glyph(c: char) -> [u8; 7] {
+    // -- Fold diacritics to their base letters
+    let c = match c {
+        'Ą' => 'A', 'Ć' => 'C', 'Ę' => 'E', 'Ł' => 'L', 'Ń' => 'N',
+        'Ó' => 'O', 'Ś' => 'S', 'Ź' | 'Ż' => 'Z',
+        c => c
+    };
+    match c {
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]
+    }
+}
+
+#[test]
+fn avatar_crop_test() {
+    // -- Largest centered square
+    let crop = AvatarCrop::default();
+    assert_eq!(crop.rect(400, 200), (100, 0, 200));
+    assert_eq!(crop.rect(200, 400), (0, 100, 200));
+    // -- Square never leaves the picture
+    let mut crop = AvatarCrop { zoom: 2.0, x: 1.0, y: 0.0 };
+    assert_eq!(crop.rect(400, 200), (300, 0, 100));
+    crop.pan(-1.0, 0.5);
+    assert_eq!(crop.rect(400, 200), (0, 50, 100));
+    // -- Zoom limits
+    crop.zoom_by(10.0);
+    assert_eq!(crop.zoom, AvatarCrop::MAX_ZOOM);
+    crop.zoom_by(-10.0);
+    assert_eq!(crop.zoom, 1.0);
+    // -- Resized to the fixed square
+    let mut png = Vec::new();
+    image::RgbImage::new(300, 120)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .unwrap();
+    let avatar = image::load_from_memory(&square_avatar(&png, &AvatarCrop::default()).unwrap()).unwrap();
+    assert_eq!((avatar.width(), avatar.height()), (AVATAR_SIZE, AVATAR_SIZE));
+    assert!(square_avatar(b"not an image", &AvatarCrop::default()).is_err());
+}
+
+#[test]
+fn initials_avatar_test() {
+    assert_eq!(initials("Ada Lovelace"), "AL");
+    assert_eq!(initials("karol"), "K");
+    assert_eq!(initials("  (Miśka) z  domu  "), "MZ");
+    assert_eq!(initials(""), "");
+    // -- Same id, same color
+    let id = Ulid(0x0192_b3c4_d5e6_f708_1a2b_3c4d_5e6f_7a8b);
+    assert_eq!(avatar_color(&id), avatar_color(&id));
+    assert_eq!(avatar_color(&id), [52, 178, 155]);
+    // -- Pixels of the small render
+    let img = render_initials("AL", [0, 0, 0], 16);
+    let ascii = img.rows()
+        .map(|row| row.map(|px| if px.0 == [255, 255, 255] { '#' } else { '.' }).collect::<String>())
+        .collect::<Vec<_>>();
+    assert_eq!(ascii, [
+        "................",
+        "................",
+        "................",
+        "................",
+        "...###..#.......",
+        "..#...#.#.......",
+        "..#...#.#.......",
+        "..#####.#.......",
+        "..#...#.#.......",
+        "..#...#.#.......",
+        "..#...#.#####...",
+        "................",
+        "................",
+        "................",
+        "................",
+        "................",
+    ]);
+    // -- Cached and used only for accounts without avatar
+    let png = initials_avatar(&id, "Ada Lovelace");
+    assert!(Rc::ptr_eq(&png, &initials_avatar(&id, "ada lovelace")));
+    let decoded = image::load_from_memory(&png).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (AVATAR_SIZE, AVATAR_SIZE));
+    let mut acc = Account {
+        acc_id: crate::util::Id { tb: crate::util::Tb::Acc, id },
+        username: "Ada Lovelace".into(),
+        av: Rc::new(vec![]),
+    };
+    assert!(Rc::ptr_eq(&avatar_of(&acc), &png));
+    acc.av = Rc::new(vec![1, 2, 3]);
+    assert_eq!(*avatar_of(&acc), vec![1, 2, 3]);
+}
+
@@ -0,0 +1,431 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use chrono_lite::Datetime;
+use tracing_lite::{info, warn};
+use ulid::Ulid;
+
+use crate::auth::{AuthSession, Credentials, LocalAuthServer};
+use crate::cont::acc::{Account, Member, Presence, PresenceInfo};
+use crate::cont::room::{InviteTarget, Room, RoomInvite};
+use crate::i18n::{t, t_with};
+use crate::storage::app_path;
+use crate::util::{now_ms, Id, Tb};
+
+
+
+/// File (in the [config_dir](crate::storage::config_dir)) with accounts of the [LocalBackend].
+const LOCAL_AUTH_FILE: &str = "local_auth.json";
+/// Time (ms) after which typing notification expires.
+pub const TYPING_TIMEOUT: u64 = 5_000;
+
+
+/// Errors returned from the backend.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BackendError {
+    /// Request was rejected with the given reason (catalog key, translated on display).
+    Rejected(String),
+    /// Backend is not reachable.
+    Disconnected
+}
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackendError::Rejected(reason) => f.write_str(&t_with("backend.rejected", &[("reason", &t(reason))])),
+            BackendError::Disconnected => f.write_str(&t("backend.disconnected")),
+        }
+    }
+}
+
+
+/// Requests the UI can send to the backend.  
+/// Each call returns the state confirmed by the backend.
+pub trait Backend {
+    /// Create new account and sign in.
+    fn register(&self, cred: &Credentials) -> Result<AuthSession, BackendError>;
+    /// Exchange credentials for the session.
+    fn login(&self, cred: &Credentials) -> Result<AuthSession, BackendError>;
+    /// Exchange token of the remembered session for the session.
+    fn resume_session(&self, token: &str) -> Result<AuthSession, BackendError>;
+    /// Invalidate the session.
+    fn logout(&self, token: &str) -> Result<(), BackendError>;
+    /// Update profile (username and avatar) of the session account.
+    fn update_profile(&self, token: &str, acc: Account) -> Result<Account, BackendError>;
+    /// Status line of the session account.
+    fn status(&self, token: &str) -> Result<Option<String>, BackendError>;
+    /// Status lines of the given accounts (accounts without one are left out).
+    fn statuses(&self, accs: &[Id]) -> Result<HashMap<Ulid, String>, BackendError>;
+    /// Set (or clear with blank) status line of the session account.
+    fn set_status(&self, token: &str, status: Option<String>) -> Result<(), BackendError>;
+    /// Set presence of the account.
+    fn set_presence(&self, acc: &Id, presence: Presence) -> Result<(), BackendError>;
+    /// Presence of the given accounts.
+    fn presence(&self, accs: &[Id]) -> Result<HashMap<Ulid, PresenceInfo>, BackendError>;
+    /// Notify room members that the account is typing.
+    fn send_typing(&self, room: &Id, acc: &Id) -> Result<(), BackendError>;
+    /// Notify room members that the account stopped typing (eg. sent the msg).
+    fn stop_typing(&self, room: &Id, acc: &Id) -> Result<(), BackendError>;
+    /// Accounts typing in the room (during last [TYPING_TIMEOUT]).
+    fn typing(&self, room: &Id) -> Result<Vec<Id>, BackendError>;
+    /// Open a separate connection to the same backend (used by another signed in account).
+    fn new_connection(&self) -> Rc<dyn Backend>;
+    /// Create a new room and return it as stored by the backend.
+    fn create_room(&self, room: Room) -> Result<Room, BackendError>;
+    /// Update room data (name, description, avatar, members and owner).
+    fn update_room(&self, room: Room) -> Result<Room, BackendError>;
+    /// Delete the room with all its msgs.
+    fn delete_room(&self, room: &Id) -> Result<(), BackendError>;
+    /// Remove account from the room members.
+    fn leave_room(&self, room: &Id, acc: &Id) -> Result<(), BackendError>;
+    /// Create a new room invite.
+    fn create_invite(&self, invite: RoomInvite) -> Result<RoomInvite, BackendError>;
+    /// Revoke (delete) not yet used room invite.
+    fn revoke_invite(&self, invite: &Id) -> Result<(), BackendError>;
+    /// Active invites of the room.
+    fn room_invites(&self, room: &Id) -> Result<Vec<RoomInvite>, BackendError>;
+    /// Active invites addressed to the account.
+    fn pending_invites(&self, acc: &Id) -> Result<Vec<RoomInvite>, BackendError>;
+    /// Accept invite (by id or link token), adding account as a room member.
+    fn accept_invite(&self, invite: InviteRef, acc: &Id) -> Result<Room, BackendError>;
+    /// Decline invite addressed to the account.
+    fn decline_invite(&self, invite: &Id, acc: &Id) -> Result<(), BackendError>;
+}
+
+
+/// Reference used to accept the [RoomInvite].
+#[derive(Clone, Debug, PartialEq)]
+pub enum InviteRef {
+    Id(Id),
+    Token(String)
+}
+
+
+/// Stand-in backend living on the UI thread (until the real one is connected).  
+/// Connections made with [Backend::new_connection] share its state.
+#[derive(Clone, Debug, Default)]
+pub struct LocalBackend {
+    auth: Rc<LocalAuthServer>,
+    rooms: Rc<RefCell<HashMap<Ulid, Room>>>,
+    invites: Rc<RefCell<HashMap<Ulid, RoomInvite>>>,
+    presence: Rc<RefCell<HashMap<Ulid, PresenceInfo>>>,
+    /// K: room id, V: (account id, last typing notification time)
+    typing: Rc<RefCell<HashMap<Ulid, HashMap<Ulid, u64>>>>
+}
+
+impl LocalBackend {
+    /// Create backend with accounts kept between runs.
+    pub fn new() -> Self {
+        Self {
+            auth: Rc::new(LocalAuthServer::with_store(app_path(LOCAL_AUTH_FILE))),
+            ..Self::default()
+        }
+    }
+
+    /// Drop expired invites and keep `active_invites` of the rooms in sync.
+    fn sync_invites(&self) {
+        let mut invites = self.invites.borrow_mut();
+        invites.retain(|_, inv| !inv.is_expired());
+        for room in self.rooms.borrow_mut().values_mut() {
+            let active = invites
+                .values()
+                .filter(|inv| inv.room_id == room.room_id)
+                .cloned()
+                .collect::<Vec<_>>();
+            room.active_invites = (!active.is_empty()).then_some(active);
+        }
+    }
+}
+
+impl Backend for LocalBackend {
+    fn register(&self, cred: &Credentials) -> Result<AuthSession, BackendError> {
+        self.auth.register(cred)
+    }
+
+    fn login(&self, cred: &Credentials) -> Result<AuthSession, BackendError> {
+        self.auth.login(cred)
+    }
+
+    fn resume_session(&self, token: &str) -> Result<AuthSession, BackendError> {
+        self.auth.resume(token)
+    }
+
+    fn logout(&self, token: &str) -> Result<(), BackendError> {
+        self.auth.logout(token)
+    }
+
+    fn update_profile(&self, token: &str, acc: Account) -> Result<Account, BackendError> {
+        self.auth.update_profile(token, acc)
+    }
+
+    fn status(&self, token: &str) -> Result<Option<String>, BackendError> {
+        self.auth.status(token)
+    }
+
+    fn statuses(&self, accs: &[Id]) -> Result<HashMap<Ulid, String>, BackendError> {
+        Ok(self.auth.statuses(accs))
+    }
+
+    fn set_status(&self, token: &str, status: Option<String>) -> Result<(), BackendError> {
+        self.auth.set_status(token, status)
+    }
+
+    fn set_presence(&self, acc: &Id, presence: Presence) -> Result<(), BackendError> {
+        info!("->> local backend: set_presence({acc}, {presence:?})");
+        self.presence.borrow_mut().insert(acc.id, PresenceInfo { presence, last_seen: now_ms() });
+        // -- Keep `active_members` of the rooms in sync
+        for room in self.rooms.borrow_mut().values_mut() {
+            if !room.members.iter().any(|m| m.member_id == *acc) { continue }
+            room.active_members.retain(|m| m != acc);
+            if presence != Presence::Offline {
+                room.active_members.push(acc.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn presence(&self, accs: &[Id]) -> Result<HashMap<Ulid, PresenceInfo>, BackendError> {
+        let presence = self.presence.borrow();
+        Ok(accs
+            .iter()
+            .map(|acc| (acc.id, presence.get(&acc.id).copied().unwrap_or_default()))
+            .collect()
+        )
+    }
+
+    fn send_typing(&self, room: &Id, acc: &Id) -> Result<(), BackendError> {
+        self.typing.borrow_mut().entry(room.id).or_default().insert(acc.id, now_ms());
+        Ok(())
+    }
+
+    fn stop_typing(&self, room: &Id, acc: &Id) -> Result<(), BackendError> {
+        if let Some(accs) = self.typing.borrow_mut().get_mut(&room.id) {
+            accs.remove(&acc.id);
+        }
+        Ok(())
+    }
+
+    fn typing(&self, room: &Id) -> Result<Vec<Id>, BackendError> {
+        let now = now_ms();
+        let mut typing = self.typing.borrow_mut();
+        let Some(accs) = typing.get_mut(&room.id) else { return Ok(Vec::new()) };
+        accs.retain(|_, at| now.saturating_sub(*at) < TYPING_TIMEOUT);
+        Ok(accs.keys().map(|id| Id { tb: Tb::Acc, id: *id }).collect())
+    }
+
+    fn new_connection(&self) -> Rc<dyn Backend> {
+        Rc::new(self.clone())
+    }
+
+    fn create_room(&self, room: Room) -> Result<Room, BackendError> {
+        info!("->> local backend: create_room({})", room.room_id);
+        if room.name.trim().is_empty() {
+            warn!("local backend: room without a name");
+            return Err(BackendError::Rejected("backend.room_name_empty".into()))
+        }
+        self.rooms.borrow_mut().insert(room.room_id.id, room.clone());
+        Ok(room)
+    }
+
+    fn update_room(&self, mut room: Room) -> Result<Room, BackendError> {
+        info!("->> local backend: update_room({})", room.room_id);
+        if room.name.trim().is_empty() {
+            return Err(BackendError::Rejected("backend.room_name_empty".into()))
+        }
+        if !room.members.iter().any(|m| m.member_id == room.owner) {
+            return Err(BackendError::Rejected("backend.owner_not_member".into()))
+        }
+        let mut rooms = self.rooms.borrow_mut();
+        // -- Keep data owned by the backend
+        if let Some(stored) = rooms.get(&room.room_id.id) {
+            room.created = stored.created.clone();
+            for member in room.members.iter_mut() {
+                if let Some(old) = stored.members.iter().find(|m| m.member_id == member.member_id) {
+                    member.since = old.since.clone();
+                }
+            }
+        }
+        rooms.insert(room.room_id.id, room.clone());
+        Ok(room)
+    }
+
+    fn delete_room(&self, room: &Id) -> Result<(), BackendError> {
+        info!("->> local backend: delete_room({room})");
+        self.rooms.borrow_mut().remove(&room.id);
+        // -- Invites can't lead to the deleted room any more
+        self.invites.borrow_mut().retain(|_, inv| inv.room_id != *room);
+        Ok(())
+    }
+
+    fn leave_room(&self, room: &Id, acc: &Id) -> Result<(), BackendError> {
+        info!("->> local backend: leave_room({room}, {acc})");
+        let mut rooms = self.rooms.borrow_mut();
+        if let Some(stored) = rooms.get_mut(&room.id) {
+            if stored.owner == *acc {
+                return Err(BackendError::Rejected("backend.owner_cant_leave".into()))
+            }
+            stored.members.retain(|m| m.member_id != *acc);
+        }
+        Ok(())
+    }
+
+    fn create_invite(&self, invite: RoomInvite) -> Result<RoomInvite, BackendError> {
+        info!("->> local backend: create_invite({})", invite.invite_id);
+        {
+            let rooms = self.rooms.borrow();
+            let Some(room) = rooms.get(&invite.room_id.id) else {
+                return Err(BackendError::Rejected("backend.unknown_room".into()))
+            };
+            if room.owner != invite.inviter {
+                return Err(BackendError::Rejected("backend.owner_only_invite".into()))
+            }
+            if let InviteTarget::Account(acc) = &invite.target {
+                if room.members.iter().any(|m| m.member_id == *acc) {
+                    return Err(BackendError::Rejected("backend.already_member".into()))
+                }
+            }
+        }
+        self.invites.borrow_mut().insert(invite.invite_id.id, invite.clone());
+        self.sync_invites();
+        Ok(invite)
+    }
+
+    fn revoke_invite(&self, invite: &Id) -> Result<(), BackendError> {
+        info!("->> local backend: revoke_invite({invite})");
+        self.invites.borrow_mut().remove(&invite.id);
+        self.sync_invites();
+        Ok(())
+    }
+
+    fn room_invites(&self, room: &Id) -> Result<Vec<RoomInvite>, BackendError> {
+        self.sync_invites();
+        Ok(self.invites.borrow().values().filter(|inv| inv.room_id == *room).cloned().collect())
+    }
+
+    fn pending_invites(&self, acc: &Id) -> Result<Vec<RoomInvite>, BackendError> {
+        self.sync_invites();
+        Ok(self.invites
+            .borrow()
+            .values()
+            .filter(|inv| matches!(&inv.target, InviteTarget::Account(invitee) if invitee == acc))
+            .cloned()
+            .collect()
+        )
+    }
+
+    fn accept_invite(&self, invite: InviteRef, acc: &Id) -> Result<Room, BackendError> {
+        info!("->> local backend: accept_invite({invite:?}, {acc})");
+        self.sync_invites();
+        let found = self.invites.borrow().values().find(|inv| match (&invite, &inv.target) {
+            (InviteRef::Id(id), _) => inv.invite_id == *id,
+            (InviteRef::Token(token), InviteTarget::Link(link)) => token == link,
+            (InviteRef::Token(_), InviteTarget::Account(_)) => false
+        }).cloned();
+        let Some(found) = found else {
+            return Err(BackendError::Rejected("backend.invite_not_found".into()))
+        };
+        if !found.is_for(acc) {
+            return Err(BackendError::Rejected("backend.invite_other_account".into()))
+        }
+        let room = {
+            let mut rooms = self.rooms.borrow_mut();
+            let Some(room) = rooms.get_mut(&found.room_id.id) else {
+                return Err(BackendError::Rejected("backend.room_gone".into()))
+            };
+            if !room.members.iter().any(|m| m.member_id == *acc) {
+                room.members.push(Member { member_id: acc.clone(), since: Datetime::default() });
+            }
+            room.clone()
+        };
+        // -- Link invites stay valid until revoked or expired
+        if let InviteTarget::Account(_) = found.target {
+            self.invites.borrow_mut().remove(&found.invite_id.id);
+        }
+        self.sync_invites();
+        Ok(room)
+    }
+
+    fn decline_invite(&self, invite: &Id, acc: &Id) -> Result<(), BackendError> {
+        info!("->> local backend: decline_invite({invite}, {acc})");
+        let mut invites = self.invites.borrow_mut();
+        match invites.get(&invite.id) {
+            Some(inv) if inv.is_for(acc) => { invites.remove(&invite.id); },
+            Some(_) => return Err(BackendError::Rejected("backend.invite_other_account".into())),
+            None => warn!("local backend: invite {invite} already gone")
+        }
+        drop(invites);
+        self.sync_invites();
+        Ok(())
+    }
+}
+
+
+#[test]
+fn typing_test() {
+    let backend = LocalBackend::default();
+    let (room, acc) = (Id::new(Tb::Room), Id::new(Tb::Acc));
+    backend.send_typing(&room, &acc).unwrap();
+    assert_eq!(backend.typing(&room).unwrap(), [acc.clone()]);
+    backend.stop_typing(&room, &acc).unwrap();
+    assert!(backend.typing(&room).unwrap().is_empty());
+}
+
+#[test]
+fn invite_accept_decline_test() {
+    let backend = LocalBackend::default();
+    let owner = Id::new(Tb::Acc);
+    let guest = Id::new(Tb::Acc);
+    let room = Room {
+        room_id: Id::new(Tb::Room),
+        name: "Room".into(),
+        members: vec![Member { member_id: owner.clone(), since: Datetime::default() }],
+        active_members: vec![],
+        active_invites: None,
+        owner: owner.clone(),
+        created: Datetime::default(),
+        description: None,
+        avatar: None
+    };
+    let room = backend.create_room(room).unwrap();
+    let invite = |target: InviteTarget, expires: u64| RoomInvite {
+        invite_id: Id::new(Tb::Inv),
+        room_id: room.room_id.clone(),
+        room_name: room.name.clone(),
+        inviter: owner.clone(),
+        target,
+        created: Datetime::default(),
+        expires
+    };
+    // -- Only owner can invite
+    let mut by_guest = invite(InviteTarget::Account(guest.clone()), now_ms() + 60_000);
+    by_guest.inviter = guest.clone();
+    assert!(backend.create_invite(by_guest).is_err());
+    // -- Decline
+    let inv = backend.create_invite(invite(InviteTarget::Account(guest.clone()), now_ms() + 60_000)).unwrap();
+    assert_eq!(backend.pending_invites(&guest).unwrap().len(), 1);
+    assert!(backend.decline_invite(&inv.invite_id, &owner).is_err());
+    backend.decline_invite(&inv.invite_id, &guest).unwrap();
+    assert!(backend.pending_invites(&guest).unwrap().is_empty());
+    // -- Expired
+    let inv = backend.create_invite(invite(InviteTarget::Account(guest.clone()), now_ms() - 1)).unwrap();
+    assert!(backend.accept_invite(InviteRef::Id(inv.invite_id), &guest).is_err());
+    // -- Accept
+    let inv = backend.create_invite(invite(InviteTarget::Account(guest.clone()), now_ms() + 60_000)).unwrap();
+    assert_eq!(backend.room_invites(&room.room_id).unwrap().len(), 1);
+    let joined = backend.accept_invite(InviteRef::Id(inv.invite_id), &guest).unwrap();
+    assert!(joined.members.iter().any(|m| m.member_id == guest));
+    assert!(backend.room_invites(&room.room_id).unwrap().is_empty());
+    // -- Link
+    let other = Id::new(Tb::Acc);
+    let token = String::from("secret-token");
+    backend.create_invite(invite(InviteTarget::Link(token.clone()), now_ms() + 60_000)).unwrap();
+    assert!(backend.accept_invite(InviteRef::Token("wrong".into()), &other).is_err());
+    let joined = backend.accept_invite(InviteRef::Token(token), &other).unwrap();
+    assert_eq!(joined.members.len(), 3);
+    // -- Deleted room takes its pending invites along
+    backend.create_invite(invite(InviteTarget::Account(Id::new(Tb::Acc)), now_ms() + 60_000)).unwrap();
+    backend.delete_room(&room.room_id).unwrap();
+    assert!(backend.invites.borrow().is_empty());
+}
@@ -360,6 +360,15 @@ impl RoomMsgChunks {
         }
     }
 
+    /// Returns reference to the last Msg matching the `pred`.
+    pub fn last_msg_matching(&self, pred: impl Fn(&MsgViewData) -> bool) -> Option<&MsgViewData> {
+        self.chunks
+            .iter()
+            .rev()
+            .flat_map(|chunk| chunk.msgs.iter().rev())
+            .find(|msg| pred(msg))
+    }
+
     /// Updates Self with given [MsgViewData].
     pub fn update_one(&mut self, msg: &MsgViewData) {
         debug!("fn: update_one");
@@ -385,7 +394,7 @@ impl RoomMsgChunks {
                 *old = msg.clone();
                 debug!("updated chunk with msg");
                 trace!("old: {}", old.msg.text.current);
-                trace!("new: {}", chunk.msgs.get(15).unwrap().msg.text.current);
+                trace!("new: {}", msg.msg.text.current);
             });
     }
 
@@ -1,15 +1,19 @@
 use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::rc::Rc;
 
 use im::{vector, Vector};
 use tracing_lite::debug;
 use ulid::Ulid;
 
-use crate::util::{Id, Tb};
+use crate::config::Privacy;
+use crate::util::{day_from_ms, Id, Tb};
 use crate::view_data::msg::MsgViewData;
 use super::RoomMsgChunks;
 
 
+/// Max time gap (in ms) between two msgs of the same author to keep them in one group.
+pub const GROUP_WINDOW_MS: u64 = 5 * 60 * 1000;
 
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -213,6 +217,30 @@ impl DisplayChunks {
     }
 
 
+    /// Get visible msgs with their index and [MsgLayout].
+    /// Layout is calculated on each call from the current vector state,
+    /// so it stays valid after appends, older chunk loads, edits and removals.  
+    /// Msgs and reactions of blocked authors are skipped, msgs of muted ones are collapsed.
+    pub fn visible_with_layout(&self, privacy: &Privacy) -> Vec<(usize, MsgLayout, MsgViewData)> {
+        let start = match *self.status.borrow() {
+            DisplayStatus::AllVisible => 0,
+            DisplayStatus::PartiallyHidden(idx, _) => idx as usize
+        };
+        let mut prev = None;
+        self.vec
+            .iter()
+            .enumerate()
+            .skip(start)
+            .filter(|(_, msg)| !privacy.is_blocked(&msg.msg.author.id))
+            .map(|(idx, msg)| {
+                let mut layout = MsgLayout::new(prev, msg);
+                layout.collapsed = privacy.is_muted(&msg.msg.author.id);
+                prev = Some(msg);
+                (idx, layout, without_blocked_reactions(msg, privacy))
+            })
+            .collect()
+    }
+
     pub fn get_visible_indicies(&self) -> Vec<usize> {
         match *self.status.borrow() {
             DisplayStatus::AllVisible => {
@@ -245,6 +273,71 @@ impl IntoIterator for DisplayChunks {
     }
 }
 
+// MARK: MsgLayout
+
+/// Describes how msg should be drawn in relation to the msg above it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MsgLayout {
+    /// Day (since unix epoch) for which separator is drawn above the msg.
+    pub day_separator: Option<u64>,
+    /// Show author name and avatar (first msg in the group).
+    pub show_header: bool,
+    /// Author is muted, so msg is drawn as a "hidden message" row.
+    pub collapsed: bool
+}
+
+impl Default for MsgLayout {
+    /// Standalone msg: with header and without separator.
+    fn default() -> Self {
+        Self {
+            day_separator: None,
+            show_header: true,
+            collapsed: false
+        }
+    }
+}
+
+impl MsgLayout {
+    /// Calculate layout for the `msg` based on the previous displayed msg.
+    pub fn new(prev: Option<&MsgViewData>, msg: &MsgViewData) -> Self {
+        let msg_ts = msg.ulid().timestamp_ms();
+        let day = day_from_ms(msg_ts);
+        match prev {
+            None => Self {
+                day_separator: Some(day),
+                show_header: true,
+                collapsed: false
+            },
+            Some(prev) => {
+                let prev_ts = prev.ulid().timestamp_ms();
+                let new_day = day_from_ms(prev_ts) != day;
+                let same_author = prev.msg.author == msg.msg.author;
+                let in_window = msg_ts.saturating_sub(prev_ts) <= GROUP_WINDOW_MS;
+                Self {
+                    day_separator: new_day.then_some(day),
+                    show_header: new_day || !same_author || !in_window,
+                    collapsed: false
+                }
+            }
+        }
+    }
+}
+
+/// Copy of the msg without reactions of blocked accounts (same msg if there is none).
+fn without_blocked_reactions(msg: &MsgViewData, privacy: &Privacy) -> MsgViewData {
+    let has_blocked = msg.msg.reactions
+        .as_ref()
+        .is_some_and(|r| r.iter().any(|r| privacy.is_blocked(&r.author.id)));
+    if !has_blocked {
+        return msg.clone()
+    }
+    let mut filtered = msg.msg.as_ref().clone();
+    if let Some(reactions) = filtered.reactions.as_mut() {
+        reactions.retain(|r| !privacy.is_blocked(&r.author.id));
+    }
+    MsgViewData { msg: Rc::new(filtered), ..msg.clone() }
+}
+
 // /// Chunk load cases.
 // #[derive(Clone, Debug)]
 // pub enum ChunkLoadCase {
@@ -415,4 +508,117 @@ fn display_append_older_chunk_test() {
     assert_eq!(display.vec.front().unwrap().ulid(), msg_vec[0].ulid());
     assert_eq!(display_alt.total_stored, 62);
     assert_eq!(display_alt.vec.front().unwrap().ulid(), msg_vec[0].ulid());
-}
\ No newline at end of file
+}
+
+#[test]
+fn display_layout_test() {
+    let act_room = Id::new(Tb::Room);
+    let acc = crate::cont::acc::Account {
+        acc_id: Id::new(Tb::Acc),
+        username: "Karol".into(),
+        av: std::rc::Rc::new(vec![]),
+    };
+    let other = crate::cont::acc::Account {
+        acc_id: Id::new(Tb::Acc),
+        username: "Konrad".into(),
+        av: std::rc::Rc::new(vec![]),
+    };
+    // -- Msgs: 2 from yesterday, 2 from today (+ 1 outside the window) and 1 from other author
+    let noon = crate::util::today() * 86_400_000 + 43_200_000;
+    let now = (noon as i64 - crate::util::local_offset_ms(noon)) as u64; // Today's local noon
+    let stamps = [
+        now - 86_400_000 - 2_000,
+        now - 86_400_000 - 1_000,
+        now - GROUP_WINDOW_MS - 2_000,
+        now - 1_000,
+        now - 500,
+        now
+    ];
+    let mut msg_vec = Vec::new();
+    for (n, ts) in stamps.into_iter().enumerate() {
+        let author = if n == 4 { &other } else { &acc };
+        let mut msg = MsgViewData::new_from_click(act_room.clone(), author);
+        msg.id.id = Ulid::from_parts(ts, n as u128);
+        msg_vec.push(msg);
+    }
+    let mut display = DisplayChunks::new();
+    // ------------------
+    // APPEND MANY
+    display.append_many(&msg_vec[2..]);
+    let layouts = display.visible_with_layout(&Privacy::default()).into_iter().map(|(_, l, _)| l).collect::<Vec<_>>();
+    assert!(layouts[0].day_separator.is_some() && layouts[0].show_header);
+    assert_eq!(layouts[1], MsgLayout { day_separator: None, show_header: true, collapsed: false });
+    assert_eq!(layouts[2], MsgLayout { day_separator: None, show_header: true, collapsed: false });
+    assert_eq!(layouts[3], MsgLayout { day_separator: None, show_header: true, collapsed: false });
+
+    // PREPEND OLDER
+    display.append_older_chunk(&msg_vec[..2]);
+    let layouts = display.visible_with_layout(&Privacy::default()).into_iter().map(|(_, l, _)| l).collect::<Vec<_>>();
+    assert!(layouts[0].day_separator.is_some() && layouts[0].show_header);
+    assert_eq!(layouts[1], MsgLayout { day_separator: None, show_header: false, collapsed: false });
+    assert!(layouts[2].day_separator.is_some() && layouts[2].show_header);
+
+    // MSG EDITED
+    display.msg_edited(msg_vec[1].clone());
+    let layouts = display.visible_with_layout(&Privacy::default()).into_iter().map(|(_, l, _)| l).collect::<Vec<_>>();
+    assert_eq!(layouts[1], MsgLayout { day_separator: None, show_header: false, collapsed: false });
+
+    // MSG REMOVED (other author in the middle - groups join)
+    display.msg_removed(msg_vec[4].ulid());
+    let layouts = display.visible_with_layout(&Privacy::default()).into_iter().map(|(_, l, _)| l).collect::<Vec<_>>();
+    assert_eq!(layouts.len(), 5);
+    assert_eq!(layouts[4], MsgLayout { day_separator: None, show_header: false, collapsed: false });
+}
+
+#[test]
+fn display_privacy_test() {
+    let act_room = Id::new(Tb::Room);
+    let [karol, konrad, mama] = ["Karol", "Konrad", "Mama"].map(|name| crate::cont::acc::Account {
+        acc_id: Id::new(Tb::Acc),
+        username: name.into(),
+        av: std::rc::Rc::new(vec![]),
+    });
+    let now = crate::util::now_ms();
+    let mut msg_vec = Vec::new();
+    for (n, author) in [&karol, &konrad, &karol, &mama].into_iter().enumerate() {
+        let mut msg = MsgViewData::new_from_click(act_room.clone(), author);
+        msg.id.id = Ulid::from_parts(now + n as u64, n as u128);
+        msg_vec.push(msg);
+    }
+    // -- Reaction of the blocked account on the first msg
+    let mut msg = msg_vec[0].msg.as_ref().clone();
+    msg.reactions = Some(["👍", "👎"].into_iter().zip([&mama, &konrad]).map(|(emoji, acc)| crate::cont::msg::Reaction {
+        rea_id: Id::new(Tb::Rea),
+        author: acc.acc_id.clone(),
+        grandparent_id: None,
+        parent_id: msg.msg_id.clone(),
+        room_id: act_room.clone(),
+        emoji: emoji.chars().next().unwrap(),
+        created: chrono_lite::Datetime::default(),
+        delivered_to_all: true,
+        viewed_by_all: true
+    }).collect());
+    msg_vec[0].msg = Rc::new(msg);
+    let mut display = DisplayChunks::new();
+    display.append_many(&msg_vec);
+    // -- Nothing filtered
+    let visible = display.visible_with_layout(&Privacy::default());
+    assert_eq!(visible.len(), 4);
+    assert!(visible.iter().all(|(_, l, _)| !l.collapsed));
+    // -- Blocked: msgs and reactions gone, groups join around them
+    let mut privacy = Privacy::default();
+    privacy.toggle_mute(konrad.acc_id.id);
+    privacy.toggle_block(konrad.acc_id.id);
+    privacy.toggle_mute(konrad.acc_id.id);
+    assert!(privacy.is_blocked(&konrad.acc_id.id) && !privacy.is_muted(&konrad.acc_id.id));
+    let visible = display.visible_with_layout(&privacy);
+    assert_eq!(visible.iter().map(|(idx, _, _)| *idx).collect::<Vec<_>>(), [0, 2, 3]);
+    assert!(!visible[1].1.show_header);
+    assert_eq!(visible[0].2.msg.reactions.as_ref().unwrap().len(), 1);
+    assert_eq!(display.vec[0].msg.reactions.as_ref().unwrap().len(), 2);
+    // -- Muted: still there, but collapsed
+    privacy.toggle_mute(mama.acc_id.id);
+    let visible = display.visible_with_layout(&privacy);
+    assert_eq!(visible.len(), 3);
+    assert!(visible[2].1.collapsed && !visible[0].1.collapsed);
+}
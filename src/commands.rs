@@ -0,0 +1,246 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use floem::action::{inspect, set_window_scale};
+use floem::menu::{Menu, MenuItem};
+use floem::prelude::*;
+use floem::reactive::use_context;
+use tracing_lite::{debug, warn};
+
+use crate::config::{ChatConfig, KeyBinding, MAX_SCALE, MIN_SCALE};
+use crate::i18n::t;
+use crate::view_data::session::APP;
+use crate::view_data::MsgEvent;
+use crate::views::dialogs::Dialog;
+use crate::views::msgs::RoomMsgUpt;
+use crate::views::panes::Panes;
+use crate::views::toolbar::{add_test_account, add_test_msgs};
+
+
+thread_local! {
+    /// Registered commands, in the order they are listed in menus and palette.
+    static COMMANDS: RefCell<Vec<Command>> = const { RefCell::new(Vec::new()) };
+}
+
+
+/// Toolbar menu listing the command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuGroup {
+    New,
+    Edit
+}
+
+/// Action which can be run from the menus, the palette or by its key binding.
+#[derive(Clone)]
+pub struct Command {
+    /// Stable name, eg. `room.new`.
+    pub id: &'static str,
+    /// Catalog key of the title (see [Command::title]).
+    pub title_key: &'static str,
+    /// Menu with the section in it (sections are separated).
+    pub menu: Option<(MenuGroup, u8)>,
+    pub key: Option<KeyBinding>,
+    handler: Rc<dyn Fn()>
+}
+
+impl Command {
+    pub fn new(id: &'static str, title_key: &'static str, handler: impl Fn() + 'static) -> Self {
+        Self { id, title_key, menu: None, key: None, handler: Rc::new(handler) }
+    }
+
+    /// Title in the current language.
+    pub fn title(&self) -> String {
+        t(self.title_key)
+    }
+
+    pub fn key(mut self, key: KeyBinding) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    pub fn menu(mut self, menu: MenuGroup, section: u8) -> Self {
+        self.menu = Some((menu, section));
+        self
+    }
+
+    pub fn run(&self) {
+        debug!("fn: Command::run: {}", self.id);
+        (self.handler)()
+    }
+}
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Command")
+            .field("id", &self.id)
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+
+/// Add the command (replaces the one with the same id).
+pub fn register(cmd: Command) {
+    COMMANDS.with_borrow_mut(|cmds| match cmds.iter_mut().find(|c| c.id == cmd.id) {
+        Some(old) => *old = cmd,
+        None => cmds.push(cmd)
+    })
+}
+
+/// All registered commands.
+pub fn commands() -> Vec<Command> {
+    COMMANDS.with_borrow(|cmds| cmds.clone())
+}
+
+/// Run the command with given id, returns `false` if there is no such command.
+pub fn run_command(id: &str) -> bool {
+    // -- Cloned out, so handler can use the registry
+    let cmd = COMMANDS.with_borrow(|cmds| cmds.iter().find(|c| c.id == id).cloned());
+    match cmd {
+        Some(cmd) => { cmd.run(); true },
+        None => { warn!("Unknown command: {id}"); false }
+    }
+}
+
+/// Run the command bound to the pressed key, returns `false` if none is.
+pub fn run_key(pressed: &KeyBinding) -> bool {
+    let cmd = COMMANDS.with_borrow(|cmds| cmds.iter().find(|c| c.key.as_ref() == Some(pressed)).cloned());
+    cmd.inspect(Command::run).is_some()
+}
+
+/// Toolbar menu made of the commands of the `group`.
+pub fn menu_of(group: MenuGroup) -> Menu {
+    let mut listed: Vec<_> = commands()
+        .into_iter()
+        .filter_map(|cmd| cmd.menu.filter(|(g, _)| *g == group).map(|(_, section)| (section, cmd)))
+        .collect();
+    listed.sort_by_key(|(section, _)| *section);
+    let mut menu = Menu::new("");
+    let mut last_section = None;
+    for (section, cmd) in listed {
+        if last_section.is_some_and(|last| last != section) {
+            menu = menu.separator();
+        }
+        last_section = Some(section);
+        let title = match &cmd.key {
+            Some(key) => format!("{}\t{key}", cmd.title()),
+            None => cmd.title()
+        };
+        menu = menu.entry(MenuItem::new(title).action(move || cmd.run()));
+    }
+    menu
+}
+
+
+// MARK: Defaults
+
+/// Register the commands of the main view (needs the contexts provided in `main` and the [Panes]).
+pub fn register_default_commands() {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let panes = use_context::<Panes>().unwrap();
+    let msg_event = use_context::<RwSignal<MsgEvent>>().unwrap();
+    let ctrl = |key: &str| KeyBinding::new(key, true, false, false);
+
+    register(Command::new("palette.open", "cmd.palette", move || dialog.set(Dialog::Palette))
+        .key(ctrl("k")));
+    register(Command::new("app.settings", "cmd.settings", move || dialog.set(Dialog::Settings))
+        .key(ctrl(",")));
+    // -- New
+    register(Command::new("msg.new_test", "cmd.new_test_msg", move || add_test_msgs(msg_event, 1))
+        .menu(MenuGroup::New, 0));
+    register(Command::new("msg.new_tests", "cmd.new_test_msgs", move || add_test_msgs(msg_event, 40))
+        .menu(MenuGroup::New, 0));
+    register(Command::new("room.new", "cmd.new_room", move || dialog.set(Dialog::NewRoom(None)))
+        .menu(MenuGroup::New, 0));
+    register(Command::new("account.new_test", "cmd.new_test_account", add_test_account)
+        .menu(MenuGroup::New, 1));
+    // -- Edit
+    register(Command::new("account.profile", "cmd.edit_profile", move || dialog.set(Dialog::Profile))
+        .menu(MenuGroup::Edit, 0));
+    register(Command::new("account.privacy", "cmd.privacy", move || dialog.set(Dialog::Privacy))
+        .menu(MenuGroup::Edit, 0));
+    register(Command::new("room.settings", "cmd.room_settings", move || {
+        if let Some(active) = APP.with(|app| app.active_room.get_untracked()) {
+            dialog.set(Dialog::RoomSettings(active.id));
+        }
+    }).menu(MenuGroup::Edit, 1));
+    // -- Rooms and accounts
+    register(Command::new("room.invitations", "cmd.invitations", move || dialog.set(Dialog::Invitations)));
+    register(Command::new("room.load_more", "cmd.load_more", || APP.with(|app| {
+        if let Some(ar) = app.active_room.get_untracked() {
+            app.rooms_tabs.with_untracked(|rt| {
+                if let Some(act_tab) = rt.get(&ar.id) {
+                    act_tab.2.set(RoomMsgUpt::LoadMore);
+                }
+            });
+        }
+    })));
+    register(Command::new("account.add", "cmd.add_account", move || dialog.set(Dialog::AddAccount)));
+    register(Command::new("account.sign_out", "cmd.sign_out", || APP.with(|app| app.sign_out())));
+    // -- Window
+    register(Command::new("view.toggle_sidebar", "cmd.toggle_sidebar", move || panes.toggle_sidebar())
+        .key(ctrl("b")));
+    register(Command::new("view.zoom_in", "cmd.zoom_in", move || {
+        config.update(|cf| { cf.scale = (cf.scale * 1.1).min(MAX_SCALE); set_window_scale(cf.scale); });
+    }).key(ctrl("=")));
+    register(Command::new("view.zoom_out", "cmd.zoom_out", move || {
+        config.update(|cf| { cf.scale = (cf.scale / 1.1).max(MIN_SCALE); set_window_scale(cf.scale); });
+    }).key(ctrl("-")));
+    register(Command::new("view.zoom_reset", "cmd.zoom_reset", move || {
+        config.update(|cf| { cf.scale = 1.; set_window_scale(cf.scale); });
+    }).key(ctrl("0")));
+    register(Command::new("dev.inspector", "cmd.inspector", inspect)
+        .key(KeyBinding::key("F11")));
+}
+
+
+// MARK: Search
+
+/// Fuzzy match of the `query` chars (in order, ignoring case) in the `text`.
+/// Higher score is better: consecutive chars and word starts are preferred.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+    for q in query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|c| *c == q)?;
+        let word_start = found == 0 || !text[found - 1].is_alphanumeric();
+        score += match last_match {
+            Some(last) if last + 1 == found => 5,
+            _ if word_start => 3,
+            _ => 1
+        };
+        // -- Gaps between the matched chars cost a bit
+        score -= last_match.map_or(found, |last| found - last - 1).min(3) as i32;
+        last_match = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// Items with titles matching the query, best first (all of them for an empty query).
+pub fn fuzzy_filter<T>(query: &str, items: impl IntoIterator<Item = (String, T)>) -> Vec<(String, T)> {
+    let mut matched: Vec<_> = items
+        .into_iter()
+        .filter_map(|(title, item)| fuzzy_score(query, &title).map(|score| (score, title, item)))
+        .collect();
+    // -- Stable sort keeps the original order of equal scores
+    matched.sort_by_key(|(score, ..)| std::cmp::Reverse(*score));
+    matched.into_iter().map(|(_, title, item)| (title, item)).collect()
+}
+
+
+#[test]
+fn fuzzy_test() {
+    assert_eq!(fuzzy_score("", "anything"), Some(0));
+    assert_eq!(fuzzy_score("xyz", "New room"), None);
+    assert_eq!(fuzzy_score("mooR", "New room"), None);
+    assert!(fuzzy_score("nr", "New room").unwrap() > fuzzy_score("nr", "Sign out and return").unwrap());
+    assert!(fuzzy_score("room", "New room").unwrap() > fuzzy_score("room", "Zoom out rather").unwrap_or(-100));
+    let items = ["Zoom out", "Room settings", "New room"].map(|t| (t.to_string(), t));
+    let found: Vec<_> = fuzzy_filter("room", items).into_iter().map(|(_, t)| t).collect();
+    assert_eq!(found, vec!("Room settings", "New room"));
+    assert_eq!(fuzzy_filter("", [("a".to_string(), 1), ("b".to_string(), 2)]).len(), 2);
+}
@@ -1,88 +1,365 @@
-use std::fs::OpenOptions;
-use std::io::BufReader;
-use std::ops::{DivAssign, MulAssign};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
-use tracing_lite::error;
-use floem::action::{inspect, set_window_scale};
+use serde_json::{json, Map, Value};
+use tracing_lite::{error, info, warn};
+use floem::action::{exec_after, set_window_scale};
 use floem::event::{Event, EventListener, EventPropagation};
-use floem::keyboard::{Key, Modifiers, NamedKey};
+use floem::keyboard::{Key, Modifiers};
 use floem::{prelude::*, Application};
 use floem::kurbo::{Point, Size};
-use floem::reactive::provide_context;
-use floem::window::{Theme, WindowConfig};
+use floem::reactive::{provide_context, use_context};
+use floem::window::WindowConfig;
+use ulid::Ulid;
 
+use crate::commands::run_key;
+use crate::links::domain_of;
+use crate::i18n::{follow_config_lang, t, t_with};
+use crate::storage::{app_path, backup_corrupt, write_atomic};
+use crate::theme::{follow_config_theme, LIGHT};
+use crate::views::dialogs::Dialog;
 
-const CONFIG_PATH: &str = "cc.txt";
+
+/// Name of the config file in the [config_dir](crate::storage::config_dir).
+pub const CONFIG_FILE: &str = "config.json";
+/// Config of the older versions, kept in the working dir (imported if there is no other).
+pub const LEGACY_CONFIG_PATH: &str = "cc.txt";
+/// Schema version of the written config, raise it with a new entry of [MIGRATIONS].
+pub const CONFIG_VERSION: u32 = 1;
+pub const MIN_SCALE: f64 = 0.25;
+pub const MAX_SCALE: f64 = 4.;
+/// Config is written after that long without further setting changes.
+pub const CONFIG_SAVE_DELAY: Duration = Duration::from_millis(500);
+/// Smallest window size restored from the config.
+const MIN_WINDOW: Size = Size::new(200., 150.);
+
+/// Migrations of the stored config, `MIGRATIONS[v]` updates version `v` to `v + 1`.
+const MIGRATIONS: [fn(&mut Map<String, Value>); CONFIG_VERSION as usize] = [migrate_unversioned];
+
+thread_local! {
+    /// Off while the stored config which failed to load is kept in place,
+    /// so the defaults used instead don't overwrite it (see [enable_saving]).
+    static SAVING: Cell<bool> = const { Cell::new(true) };
+    /// Id of the last scheduled write; only the most recent one runs.
+    static PENDING_SAVE: Cell<u64> = const { Cell::new(0) };
+}
 
 
 /// Configuration struct for the chat client.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatConfig {
-    theme: Theme,
-    language: Lang, // TODO!
-    position: Point,
-    size: Size,
-    scale: f64
+    /// Schema version of the stored config (see [CONFIG_VERSION]).
+    #[serde(default)]
+    pub version: u32,
+    /// Name of the built-in or user theme (see [crate::theme]).
+    pub theme: String,
+    /// Language of the UI texts.
+    pub language: Lang,
+    pub position: Point,
+    pub size: Size,
+    pub scale: f64,
+    /// Order of the rooms on the rooms list.
+    #[serde(default)]
+    pub rooms_order: RoomsOrder,
+    /// Block and mute lists, K: account of the session user.
+    #[serde(default)]
+    pub privacy: HashMap<Ulid, Privacy>,
+    /// Key bindings of the msg editor.
+    #[serde(default)]
+    pub keymap: Keymap,
+    /// Domains of the links opened without confirmation.
+    #[serde(default)]
+    pub trusted_domains: Vec<String>,
+    /// Show preview cards of the links in msgs.
+    #[serde(default = "enabled")]
+    pub link_previews: bool,
+    #[serde(default)]
+    pub notifications: Notifications,
+    /// Sizes of the main view panes.
+    #[serde(default)]
+    pub layout: PaneLayout
 }
 
 impl ChatConfig {
-    pub fn fetch() -> Self {
-        let config_file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(CONFIG_PATH);
-        match config_file {
-            Ok(cf) => {
-                let reader = BufReader::new(cf);
-                serde_json::from_reader(reader).unwrap_or_default()
+    /// Stored config (the legacy one is imported if there is no other), default one if there is none.
+    /// Corrupt file (or one of a newer version) is moved aside, so the defaults used instead don't overwrite it;
+    /// if it can't be moved (or read at all) saving is disabled until the user changes a setting.
+    pub fn fetch() -> Result<Self, ConfigError> {
+        let path = config_path();
+        let (read_from, json) = match fs::read_to_string(&path) {
+            Ok(json) => (path.clone(), json),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => match fs::read_to_string(LEGACY_CONFIG_PATH) {
+                Ok(json) => { info!("Importing config from {LEGACY_CONFIG_PATH}"); (LEGACY_CONFIG_PATH.into(), json) },
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+                Err(e) => return Err(ConfigError::Unreadable { path: LEGACY_CONFIG_PATH.into(), error: e.to_string() })
+            },
+            Err(e) => {
+                SAVING.with(|s| s.set(false));
+                return Err(ConfigError::Unreadable { path, error: e.to_string() })
+            }
+        };
+        // -- Newer app could keep settings unknown here, so the file isn't downgraded in place
+        let version = json_version(&json);
+        if version > CONFIG_VERSION as u64 {
+            warn!("Config {} is of newer version {version}", read_from.display());
+            let backup = backup_corrupt(&read_from)
+                .inspect_err(|e| error!("Failed to back up newer config: {e}"))
+                .ok();
+            if backup.is_none() {
+                SAVING.with(|s| s.set(false));
+            }
+            return Err(ConfigError::Newer { path: read_from, backup, version })
+        }
+        match Self::from_json(&json) {
+            Ok(config) => {
+                // -- Write back migrated (or imported) one
+                if read_from != path || json_version(&json) < CONFIG_VERSION as u64 {
+                    config.save_to_file();
+                }
+                Ok(config)
             },
-            Err(e) => { error!("Failed to open config file: {e}"); Self::default() }
+            Err(error) => {
+                error!("Corrupt config {}: {error}", read_from.display());
+                let backup = backup_corrupt(&read_from)
+                    .inspect_err(|e| error!("Failed to back up corrupt config: {e}"))
+                    .ok();
+                if backup.is_none() {
+                    SAVING.with(|s| s.set(false));
+                }
+                Err(ConfigError::Corrupt { path: read_from, backup, error })
+            }
+        }
+    }
+
+    /// Parse the stored config, migrating it from the older versions and fixing invalid values.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let Value::Object(mut fields) = serde_json::from_str(json).map_err(|e| e.to_string())? else {
+            return Err("not a JSON object".into())
+        };
+        let version = fields.get("version").map_or(Some(0), Value::as_u64).ok_or("version is not a number")?;
+        if version > CONFIG_VERSION as u64 {
+            return Err(format!("version {version} is newer than supported {CONFIG_VERSION}"))
+        }
+        for (v, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            info!("Migrating config from version {v}");
+            migrate(&mut fields);
+        }
+        fields.insert("version".into(), CONFIG_VERSION.into());
+        let mut config: Self = serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())?;
+        for fixed in config.validate() {
+            warn!("Invalid {fixed} in the config, using default one");
         }
+        Ok(config)
+    }
+
+    /// Replace the invalid values with the defaults, returns names of the fixed ones.
+    pub fn validate(&mut self) -> Vec<&'static str> {
+        let default = Self::default();
+        let mut fixed = Vec::new();
+        if !(MIN_SCALE..=MAX_SCALE).contains(&self.scale) {
+            self.scale = default.scale;
+            fixed.push("scale");
+        }
+        if !(self.size.width >= MIN_WINDOW.width && self.size.height >= MIN_WINDOW.height && self.size.is_finite()) {
+            self.size = default.size;
+            fixed.push("size");
+        }
+        if !self.layout.is_valid() {
+            self.layout = default.layout;
+            fixed.push("layout");
+        }
+        if !self.position.is_finite() {
+            self.position = default.position;
+            fixed.push("position");
+        }
+        // -- Without a send key msgs could not be sent at all
+        if self.keymap.send.is_empty() {
+            self.keymap.send = default.keymap.send;
+            fixed.push("keymap.send");
+        }
+        let domains = self.trusted_domains.len();
+        self.trusted_domains.retain(|d| !d.trim().is_empty());
+        if self.trusted_domains.len() != domains {
+            fixed.push("trusted_domains");
+        }
+        fixed
     }
     
-    pub fn save_to_file(&self) -> Option<()> {
-        let config_file = OpenOptions::new()
-            .truncate(true)
-            .write(true)
-            .create(true)
-            .open(CONFIG_PATH);
-        match config_file {
-            Ok(cf) => {
-                match serde_json::to_writer_pretty(cf, self) {
-                    Ok(_) => Some(()),
-                    Err(e) => { error!("Failed to save config file: {e}"); None }
-                }
+    /// Check if the link leads to one of the trusted domains (or their subdomains).
+    pub fn is_trusted(&self, url: &str) -> bool {
+        domain_of(url).is_some_and(|domain| self.trusted_domains.iter().any(|trusted| {
+            domain == *trusted || domain.ends_with(&format!(".{trusted}"))
+        }))
+    }
+
+    /// Block and mute lists of the account.
+    pub fn privacy_of(&self, acc: &Ulid) -> Privacy {
+        self.privacy.get(acc).cloned().unwrap_or_default()
+    }
+
+    /// Restore defaults of the settings shown in the `section`.
+    pub fn reset_section(&mut self, section: SettingsSection) {
+        let default = Self::default();
+        match section {
+            SettingsSection::Appearance => {
+                self.theme = default.theme;
+                self.scale = default.scale;
+                self.rooms_order.sort = default.rooms_order.sort;
+                self.layout = default.layout;
             },
-            Err(e) => { error!("Failed to open config file: {e}"); None }
+            SettingsSection::Language => self.language = default.language,
+            SettingsSection::Notifications => self.notifications = default.notifications,
+            SettingsSection::Keybindings => self.keymap = default.keymap,
+            // -- Window geometry follows the window itself, there is nothing to reset
+            SettingsSection::Storage => {},
+            SettingsSection::Privacy => {
+                self.trusted_domains = default.trusted_domains;
+                self.link_previews = default.link_previews;
+            }
         }
     }
+
+    /// Write the config to the [config_path] (atomically, old file is kept on failure).
+    /// Skipped while the stored config is kept (see [enable_saving]).
+    pub fn save_to_file(&self) -> Option<()> {
+        if !SAVING.with(|s| s.get()) {
+            warn!("Config not saved, the stored one is kept until a setting is changed");
+            return None
+        }
+        let json = match serde_json::to_vec_pretty(self) {
+            Ok(json) => json,
+            Err(e) => { error!("Failed to serialize config: {e}"); return None }
+        };
+        match write_atomic(&config_path(), &json) {
+            Ok(_) => Some(()),
+            Err(e) => { error!("Failed to save config file: {e}"); None }
+        }
+    }
+}
+
+/// Path of the config file, in the working dir if there is no [config_dir](crate::storage::config_dir).
+pub fn config_path() -> PathBuf {
+    app_path(CONFIG_FILE)
+}
+
+/// Write the config once it stops changing for [CONFIG_SAVE_DELAY].
+pub fn save_debounced(config: RwSignal<ChatConfig>) {
+    let id = PENDING_SAVE.with(|p| { p.set(p.get() + 1); p.get() });
+    exec_after(CONFIG_SAVE_DELAY, move |_| {
+        if PENDING_SAVE.with(|p| p.get()) == id {
+            config.with_untracked(|cf| cf.save_to_file());
+        }
+    });
+}
+
+/// Allow saving the config again, after the stored one was kept (see [ChatConfig::fetch]).
+pub fn enable_saving() {
+    if !SAVING.with(|s| s.replace(true)) {
+        info!("Config saving enabled, the kept file will be replaced");
+    }
+}
+
+/// Version of the stored config, 0 for the unversioned one.
+fn json_version(json: &str) -> u64 {
+    serde_json::from_str::<Value>(json)
+        .ok()
+        .and_then(|value| value.get("version")?.as_u64())
+        .unwrap_or(0)
+}
+
+/// Unversioned `cc.txt` of the older builds. Settings they could miss had no defaults,
+/// so such file failed to parse; they are filled in now. Its `theme` (floem `Theme`
+/// variant, `"Light"` or `"Dark"`) is a valid name of the built-in theme as it is.
+fn migrate_unversioned(fields: &mut Map<String, Value>) {
+    let defaults = [
+        ("theme", json!(LIGHT)),
+        ("language", json!("English")),
+        ("position", json!({"x": 500., "y": 500.})),
+        ("size", json!({"width": 700., "height": 520.})),
+        ("scale", json!(1.))
+    ];
+    for (key, value) in defaults {
+        fields.entry(key).or_insert(value);
+    }
+}
+
+/// Why the stored config was not used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// File exists but can't be read (eg. no permission).
+    Unreadable { path: PathBuf, error: String },
+    /// File is not a valid config; it was moved to the `backup` path (if that succeeded).
+    Corrupt { path: PathBuf, backup: Option<PathBuf>, error: String },
+    /// File was written by a newer app version; it was moved to the `backup` path (if that succeeded).
+    Newer { path: PathBuf, backup: Option<PathBuf>, version: u64 }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Unreadable { path, error } => t_with("config.unreadable", &[("path", &path.display()), ("error", error)]),
+            Self::Corrupt { path, backup: Some(backup), error } => t_with("config.corrupt", &[
+                ("path", &path.display()), ("backup", &backup.display()), ("error", error)
+            ]),
+            Self::Corrupt { path, backup: None, error } => t_with("config.corrupt_kept", &[("path", &path.display()), ("error", error)]),
+            Self::Newer { path, backup: Some(backup), version } => t_with("config.newer", &[
+                ("path", &path.display()), ("backup", &backup.display()), ("version", version)
+            ]),
+            Self::Newer { path, backup: None, version } => t_with("config.newer_kept", &[("path", &path.display()), ("version", version)])
+        })
+    }
 }
 
 
 impl Default for ChatConfig {
     fn default() -> Self {
         Self {
-            theme: Theme::Dark,
+            version: CONFIG_VERSION,
+            theme: LIGHT.into(),
             position: Point { x: 500., y: 500. },
             size: Size {
                 width: 700.,
                 height: 520.
             },
             scale: 1.,
-            language: Lang::English
+            language: Lang::English,
+            rooms_order: RoomsOrder::default(),
+            privacy: HashMap::new(),
+            keymap: Keymap::default(),
+            trusted_domains: Vec::new(),
+            link_previews: true,
+            notifications: Notifications::default(),
+            layout: PaneLayout::default()
         }
     }
 }
 
 
+fn enabled() -> bool {
+    true
+}
+
+
 /// Launch application with reactive config and window resizing.
 pub fn launch_with_config<V: IntoView + 'static>(app_view: impl FnOnce() -> V + 'static) {
-    // -- Fetch application config of apply default one
-    let config = RwSignal::new(ChatConfig::fetch());
+    // -- Fetch application config or apply default one (telling the user why)
+    let config = RwSignal::new(ChatConfig::fetch().unwrap_or_else(|e| {
+        if let Some(dialog) = use_context::<RwSignal<Dialog>>() {
+            dialog.set(Dialog::Notice(e.to_string()));
+        }
+        ChatConfig::default()
+    }));
     // -- Save it as a context into floem runtime
     provide_context(config);
+    // -- Restyle views on theme change and translate them on language change
+    follow_config_theme(config);
+    follow_config_lang(config);
     // -- Provide reactive way to update config and save it to file
     // create_updater(move || config.get(), |cf|{ cf.save_to_file(); });
     // -- Construct window settings from config with reactive updates (.with())
@@ -97,34 +374,18 @@ pub fn launch_with_config<V: IntoView + 'static>(app_view: impl FnOnce() -> V +
             set_window_scale(config.with(|cf| cf.scale));
             app_view()
                 .keyboard_navigable()
-                // -- Make window 10% bigger
-                .on_key_down(
-                    Key::Character("=".into()),
-                    |m| m == Modifiers::CONTROL,
-                    move |_| {
-                        config.update(|cf| { cf.scale.mul_assign(1.1); set_window_scale(cf.scale); });
-                    })
-                // -- Make scale 10% smaller
-                .on_key_down(
-                    Key::Character("-".into()),
-                    |m| m == Modifiers::CONTROL,
-                    move |_| {
-                        config.update(|cf| { cf.scale.div_assign(1.1); set_window_scale(cf.scale); });
-                    })
-                // -- Make scale back to default
-                .on_key_down(
-                    Key::Character("0".into()),
-                    |m| m == Modifiers::CONTROL,
-                    move |_| {
-                        config.update(|cf| { cf.scale = 1.; set_window_scale(cf.scale); });
-                    })
-                // -- Open inspector on F11 key
-                .on_key_down(
-                    Key::Named(NamedKey::F11),
-                    |m| m.is_empty(),
-                    |_| {
-                        inspect();
-                    },
+                // -- Zoom, inspector, palette, .. (see `register_default_commands`)
+                .on_event(
+                    EventListener::KeyDown,
+                    move |ev| {
+                        if let Event::KeyDown(ke) = ev {
+                            let pressed = KeyBinding::pressed(&ke.key.logical_key, ke.modifiers);
+                            if pressed.is_some_and(|p| run_key(&p)) {
+                                return EventPropagation::Stop
+                            }
+                        }
+                        EventPropagation::Continue
+                    }
                 )
                 // -- Update position in config on move end
                 .on_event_stop(
@@ -157,8 +418,567 @@ pub fn launch_with_config<V: IntoView + 'static>(app_view: impl FnOnce() -> V +
 }
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Lang {
     English,
     Polish
-}
\ No newline at end of file
+}
+
+
+/// Sections of the settings window, each can be reset separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsSection {
+    Appearance,
+    Language,
+    Notifications,
+    Keybindings,
+    Storage,
+    Privacy
+}
+
+impl SettingsSection {
+    pub const ALL: [Self; 6] = [
+        Self::Appearance, Self::Language, Self::Notifications,
+        Self::Keybindings, Self::Storage, Self::Privacy
+    ];
+
+    /// Catalog key of the section title.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Appearance => "settings.appearance",
+            Self::Language => "settings.language",
+            Self::Notifications => "settings.notifications",
+            Self::Keybindings => "settings.keybindings",
+            Self::Storage => "settings.storage",
+            Self::Privacy => "settings.privacy"
+        }
+    }
+}
+
+impl Display for SettingsSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&t(self.key()))
+    }
+}
+
+/// Unread counters and typing notifications.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Notifications {
+    /// Show unread msgs count on the rooms list.
+    pub unread_badges: bool,
+    /// Count only msgs mentioning the user as unread.
+    pub mentions_only: bool,
+    /// Let others see when the user is typing.
+    pub send_typing: bool
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self { unread_badges: true, mentions_only: false, send_typing: true }
+    }
+}
+
+
+// MARK: Layout
+
+pub const MIN_SIDEBAR_WIDTH: f64 = 120.;
+pub const MAX_SIDEBAR_WIDTH: f64 = 480.;
+pub const MIN_EDITOR_HEIGHT: f64 = 60.;
+pub const MAX_EDITOR_HEIGHT: f64 = 400.;
+
+/// Sizes of the main view panes, changed by dragging the splitters between them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneLayout {
+    /// Width of the rooms list.
+    pub sidebar_width: f64,
+    pub editor_height: f64,
+    /// Rooms list hidden by the user (compact layout hides it anyway).
+    pub sidebar_collapsed: bool
+}
+
+impl PaneLayout {
+    /// Widen (or narrow, if negative) the rooms list within the limits.
+    pub fn resize_sidebar(&mut self, by: f64) {
+        self.sidebar_width = (self.sidebar_width + by).clamp(MIN_SIDEBAR_WIDTH, MAX_SIDEBAR_WIDTH);
+    }
+
+    pub fn resize_editor(&mut self, by: f64) {
+        self.editor_height = (self.editor_height + by).clamp(MIN_EDITOR_HEIGHT, MAX_EDITOR_HEIGHT);
+    }
+
+    pub fn is_valid(&self) -> bool {
+        (MIN_SIDEBAR_WIDTH..=MAX_SIDEBAR_WIDTH).contains(&self.sidebar_width)
+            && (MIN_EDITOR_HEIGHT..=MAX_EDITOR_HEIGHT).contains(&self.editor_height)
+    }
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        Self { sidebar_width: 200., editor_height: 90., sidebar_collapsed: false }
+    }
+}
+
+
+// MARK: RoomsOrder
+
+/// How non-pinned rooms are sorted on the rooms list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoomSort {
+    /// Room with the most recent msg on top.
+    #[default]
+    Activity,
+    /// Order set by the user.
+    Manual
+}
+
+/// User preferences regarding the order of the rooms list.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoomsOrder {
+    pub sort: RoomSort,
+    /// Pinned rooms (always on top, in that order).
+    pub pinned: Vec<Ulid>,
+    /// Manual order of the not pinned rooms.
+    pub manual: Vec<Ulid>
+}
+
+impl RoomsOrder {
+    pub fn is_pinned(&self, room: &Ulid) -> bool {
+        self.pinned.contains(room)
+    }
+
+    /// Pin the room (on the bottom of pinned list) or unpin it.
+    pub fn toggle_pin(&mut self, room: Ulid) {
+        if let Some(pos) = self.pinned.iter().position(|r| *r == room) {
+            self.pinned.remove(pos);
+        } else {
+            self.pinned.push(room);
+        }
+    }
+
+    /// Sort rooms given as (room id, last activity ms) pairs.
+    /// Pinned rooms go first, the rest is sorted depending on [RoomSort].
+    /// Sort is stable, so rooms with equal keys keep their order.
+    pub fn sort(&self, rooms: &mut [(Ulid, u64)]) {
+        let by_activity = |a: &(Ulid, u64), b: &(Ulid, u64)| b.1.cmp(&a.1).then(a.0.cmp(&b.0));
+        rooms.sort_by(|a, b| {
+            let a_pin = self.pinned.iter().position(|r| *r == a.0);
+            let b_pin = self.pinned.iter().position(|r| *r == b.0);
+            match (a_pin, b_pin) {
+                (Some(a_pin), Some(b_pin)) => a_pin.cmp(&b_pin),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => match self.sort {
+                    RoomSort::Activity => by_activity(a, b),
+                    RoomSort::Manual => {
+                        let a_pos = self.manual.iter().position(|r| *r == a.0).unwrap_or(usize::MAX);
+                        let b_pos = self.manual.iter().position(|r| *r == b.0).unwrap_or(usize::MAX);
+                        a_pos.cmp(&b_pos).then_with(|| by_activity(a, b))
+                    }
+                }
+            }
+        });
+    }
+
+    /// Move room one position up or down within its section (pinned or not)
+    /// using `current` as the visible order of the rooms.
+    /// Moving not pinned room switches sorting to [RoomSort::Manual].
+    pub fn move_room(&mut self, room: Ulid, up: bool, current: &[Ulid]) {
+        let list = if self.is_pinned(&room) {
+            &mut self.pinned
+        } else {
+            // -- Seed manual order from what is currently visible
+            self.manual = current
+                .iter()
+                .filter(|r| !self.pinned.contains(r))
+                .cloned()
+                .collect();
+            self.sort = RoomSort::Manual;
+            &mut self.manual
+        };
+        if let Some(pos) = list.iter().position(|r| *r == room) {
+            match up {
+                true if pos > 0 => list.swap(pos, pos - 1),
+                false if pos + 1 < list.len() => list.swap(pos, pos + 1),
+                _ => {}
+            }
+        }
+    }
+}
+
+
+// MARK: Privacy
+
+/// Accounts whose msgs are hidden (muted) or filtered out (blocked).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Privacy {
+    pub blocked: Vec<Ulid>,
+    pub muted: Vec<Ulid>
+}
+
+impl Privacy {
+    pub fn is_blocked(&self, acc: &Ulid) -> bool {
+        self.blocked.contains(acc)
+    }
+
+    pub fn is_muted(&self, acc: &Ulid) -> bool {
+        self.muted.contains(acc)
+    }
+
+    /// Block or unblock the account (blocking replaces muting).
+    pub fn toggle_block(&mut self, acc: Ulid) {
+        if let Some(pos) = self.blocked.iter().position(|a| *a == acc) {
+            self.blocked.remove(pos);
+        } else {
+            self.muted.retain(|a| *a != acc);
+            self.blocked.push(acc);
+        }
+    }
+
+    /// Mute or unmute the account (blocked account can't be muted).
+    pub fn toggle_mute(&mut self, acc: Ulid) {
+        if let Some(pos) = self.muted.iter().position(|a| *a == acc) {
+            self.muted.remove(pos);
+        } else if !self.is_blocked(&acc) {
+            self.muted.push(acc);
+        }
+    }
+}
+
+
+// MARK: Keymap
+
+/// Msg editor actions which can be bound to keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorAction {
+    /// Send the msg (or save the edited one).
+    Send,
+    /// Insert new line.
+    NewLine,
+    /// Start editing last msg of the user (only in an empty editor).
+    EditLast
+}
+
+/// Key with modifiers, stored in the config as e.g. `"Ctrl+Enter"`.
+/// Key names follow floem [NamedKey](floem::keyboard::NamedKey) names (`Enter`, `ArrowUp`, ..).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub key: String,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool
+}
+
+impl KeyBinding {
+    pub fn new(key: &str, ctrl: bool, shift: bool, alt: bool) -> Self {
+        Self { key: key.into(), ctrl, shift, alt }
+    }
+
+    /// Key without modifiers.
+    pub fn key(key: &str) -> Self {
+        Self::new(key, false, false, false)
+    }
+
+    /// Binding of the pressed key (`None` for keys without a name).
+    pub fn pressed(key: &Key, mods: Modifiers) -> Option<Self> {
+        let key = match key {
+            Key::Character(c) => c.to_string(),
+            Key::Named(named) => format!("{named:?}"),
+            _ => return None
+        };
+        Some(Self { key, ctrl: mods.control(), shift: mods.shift(), alt: mods.alt() })
+    }
+}
+
+impl std::str::FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // -- `+` can be a key itself, eg. "Ctrl++"
+        let (mods, key) = match s.rsplit_once('+') {
+            Some((mods, "")) if mods.ends_with('+') => (&mods[..mods.len() - 1], "+"),
+            Some((mods, key)) => (mods, key),
+            None => ("", s)
+        };
+        if key.is_empty() {
+            return Err(t_with("keys.missing_key", &[("binding", &s)]))
+        }
+        let mut binding = Self::key(key);
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            match m.to_lowercase().as_str() {
+                "ctrl" | "control" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                "alt" => binding.alt = true,
+                _ => return Err(t_with("keys.unknown_modifier", &[("modifier", &m), ("binding", &s)]))
+            }
+        }
+        Ok(binding)
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl { f.write_str("Ctrl+")? }
+        if self.shift { f.write_str("Shift+")? }
+        if self.alt { f.write_str("Alt+")? }
+        f.write_str(&self.key)
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(value: KeyBinding) -> Self {
+        value.to_string()
+    }
+}
+
+/// Editor key bindings; each action can have several keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keymap {
+    pub send: Vec<KeyBinding>,
+    pub new_line: Vec<KeyBinding>,
+    pub edit_last: Vec<KeyBinding>
+}
+
+impl Keymap {
+    /// Enter sends, Shift+Enter inserts new line.
+    pub fn enter_to_send() -> Self {
+        Self {
+            send: vec!(KeyBinding::key("Enter")),
+            new_line: vec!(KeyBinding::new("Enter", false, true, false)),
+            edit_last: vec!(KeyBinding::key("ArrowUp"))
+        }
+    }
+
+    /// Ctrl+Enter sends, Enter (and Shift+Enter) inserts new line.
+    pub fn ctrl_enter_to_send() -> Self {
+        Self {
+            send: vec!(KeyBinding::new("Enter", true, false, false)),
+            new_line: vec!(KeyBinding::key("Enter"), KeyBinding::new("Enter", false, true, false)),
+            edit_last: vec!(KeyBinding::key("ArrowUp"))
+        }
+    }
+
+    /// Keys of each action.
+    pub fn bindings(&self) -> [(&Vec<KeyBinding>, EditorAction); 3] {
+        [
+            (&self.send, EditorAction::Send),
+            (&self.new_line, EditorAction::NewLine),
+            (&self.edit_last, EditorAction::EditLast)
+        ]
+    }
+
+    /// Action bound to the pressed key (if any).
+    pub fn action(&self, pressed: &KeyBinding) -> Option<EditorAction> {
+        self.bindings()
+            .into_iter()
+            .find(|(keys, _)| keys.contains(pressed))
+            .map(|(_, action)| action)
+    }
+
+    /// Comma separated list of bindings, eg. `"Enter, Shift+Enter"`.
+    pub fn parse_list(text: &str) -> Result<Vec<KeyBinding>, String> {
+        text.split(',')
+            .map(str::trim)
+            .filter(|b| !b.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    pub fn list_to_string(keys: &[KeyBinding]) -> String {
+        keys.iter().map(KeyBinding::to_string).collect::<Vec<_>>().join(", ")
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::enter_to_send()
+    }
+}
+
+
+#[test]
+fn keymap_test() {
+    let enter = KeyBinding::key("Enter");
+    let shift_enter: KeyBinding = "Shift+Enter".parse().unwrap();
+    let ctrl_enter: KeyBinding = "control+Enter".parse().unwrap();
+    assert_eq!(shift_enter, KeyBinding::new("Enter", false, true, false));
+    assert_eq!(ctrl_enter.to_string(), "Ctrl+Enter");
+    assert_eq!("Ctrl++".parse::<KeyBinding>().unwrap(), KeyBinding::new("+", true, false, false));
+    assert!("Ctrl+".parse::<KeyBinding>().is_err());
+    assert!("Meta+Enter".parse::<KeyBinding>().is_err());
+
+    let keymap = Keymap::default();
+    assert_eq!(keymap.action(&enter), Some(EditorAction::Send));
+    assert_eq!(keymap.action(&shift_enter), Some(EditorAction::NewLine));
+    assert_eq!(keymap.action(&ctrl_enter), None);
+    assert_eq!(keymap.action(&KeyBinding::key("ArrowUp")), Some(EditorAction::EditLast));
+
+    let keymap = Keymap::ctrl_enter_to_send();
+    assert_eq!(keymap.action(&enter), Some(EditorAction::NewLine));
+    assert_eq!(keymap.action(&ctrl_enter), Some(EditorAction::Send));
+
+    // -- Stored as plain strings
+    let json = serde_json::to_string(&keymap).unwrap();
+    assert_eq!(json, r#"{"send":["Ctrl+Enter"],"new_line":["Enter","Shift+Enter"],"edit_last":["ArrowUp"]}"#);
+    assert_eq!(serde_json::from_str::<Keymap>(&json).unwrap(), keymap);
+}
+
+#[test]
+fn keymap_bindings_fire_test() {
+    use floem::keyboard::NamedKey;
+    // -- Every binding of every action is reachable from a key press
+    for keymap in [Keymap::enter_to_send(), Keymap::ctrl_enter_to_send()] {
+        for (keys, action) in keymap.bindings() {
+            for binding in keys {
+                let key = match binding.key.as_str() {
+                    "Enter" => Key::Named(NamedKey::Enter),
+                    "ArrowUp" => Key::Named(NamedKey::ArrowUp),
+                    c => Key::Character(c.into())
+                };
+                let mut mods = Modifiers::empty();
+                mods.set(Modifiers::CONTROL, binding.ctrl);
+                mods.set(Modifiers::SHIFT, binding.shift);
+                mods.set(Modifiers::ALT, binding.alt);
+                let pressed = KeyBinding::pressed(&key, mods).unwrap();
+                assert_eq!(&pressed, binding);
+                assert_eq!(keymap.action(&pressed), Some(action), "{binding} of {action:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn reset_section_test() {
+    let mut config = ChatConfig::default();
+    config.scale = 2.;
+    config.keymap = Keymap::ctrl_enter_to_send();
+    config.notifications.mentions_only = true;
+    config.trusted_domains.push("docs.rs".into());
+    config.reset_section(SettingsSection::Keybindings);
+    assert_eq!(config.keymap, Keymap::default());
+    // -- Other sections are kept
+    assert_eq!(config.scale, 2.);
+    assert!(config.notifications.mentions_only);
+    // -- Window geometry is not a setting of the section
+    config.size = Size::new(300., 200.);
+    config.reset_section(SettingsSection::Storage);
+    assert_eq!(config.size, Size::new(300., 200.));
+    config.reset_section(SettingsSection::Appearance);
+    config.reset_section(SettingsSection::Notifications);
+    assert_eq!((config.scale, config.notifications), (1., Notifications::default()));
+    assert_eq!(config.trusted_domains, ["docs.rs"]);
+
+    let keys = Keymap::parse_list(" Enter, Shift+Enter ,").unwrap();
+    assert_eq!(Keymap::list_to_string(&keys), "Enter, Shift+Enter");
+    assert!(Keymap::parse_list("Enter, Hyper+K").is_err());
+    assert_eq!(Keymap::parse_list("").unwrap(), vec!());
+}
+
+#[test]
+fn config_migration_test() {
+    // -- Unversioned file with the floem theme and no defaults for the older settings
+    let config = ChatConfig::from_json(r#"{"theme": "Dark", "language": "Polish", "scale": 1.5}"#).unwrap();
+    assert_eq!(config.version, CONFIG_VERSION);
+    assert_eq!((config.theme.as_str(), config.language, config.scale), ("Dark", Lang::Polish, 1.5));
+    assert_eq!(config.size, ChatConfig::default().size);
+
+    let json = serde_json::to_string(&config).unwrap();
+    let again = ChatConfig::from_json(&json).unwrap();
+    assert_eq!((again.theme, again.language, again.scale), (config.theme, config.language, config.scale));
+
+    // -- Invalid values are replaced with the defaults
+    let mut config = ChatConfig::default();
+    config.scale = 40.;
+    config.size = Size::new(10., 600.);
+    config.layout.editor_height = 5.;
+    config.keymap.send.clear();
+    config.trusted_domains = vec!("docs.rs".into(), " ".into());
+    assert_eq!(config.validate(), ["scale", "size", "layout", "keymap.send", "trusted_domains"]);
+    assert_eq!(config.layout, PaneLayout::default());
+    assert_eq!(config.keymap, Keymap::default());
+    assert_eq!(config.trusted_domains, ["docs.rs"]);
+    assert!(config.validate().is_empty());
+
+    assert!(ChatConfig::from_json("{\"theme\": ").is_err());
+    assert!(ChatConfig::from_json("[1, 2]").is_err());
+    assert!(ChatConfig::from_json(r#"{"version": "one"}"#).is_err());
+    assert!(ChatConfig::from_json(r#"{"version": 1, "theme": 5}"#).is_err());
+    // -- Newer version isn't downgraded
+    let newer = serde_json::to_string(&json!({"version": CONFIG_VERSION + 1, "theme": "Dark"})).unwrap();
+    assert!(ChatConfig::from_json(&newer).is_err());
+}
+
+#[test]
+fn pane_layout_test() {
+    let mut layout = PaneLayout::default();
+    layout.resize_sidebar(50.);
+    layout.resize_editor(-10.);
+    assert_eq!((layout.sidebar_width, layout.editor_height), (250., 80.));
+    // -- Dragging past the limits stops at them
+    layout.resize_sidebar(-1000.);
+    layout.resize_editor(1000.);
+    assert_eq!((layout.sidebar_width, layout.editor_height), (MIN_SIDEBAR_WIDTH, MAX_EDITOR_HEIGHT));
+    assert!(layout.is_valid());
+
+    // -- Older configs without the layout get the default one
+    let config = ChatConfig::from_json(r#"{"version": 1, "theme": "Light", "language": "English",
+        "position": {"x": 0, "y": 0}, "size": {"width": 700, "height": 520}, "scale": 1}"#).unwrap();
+    assert_eq!(config.layout, PaneLayout::default());
+}
+
+#[test]
+fn kept_config_test() {
+    // -- Defaults used instead of the kept file are not written over it
+    SAVING.with(|s| s.set(false));
+    assert_eq!(ChatConfig::default().save_to_file(), None);
+    enable_saving();
+    assert!(SAVING.with(|s| s.get()));
+}
+
+#[test]
+fn rooms_order_test() {
+    let (a, b, c, d) = (Ulid::from_parts(1, 1), Ulid::from_parts(2, 2), Ulid::from_parts(3, 3), Ulid::from_parts(4, 4));
+    let ids = |rooms: &[(Ulid, u64)]| rooms.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let mut order = RoomsOrder::default();
+    let mut rooms = vec![(a, 10), (b, 30), (c, 20), (d, 30)];
+    // -- Most recent activity first, equal activity by id
+    order.sort(&mut rooms);
+    assert_eq!(ids(&rooms), [b, d, c, a]);
+    // -- Pinned on top in the pin order
+    order.toggle_pin(a);
+    order.toggle_pin(c);
+    order.sort(&mut rooms);
+    assert_eq!(ids(&rooms), [a, c, b, d]);
+    order.toggle_pin(a);
+    assert!(!order.is_pinned(&a));
+
+    // -- Moving not pinned room seeds manual order from the visible one
+    let mut order = RoomsOrder::default();
+    order.toggle_pin(c);
+    order.move_room(a, true, &[c, b, d, a]);
+    assert_eq!(order.sort, RoomSort::Manual);
+    assert_eq!(order.manual, [b, a, d]);
+    order.sort(&mut rooms);
+    assert_eq!(ids(&rooms), [c, b, a, d]);
+    // -- Moves stop at the section edges
+    order.move_room(b, true, &ids(&rooms));
+    assert_eq!(order.manual, [b, a, d]);
+    order.move_room(d, false, &ids(&rooms));
+    assert_eq!(order.manual, [b, a, d]);
+    // -- Pinned rooms move among pinned only
+    order.toggle_pin(d);
+    order.move_room(d, true, &ids(&rooms));
+    assert_eq!(order.pinned, [d, c]);
+    order.move_room(c, false, &ids(&rooms));
+    assert_eq!(order.pinned, [d, c]);
+}
@@ -3,6 +3,7 @@ use std::{rc::Rc, sync::atomic::{AtomicU8, AtomicUsize, Ordering}};
 use chrono_lite::Datetime;
 use serde::{Deserialize, Serialize};
 
+use crate::i18n::{t, t_plural, t_with};
 use crate::util::{Id, Tb};
 
 
@@ -19,6 +20,15 @@ pub struct Account {
 }
 
 impl Account {
+    /// Placeholder account used while nobody is signed in.
+    pub fn signed_out() -> Self {
+        Self {
+            acc_id: Id::default(),
+            username: String::new(),
+            av: Rc::new(Vec::with_capacity(0))
+        }
+    }
+
     pub fn new_from_click() -> Option<Self> {
         if let Some((us, av)) = get_username_and_avatar() {
             Some(Self {
@@ -31,6 +41,43 @@ impl Account {
 }
 
 
+/// Account availability.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Presence {
+    Online,
+    Away,
+    #[default]
+    Offline
+}
+
+/// Presence with the last time (unix ms) account was seen online.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceInfo {
+    pub presence: Presence,
+    pub last_seen: u64
+}
+
+impl PresenceInfo {
+    /// Human readable presence, e.g. "online" or "last seen 5 min ago".
+    pub fn describe(&self, now: u64) -> String {
+        match self.presence {
+            Presence::Online => t("presence.online"),
+            Presence::Away => t("presence.away"),
+            Presence::Offline if self.last_seen == 0 => t("presence.offline"),
+            Presence::Offline => {
+                let mins = now.saturating_sub(self.last_seen) / 60_000;
+                match mins {
+                    0 => t("presence.just_now"),
+                    1..=59 => t_with("presence.min_ago", &[("n", &mins)]),
+                    60..=1439 => t_with("presence.h_ago", &[("n", &(mins / 60))]),
+                    _ => t_plural("presence.days_ago", mins / 1440, &[])
+                }
+            }
+        }
+    }
+}
+
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Member {
     pub member_id: Id,
@@ -34,6 +34,9 @@ pub struct Msg {
     /// Message reaction emojis - if any.
     #[serde(default)]
     pub reactions: Option<Vec<Reaction>>,
+    /// Msg this one replies to - if any.
+    #[serde(default)]
+    pub reply_to: Option<Id>,
     /// If Msg was delivered to all room members.
     pub delivered_to_all: bool,
     /// If Msg was viewed by all room members.
@@ -375,6 +378,7 @@ impl Msg {
             media: None,
             comments: None,
             reactions: None,
+            reply_to: None,
             delivered_to_all: false,
             viewed_by_all: false,
             text: Text { current: text, edits: None, last_edited: None },
@@ -394,6 +398,7 @@ impl Msg {
             created: Datetime::default(),
             comments: None,
             reactions: None,
+            reply_to: None,
             delivered_to_all: false,
             viewed_by_all: false,
             text: Text { current: text, edits: None, last_edited: None },
@@ -1,7 +1,7 @@
 use chrono_lite::Datetime;
 use serde::{Deserialize, Serialize};
 
-use crate::util::Id;
+use crate::util::{now_ms, Id};
 use super::acc::Member;
 
 
@@ -11,14 +11,58 @@ use super::acc::Member;
 pub struct Room {
     #[serde(rename = "id")]
     pub room_id: Id,
+    #[serde(default)]
+    pub name: String,
     pub members: Vec<Member>,
     pub active_members: Vec<Id>,
     #[serde(default)]
-    pub active_invites: Option<Vec<u8>>,
+    pub active_invites: Option<Vec<RoomInvite>>,
     pub owner: Id,
     pub created: Datetime,
     #[serde(default)]
     pub description: Option<String>,
     #[serde(default)]
     pub avatar: Option<Vec<u8>>,
-}
\ No newline at end of file
+}
+
+
+/// Invitation to join the room.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RoomInvite {
+    #[serde(rename = "id")]
+    pub invite_id: Id,
+    pub room_id: Id,
+    /// Name of the room at the time of invite creation.
+    #[serde(default)]
+    pub room_name: String,
+    /// Account which created the invite.
+    pub inviter: Id,
+    pub target: InviteTarget,
+    pub created: Datetime,
+    /// Unix timestamp (ms) after which invite can't be accepted.
+    pub expires: u64
+}
+
+impl RoomInvite {
+    pub fn is_expired(&self) -> bool {
+        self.expires <= now_ms()
+    }
+
+    /// Check if invite can be used by the given account.
+    pub fn is_for(&self, acc: &Id) -> bool {
+        match &self.target {
+            InviteTarget::Account(invitee) => invitee == acc,
+            InviteTarget::Link(_) => true
+        }
+    }
+}
+
+
+/// Who can use the invite.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InviteTarget {
+    /// Only this account.
+    Account(Id),
+    /// Anyone with the token.
+    Link(String)
+}
@@ -0,0 +1,129 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::time::Duration;
+
+use floem::action::exec_after;
+use floem::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing_lite::{error, trace, warn};
+use ulid::Ulid;
+
+use crate::storage::{app_path, write_atomic};
+
+
+/// File (in the [config_dir](crate::storage::config_dir)) where the unsent msgs are kept between runs.
+pub const DRAFTS_FILE: &str = "drafts.json";
+/// Drafts are written to disk after that long without further edits.
+pub const DRAFTS_SAVE_DELAY: Duration = Duration::from_secs(1);
+/// Max number of chars shown in the rooms list preview.
+const PREVIEW_LEN: usize = 40;
+
+thread_local! {
+    /// Id of the last scheduled write; only the most recent one runs.
+    static PENDING_SAVE: Cell<u64> = const { Cell::new(0) };
+    /// Id of the last done write (equal to [PENDING_SAVE] if nothing is waiting).
+    static DONE_SAVE: Cell<u64> = const { Cell::new(0) };
+}
+
+
+/// Unsent text of the editor for each room, K: room id.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Drafts(HashMap<Ulid, String>);
+
+impl Drafts {
+    pub fn load() -> Self {
+        let Ok(file) = File::open(app_path(DRAFTS_FILE)) else { return Self::default() };
+        match serde_json::from_reader(BufReader::new(file)) {
+            Ok(drafts) => drafts,
+            Err(e) => { warn!("Failed to read drafts: {e}"); Self::default() }
+        }
+    }
+
+    pub fn save(&self) -> Option<()> {
+        let json = match serde_json::to_vec_pretty(self) {
+            Ok(json) => json,
+            Err(e) => { error!("Failed to serialize drafts: {e}"); return None }
+        };
+        match write_atomic(&app_path(DRAFTS_FILE), &json) {
+            Ok(_) => Some(()),
+            Err(e) => { error!("Failed to save drafts: {e}"); None }
+        }
+    }
+
+    pub fn get(&self, room: &Ulid) -> Option<&str> {
+        self.0.get(room).map(String::as_str)
+    }
+
+    /// Store the draft (blank text removes it). Returns `true` if anything changed.
+    pub fn set(&mut self, room: Ulid, text: &str) -> bool {
+        if text.trim().is_empty() {
+            return self.0.remove(&room).is_some()
+        }
+        if self.get(&room) == Some(text) {
+            return false
+        }
+        self.0.insert(room, text.into());
+        true
+    }
+
+    /// Forget the draft of the room. Returns `true` if there was one.
+    pub fn remove(&mut self, room: &Ulid) -> bool {
+        self.0.remove(room).is_some()
+    }
+
+    /// Single line, shortened draft for the rooms list.
+    pub fn preview(&self, room: &Ulid) -> Option<String> {
+        let text = self.get(room)?.split_whitespace().collect::<Vec<_>>().join(" ");
+        match text.char_indices().nth(PREVIEW_LEN) {
+            Some((end, _)) => Some(format!("{}…", &text[..end])),
+            None => Some(text)
+        }
+    }
+}
+
+
+/// Write drafts to disk once they stop changing for [DRAFTS_SAVE_DELAY].
+pub fn save_debounced(drafts: RwSignal<Drafts>) {
+    let id = PENDING_SAVE.with(|p| { p.set(p.get() + 1); p.get() });
+    exec_after(DRAFTS_SAVE_DELAY, move |_| {
+        if PENDING_SAVE.with(|p| p.get()) == id && DONE_SAVE.with(|d| d.replace(id)) != id {
+            trace!("fn: save_debounced: writing drafts");
+            drafts.with_untracked(|d| d.save());
+        }
+    });
+}
+
+/// Write the drafts now if a [save_debounced] is still waiting (eg. on window close).
+pub fn flush_drafts(drafts: RwSignal<Drafts>) {
+    let id = PENDING_SAVE.with(|p| p.get());
+    if DONE_SAVE.with(|d| d.replace(id)) != id {
+        trace!("fn: flush_drafts: writing drafts");
+        drafts.with_untracked(|d| d.save());
+    }
+}
+
+
+#[test]
+fn drafts_test() {
+    let room = Ulid::new();
+    let mut drafts = Drafts::default();
+    assert!(drafts.set(room, "hello"));
+    assert!(!drafts.set(room, "hello"));
+    assert_eq!(drafts.get(&room), Some("hello"));
+    assert_eq!(drafts.preview(&room).as_deref(), Some("hello"));
+    // -- Preview is a single, shortened line
+    drafts.set(room, "first line\n  second   line");
+    assert_eq!(drafts.preview(&room).as_deref(), Some("first line second line"));
+    drafts.set(room, &"ż".repeat(50));
+    assert_eq!(drafts.preview(&room), Some(format!("{}…", "ż".repeat(40))));
+    // -- Blank text removes the draft
+    assert!(drafts.set(room, " \n"));
+    assert_eq!(drafts.get(&room), None);
+    assert_eq!(drafts.preview(&room), None);
+    assert!(!drafts.set(room, ""));
+    drafts.set(room, "hello");
+    assert!(drafts.remove(&room));
+    assert!(!drafts.remove(&room));
+}
@@ -0,0 +1,77 @@
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+use tracing_lite::warn;
+
+
+/// Grammars bundled with the binary (no downloads at runtime).
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static LIGHT_THEME: LazyLock<Theme> = LazyLock::new(|| bundled_theme("InspiredGitHub"));
+static DARK_THEME: LazyLock<Theme> = LazyLock::new(|| bundled_theme("base16-ocean.dark"));
+
+fn bundled_theme(name: &str) -> Theme {
+    ThemeSet::load_defaults().themes.remove(name).unwrap_or_default()
+}
+
+
+/// Highlighted part of the code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// Byte range in the code.
+    pub range: Range<usize>,
+    pub color: (u8, u8, u8),
+    pub bold: bool,
+    pub italic: bool
+}
+
+/// Highlight the code written in `lang` (name or extension, eg. `rust`, `rs`, `sh`).
+/// Colors are picked for a `dark` or light background. Returns `None` for unknown languages.
+pub fn highlight(code: &str, lang: &str, dark: bool) -> Option<Vec<Token>> {
+    let syntax = SYNTAXES.find_syntax_by_token(lang)?;
+    let theme = if dark { &*DARK_THEME } else { &*LIGHT_THEME };
+    let mut lines = HighlightLines::new(syntax, theme);
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    for line in LinesWithEndings::from(code) {
+        let ranges = match lines.highlight_line(line, &SYNTAXES) {
+            Ok(ranges) => ranges,
+            Err(e) => { warn!("Failed to highlight {lang} code: {e}"); return None }
+        };
+        for (style, text) in ranges {
+            let fg = style.foreground;
+            tokens.push(Token {
+                range: pos..pos + text.len(),
+                color: (fg.r, fg.g, fg.b),
+                bold: style.font_style.contains(FontStyle::BOLD),
+                italic: style.font_style.contains(FontStyle::ITALIC)
+            });
+            pos += text.len();
+        }
+    }
+    Some(tokens)
+}
+
+
+#[test]
+fn highlight_test() {
+    assert_eq!(highlight("whatever", "no-such-lang", false), None);
+    let code = "fn main() {\n    println!(\"hi\");\n}\n";
+    let tokens = highlight(code, "rust", false).unwrap();
+    // -- Tokens cover the whole code, in order
+    assert_eq!(tokens.first().unwrap().range.start, 0);
+    assert_eq!(tokens.last().unwrap().range.end, code.len());
+    assert!(tokens.windows(2).all(|t| t[0].range.end == t[1].range.start));
+    // -- Keyword and string are colored differently
+    let color_of = |text: &str| {
+        let start = code.find(text).unwrap();
+        tokens.iter().find(|t| t.range.contains(&start)).unwrap().color
+    };
+    assert_ne!(color_of("fn"), color_of("\"hi\""));
+    assert!(highlight("echo $HOME | grep x", "sh", true).is_some());
+    // -- Dark background gets other colors
+    assert_ne!(highlight(code, "rust", true).unwrap(), tokens);
+}
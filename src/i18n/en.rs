@@ -0,0 +1,244 @@
+/// English texts (also used for the keys missing in other catalogs).
+pub const CATALOG: &[(&str, &str)] = &[
+    // -- Common
+    ("common.cancel", "Cancel"),
+    ("common.close", "Close"),
+    ("common.save", "Save"),
+    ("common.remove", "Remove"),
+    ("common.choose_avatar", "Choose avatar"),
+    ("common.remove_avatar", "Remove avatar"),
+    ("common.unknown", "unknown"),
+    ("common.read_failed", "Unable to read {path}: {error}"),
+    ("avatar.unsupported", "Unsupported image: {error}"),
+    ("avatar.encode_failed", "Unable to encode avatar: {error}"),
+    ("config.unreadable", "Settings file {path} can't be read ({error}), the defaults are used. The file is kept until you change a setting."),
+    ("config.corrupt", "Settings file {path} is corrupt ({error}). It was moved to {backup} and the defaults are used."),
+    ("config.corrupt_kept", "Settings file {path} is corrupt ({error}), the defaults are used. The file is kept until you change a setting."),
+    ("config.newer", "Settings file {path} is from a newer app version ({version}). It was moved to {backup} and the defaults are used."),
+    ("config.newer_kept", "Settings file {path} is from a newer app version ({version}), the defaults are used. The file is kept until you change a setting."),
+    ("keys.missing_key", "Missing key in binding: \"{binding}\""),
+    ("keys.unknown_modifier", "Unknown modifier \"{modifier}\" in binding: \"{binding}\""),
+
+    // -- Commands
+    ("cmd.palette", "Command palette"),
+    ("cmd.settings", "Settings"),
+    ("cmd.new_test_msg", "New test msg"),
+    ("cmd.new_test_msgs", "New test msgs"),
+    ("cmd.new_room", "New room"),
+    ("cmd.new_test_account", "New test account"),
+    ("cmd.edit_profile", "Edit profile"),
+    ("cmd.privacy", "Privacy"),
+    ("cmd.room_settings", "Room settings"),
+    ("cmd.invitations", "Invitations"),
+    ("cmd.load_more", "Load more msgs"),
+    ("cmd.add_account", "Add account"),
+    ("cmd.sign_out", "Sign out"),
+    ("cmd.zoom_in", "Zoom in"),
+    ("cmd.zoom_out", "Zoom out"),
+    ("cmd.zoom_reset", "Reset zoom"),
+    ("cmd.toggle_sidebar", "Show or hide rooms list"),
+    ("cmd.inspector", "Open inspector"),
+    ("toolbar.new", "New"),
+    ("toolbar.edit", "Edit"),
+    ("toolbar.about", "About"),
+    ("toolbar.load_more", "load more"),
+    ("palette.room", "Room"),
+    ("palette.person", "Person"),
+    ("palette.search", "Search commands, rooms and people"),
+    ("palette.nothing_found", "Nothing found"),
+
+    // -- Slash commands
+    ("slash.unknown", "Unknown command /{name} (type / to see the commands)"),
+    ("slash.no_room", "No room selected"),
+    ("slash.not_signed_in", "Not signed in"),
+    ("slash.me", "Describe what you do"),
+    ("slash.me_usage", "Usage: /me <action>"),
+    ("slash.shrug", "Append ¯\\_(ツ)_/¯"),
+    ("slash.topic", "Change the room description"),
+    ("slash.topic_owner_only", "Only the room owner can change the topic"),
+    ("slash.invite", "Invite the account to the room"),
+    ("slash.invite_usage", "Usage: /invite @user"),
+    ("slash.no_such_user", "Nobody is called @{name}"),
+    ("slash.already_member", "@{name} is already in the room"),
+    ("slash.leave", "Leave the room"),
+    ("slash.nick", "Change your username"),
+    ("slash.nick_usage", "Usage: /nick <name> (letters, digits, `_`, `-` and `.`)"),
+
+    // -- Login and profile
+    ("login.title", "Sign in"),
+    ("login.username", "Username"),
+    ("login.password", "Password"),
+    ("login.remember", "Remember me"),
+    ("login.register", "Create account"),
+    ("login.sign_in", "Sign in"),
+    ("profile.title", "Profile"),
+    ("profile.select_avatar", "Select avatar"),
+    ("profile.status", "Status (optional)"),
+    ("presence.online", "online"),
+    ("presence.away", "away"),
+    ("presence.offline", "offline"),
+    ("presence.just_now", "last seen just now"),
+    ("presence.min_ago", "last seen {n} min ago"),
+    ("presence.h_ago", "last seen {n} h ago"),
+    ("presence.days_ago.one", "last seen {n} day ago"),
+    ("presence.days_ago.other", "last seen {n} days ago"),
+    ("privacy.title", "Privacy"),
+    ("privacy.blocked", "Blocked (msgs and reactions are not shown):"),
+    ("privacy.unblock", "Unblock"),
+    ("privacy.muted", "Muted (msgs are collapsed):"),
+    ("privacy.unmute", "Unmute"),
+    ("privacy.accounts", "Accounts:"),
+    ("privacy.mute_selected", "Mute selected"),
+    ("privacy.block_selected", "Block selected"),
+    ("privacy.nobody", "Nobody"),
+
+    // -- Rooms
+    ("rooms.sort_activity", "Sort: activity"),
+    ("rooms.sort_manual", "Sort: manual"),
+    ("rooms.draft", "Draft: {text}"),
+    ("rooms.no_msgs", "no msgs yet.."),
+    ("rooms.pin", "Pin to top"),
+    ("rooms.unpin", "Unpin"),
+    ("rooms.move_up", "Move up"),
+    ("rooms.move_down", "Move down"),
+    ("room.new_title", "New room"),
+    ("room.settings_title", "Room settings"),
+    ("room.select_avatar", "Select room avatar"),
+    ("room.name", "Room name"),
+    ("room.description", "Description (optional)"),
+    ("room.no_description", "No description"),
+    ("room.members", "Members:"),
+    ("room.add_members", "Add members:"),
+    ("room.add_selected", "Add selected"),
+    ("room.owner", "owner"),
+    ("room.owner_name", "{name} (owner)"),
+    ("room.make_owner", "Make owner"),
+    ("room.create", "Create"),
+    ("room.delete", "Delete room"),
+    ("room.delete_title", "Delete room {name}?"),
+    ("room.delete_info", "Its msgs and pending invites are removed for all members."),
+    ("room.leave", "Leave room"),
+    ("room.not_found", "Room not found"),
+    ("link.title", "Open link?"),
+    ("link.trust", "Always trust {domain}"),
+    ("link.open", "Open"),
+
+    // -- Invites
+    ("invites.title", "Invitations"),
+    ("invites.none", "No pending invitations"),
+    ("invites.list", "Invites:"),
+    ("invites.from", "from {name}, {expires}"),
+    ("invites.expired", "expired"),
+    ("invites.expires_min", "expires in {n} min"),
+    ("invites.expires_h", "expires in {n} h"),
+    ("invites.expires_days.one", "expires in {n} day"),
+    ("invites.expires_days.other", "expires in {n} days"),
+    ("invites.accept", "Accept"),
+    ("invites.decline", "Decline"),
+    ("invites.revoke", "Revoke"),
+    ("invites.invite_selected", "Invite selected"),
+    ("invites.create_link", "Create link"),
+    ("invites.token", "Invite link token"),
+    ("invites.join", "Join"),
+
+    // -- Backend errors
+    ("backend.rejected", "Rejected: {reason}"),
+    ("backend.disconnected", "Backend disconnected"),
+    ("backend.password_not_stored", "Unable to store the password"),
+    ("backend.username_empty", "Username can't be empty"),
+    ("backend.password_short", "Password needs at least 4 characters"),
+    ("backend.username_taken", "Username already taken"),
+    ("backend.invalid_credentials", "Invalid username or password"),
+    ("backend.session_expired", "Session expired"),
+    ("backend.edit_other_account", "Can't edit other account"),
+    ("backend.room_name_empty", "Room name can't be empty"),
+    ("backend.owner_not_member", "Owner must be a room member"),
+    ("backend.owner_cant_leave", "Owner can't leave the room"),
+    ("backend.unknown_room", "Unknown room"),
+    ("backend.owner_only_invite", "Only owner can invite"),
+    ("backend.already_member", "Account is already a member"),
+    ("backend.invite_not_found", "Invite not found or expired"),
+    ("backend.invite_other_account", "Invite is not for this account"),
+    ("backend.room_gone", "Room no longer exists"),
+
+    // -- Msgs and editor
+    ("msgs.members.one", "{n} member, {online} online"),
+    ("msgs.members.other", "{n} members, {online} online"),
+    ("msgs.hidden", "Hidden message (muted user) - click to show"),
+    ("msg.reply", "Reply"),
+    ("msg.show_formatted", "Show formatted"),
+    ("msg.show_raw", "Show raw text"),
+    ("msg.mute_user", "Mute user"),
+    ("msg.unmute_user", "Unmute user"),
+    ("msg.block_user", "Block user"),
+    ("msg.unblock_user", "Unblock user"),
+    ("msg.original_missing", "Original msg is not available"),
+    ("typing.single", "{name} is typing…"),
+    ("typing.pair", "{name} and {other} are typing…"),
+    ("typing.several", "Several people are typing…"),
+    ("code.show_all", "Show all"),
+    ("code.show_less", "Show less"),
+    ("code.copy", "Copy"),
+    ("code.more_lines.one", "… {n} more line"),
+    ("code.more_lines.other", "… {n} more lines"),
+    ("editor.replying", "Replying to {name}: {text}"),
+    ("editor.editing", "Editing msg (send empty to cancel)"),
+    ("editor.send_enter", "Send with Enter"),
+    ("editor.send_ctrl_enter", "Send with Ctrl+Enter"),
+    ("editor.send", "Send"),
+    ("editor.attach", "Attach"),
+
+    // -- Dates
+    ("date.today", "Today"),
+    ("date.yesterday", "Yesterday"),
+    ("date.full", "{day} {month} {year}"),
+    ("date.yesterday_at", "Yesterday, {time}"),
+    ("date.full_at", "{date}, {time}"),
+    ("date.january", "January"),
+    ("date.february", "February"),
+    ("date.march", "March"),
+    ("date.april", "April"),
+    ("date.may", "May"),
+    ("date.june", "June"),
+    ("date.july", "July"),
+    ("date.august", "August"),
+    ("date.september", "September"),
+    ("date.october", "October"),
+    ("date.november", "November"),
+    ("date.december", "December"),
+
+    // -- Settings
+    ("settings.appearance", "Appearance"),
+    ("settings.language", "Language"),
+    ("settings.notifications", "Notifications"),
+    ("settings.keybindings", "Keybindings"),
+    ("settings.storage", "Storage"),
+    ("settings.privacy", "Privacy"),
+    ("settings.reset", "Reset to defaults"),
+    ("settings.theme", "Theme"),
+    ("theme.light", "Light"),
+    ("theme.dark", "Dark"),
+    ("theme.high_contrast", "High contrast"),
+    ("settings.themes_dir", "Own themes are read from the \"{dir}\" dir"),
+    ("settings.scale", "Scale"),
+    ("settings.rooms_order", "Rooms order"),
+    ("settings.sort_activity", "Recent activity"),
+    ("settings.sort_manual", "Manual"),
+    ("settings.unread_badges", "Show unread counters on the rooms list"),
+    ("settings.mentions_only", "Count only msgs mentioning me as unread"),
+    ("settings.send_typing", "Let others see when I'm typing"),
+    ("settings.editor_keys", "Msg editor (comma separated, eg. Ctrl+Enter)"),
+    ("settings.key_send", "Send"),
+    ("settings.key_new_line", "New line"),
+    ("settings.key_edit_last", "Edit last msg"),
+    ("settings.shortcuts", "Shortcuts"),
+    ("settings.config_path", "Settings: {path}"),
+    ("settings.drafts_path", "Drafts: {path}"),
+    ("settings.window", "Window: {width}×{height} at ({x}, {y})"),
+    ("settings.save_now", "Save now"),
+    ("settings.forget_drafts", "Forget saved drafts"),
+    ("settings.link_previews", "Show link previews"),
+    ("settings.trusted_domains", "Trusted domains (links open without asking)"),
+    ("settings.none", "None"),
+    ("settings.block_lists", "Block and mute lists…")
+];
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+use floem::prelude::*;
+use floem::reactive::{create_effect, Scope};
+use floem::views::Label;
+use tracing_lite::{debug, warn};
+
+use crate::config::{ChatConfig, Lang};
+
+
+pub mod en;
+pub mod pl;
+
+
+thread_local! {
+    /// Language of the UI texts, read by [t] (so labels follow its changes).
+    static LANG: RwSignal<Lang> = Scope::new().create_rw_signal(Lang::English);
+}
+
+static EN: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| en::CATALOG.iter().copied().collect());
+static PL: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| pl::CATALOG.iter().copied().collect());
+
+
+impl Lang {
+    pub const ALL: [Self; 2] = [Self::English, Self::Polish];
+
+    /// Name of the language in itself.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Lang::English => "English",
+            Lang::Polish => "Polski"
+        }
+    }
+
+    fn catalog(self) -> &'static HashMap<&'static str, &'static str> {
+        match self {
+            Lang::English => &EN,
+            Lang::Polish => &PL
+        }
+    }
+
+    /// Suffixes of the plural keys used by the language (eg. `invites.expires_days.few`).
+    pub fn plural_forms(self) -> &'static [&'static str] {
+        match self {
+            Lang::English => &["one", "other"],
+            Lang::Polish => &["one", "few", "many"]
+        }
+    }
+
+    /// Plural form used for the `count`.
+    pub fn plural_form(self, count: u64) -> &'static str {
+        match self {
+            Lang::English if count == 1 => "one",
+            Lang::English => "other",
+            Lang::Polish if count == 1 => "one",
+            Lang::Polish if matches!(count % 10, 2..=4) && !matches!(count % 100, 12..=14) => "few",
+            Lang::Polish => "many"
+        }
+    }
+}
+
+
+/// Text of the `key` in the `lang`, English one (or the key) if it is missing.
+pub fn translate(lang: Lang, key: &str) -> &str {
+    match lang.catalog().get(key).or_else(|| EN.get(key)) {
+        Some(text) => text,
+        None => { warn!("Missing translation: {key}"); key }
+    }
+}
+
+/// Replace the `{name}` placeholders with the `args`.
+pub fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut filled = text.to_string();
+    for (name, value) in args {
+        filled = filled.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    filled
+}
+
+
+// MARK: Current
+
+/// Current language (tracked).
+pub fn lang() -> Lang {
+    LANG.with(|lang| lang.get())
+}
+
+pub fn set_lang(lang: Lang) {
+    LANG.with(|current| current.set(lang));
+}
+
+/// Text of the `key` in the current language.
+pub fn t(key: &str) -> String {
+    translate(lang(), key).to_string()
+}
+
+/// Text of the `key` with the placeholders filled, eg. `("name", &name)` for `{name}`.
+pub fn t_with(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    fill(translate(lang(), key), args)
+}
+
+/// Plural form of the `key` matching the `count` (given as `{n}` with the `args`).
+pub fn t_plural(key: &str, count: u64, args: &[(&str, &dyn Display)]) -> String {
+    let lang = lang();
+    let text = translate(lang, &format!("{key}.{}", lang.plural_form(count)));
+    fill(text, &[&[("n", &count as &dyn Display)], args].concat())
+}
+
+/// Label with the text of the `key`, changed with the language.
+pub fn t_label(key: &'static str) -> Label {
+    label(move || t(key))
+}
+
+/// Keep the UI language in sync with the one chosen in the config.
+pub fn follow_config_lang(config: RwSignal<ChatConfig>) {
+    create_effect(move |_| {
+        let lang = config.with(|cf| cf.language);
+        debug!("== effect(lang): {lang:?}");
+        if LANG.with(|current| current.get_untracked() != lang) {
+            set_lang(lang);
+        }
+    });
+}
+
+
+/// Keys passed to the translation fns in the sources (plural ones with a `#` suffix).
+#[cfg(test)]
+fn keys_used_in(source: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let calls = [
+        ("t(\"", ""), ("t_with(\"", ""), ("t_label(\"", ""), ("t_plural(\"", "#"),
+        ("dialog_buttons(\"", ""), ("toggle(\"", ""), ("binding_input(\"", ""), ("Command::new(\"", ""),
+        ("Rejected(\"", "")
+    ];
+    for (call, suffix) in calls {
+        for (start, _) in source.match_indices(call) {
+            // -- Skip longer names ending with the call, eg. `set(`
+            if source[..start].ends_with(|c: char| c.is_alphanumeric() || c == '_') { continue }
+            let rest = &source[start + call.len()..];
+            let Some(end) = rest.find('"') else { continue };
+            let mut key = &rest[..end];
+            // -- Command is registered with its id first, the title key is next
+            if call.starts_with("Command") {
+                let Some(title) = rest[end + 1..].strip_prefix(", \"").and_then(|r| r.split('"').next()) else { continue };
+                key = title;
+            }
+            keys.push(format!("{key}{suffix}"));
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+fn source_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            source_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+
+#[test]
+fn catalogs_test() {
+    // -- Every key of any catalog is in all of them (plural ones with all forms of the language)
+    let base_key = |key: &str| match key.rsplit_once('.') {
+        Some((base, form)) if Lang::ALL.iter().any(|l| l.plural_forms().contains(&form)) => format!("{base}#"),
+        _ => key.to_string()
+    };
+    let mut keys: Vec<String> = Lang::ALL.iter().flat_map(|l| l.catalog().keys().map(|k| base_key(k))).collect();
+    // -- And every key used in the sources
+    let mut files = Vec::new();
+    source_files(std::path::Path::new("src"), &mut files);
+    for file in files.iter().filter(|f| !f.starts_with("src/i18n")) {
+        keys.extend(keys_used_in(&std::fs::read_to_string(file).unwrap()));
+    }
+    // -- And the keys built at runtime, which the sources don't show as literals
+    let dynamic: Vec<&str> = crate::util::MONTHS
+        .into_iter()
+        .chain(crate::config::SettingsSection::ALL.map(|s| s.key()))
+        .collect();
+    assert_eq!(dynamic.len(), 18);
+    keys.extend(dynamic.into_iter().map(String::from));
+    keys.sort();
+    keys.dedup();
+    let mut missing = Vec::new();
+    for lang in Lang::ALL {
+        for key in &keys {
+            match key.strip_suffix('#') {
+                Some(base) => missing.extend(lang.plural_forms()
+                    .iter()
+                    .map(|form| format!("{base}.{form}"))
+                    .filter(|key| !lang.catalog().contains_key(key.as_str()))
+                    .map(|key| format!("{lang:?}: {key}"))
+                ),
+                None if !lang.catalog().contains_key(key.as_str()) => missing.push(format!("{lang:?}: {key}")),
+                None => {}
+            }
+        }
+    }
+    assert!(missing.is_empty(), "Missing translations:\n{}", missing.join("\n"));
+    assert!(keys.len() > 100);
+    assert_eq!(en::CATALOG.len(), EN.len(), "Duplicated key in the English catalog");
+    assert_eq!(pl::CATALOG.len(), PL.len(), "Duplicated key in the Polish catalog");
+
+    // -- Plurals and placeholders
+    let forms = [1, 2, 5, 12, 22, 25, 104, 111].map(|n| Lang::Polish.plural_form(n));
+    assert_eq!(forms, ["one", "few", "many", "many", "few", "many", "few", "many"]);
+    assert_eq!(Lang::English.plural_form(0), "other");
+    assert_eq!(fill("{a} and {b}, {a}", &[("a", &1), ("b", &"two")]), "1 and two, 1");
+    assert_eq!(translate(Lang::Polish, "no.such.key"), "no.such.key");
+    set_lang(Lang::Polish);
+    assert_eq!(t_plural("invites.expires_days", 3, &[]), "wygasa za 3 dni");
+    set_lang(Lang::English);
+    assert_eq!(t_plural("invites.expires_days", 1, &[]), "expires in 1 day");
+}
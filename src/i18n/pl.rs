@@ -0,0 +1,248 @@
+/// Polish texts.
+pub const CATALOG: &[(&str, &str)] = &[
+    // -- Common
+    ("common.cancel", "Anuluj"),
+    ("common.close", "Zamknij"),
+    ("common.save", "Zapisz"),
+    ("common.remove", "Usuń"),
+    ("common.choose_avatar", "Wybierz awatar"),
+    ("common.remove_avatar", "Usuń awatar"),
+    ("common.unknown", "nieznany"),
+    ("common.read_failed", "Nie można odczytać {path}: {error}"),
+    ("avatar.unsupported", "Nieobsługiwany obraz: {error}"),
+    ("avatar.encode_failed", "Nie można zapisać awatara: {error}"),
+    ("config.unreadable", "Nie można odczytać pliku ustawień {path} ({error}), używane są domyślne. Plik zostanie zachowany, dopóki nie zmienisz ustawień."),
+    ("config.corrupt", "Plik ustawień {path} jest uszkodzony ({error}). Przeniesiono go do {backup}, używane są domyślne."),
+    ("config.corrupt_kept", "Plik ustawień {path} jest uszkodzony ({error}), używane są domyślne. Plik zostanie zachowany, dopóki nie zmienisz ustawień."),
+    ("config.newer", "Plik ustawień {path} pochodzi z nowszej wersji aplikacji ({version}). Przeniesiono go do {backup}, używane są domyślne."),
+    ("config.newer_kept", "Plik ustawień {path} pochodzi z nowszej wersji aplikacji ({version}), używane są domyślne. Plik zostanie zachowany, dopóki nie zmienisz ustawień."),
+    ("keys.missing_key", "Brak klawisza w skrócie: \"{binding}\""),
+    ("keys.unknown_modifier", "Nieznany modyfikator \"{modifier}\" w skrócie: \"{binding}\""),
+
+    // -- Commands
+    ("cmd.palette", "Paleta poleceń"),
+    ("cmd.settings", "Ustawienia"),
+    ("cmd.new_test_msg", "Nowa wiadomość testowa"),
+    ("cmd.new_test_msgs", "Nowe wiadomości testowe"),
+    ("cmd.new_room", "Nowy pokój"),
+    ("cmd.new_test_account", "Nowe konto testowe"),
+    ("cmd.edit_profile", "Edytuj profil"),
+    ("cmd.privacy", "Prywatność"),
+    ("cmd.room_settings", "Ustawienia pokoju"),
+    ("cmd.invitations", "Zaproszenia"),
+    ("cmd.load_more", "Wczytaj więcej wiadomości"),
+    ("cmd.add_account", "Dodaj konto"),
+    ("cmd.sign_out", "Wyloguj"),
+    ("cmd.zoom_in", "Powiększ"),
+    ("cmd.zoom_out", "Pomniejsz"),
+    ("cmd.zoom_reset", "Resetuj powiększenie"),
+    ("cmd.toggle_sidebar", "Pokaż lub ukryj listę pokoi"),
+    ("cmd.inspector", "Otwórz inspektor"),
+    ("toolbar.new", "Nowy"),
+    ("toolbar.edit", "Edycja"),
+    ("toolbar.about", "O programie"),
+    ("toolbar.load_more", "wczytaj więcej"),
+    ("palette.room", "Pokój"),
+    ("palette.person", "Osoba"),
+    ("palette.search", "Szukaj poleceń, pokoi i osób"),
+    ("palette.nothing_found", "Nic nie znaleziono"),
+
+    // -- Slash commands
+    ("slash.unknown", "Nieznane polecenie /{name} (wpisz / aby zobaczyć polecenia)"),
+    ("slash.no_room", "Nie wybrano pokoju"),
+    ("slash.not_signed_in", "Nie zalogowano"),
+    ("slash.me", "Opisz, co robisz"),
+    ("slash.me_usage", "Użycie: /me <czynność>"),
+    ("slash.shrug", "Dopisz ¯\\_(ツ)_/¯"),
+    ("slash.topic", "Zmień opis pokoju"),
+    ("slash.topic_owner_only", "Tylko właściciel pokoju może zmienić temat"),
+    ("slash.invite", "Zaproś konto do pokoju"),
+    ("slash.invite_usage", "Użycie: /invite @użytkownik"),
+    ("slash.no_such_user", "Nikt nie nazywa się @{name}"),
+    ("slash.already_member", "@{name} jest już w pokoju"),
+    ("slash.leave", "Opuść pokój"),
+    ("slash.nick", "Zmień swoją nazwę"),
+    ("slash.nick_usage", "Użycie: /nick <nazwa> (litery, cyfry, `_`, `-` i `.`)"),
+
+    // -- Login and profile
+    ("login.title", "Logowanie"),
+    ("login.username", "Nazwa użytkownika"),
+    ("login.password", "Hasło"),
+    ("login.remember", "Zapamiętaj mnie"),
+    ("login.register", "Utwórz konto"),
+    ("login.sign_in", "Zaloguj"),
+    ("profile.title", "Profil"),
+    ("profile.select_avatar", "Wybierz awatar"),
+    ("profile.status", "Status (opcjonalnie)"),
+    ("presence.online", "dostępny"),
+    ("presence.away", "zaraz wracam"),
+    ("presence.offline", "niedostępny"),
+    ("presence.just_now", "widziany przed chwilą"),
+    ("presence.min_ago", "widziany {n} min temu"),
+    ("presence.h_ago", "widziany {n} godz. temu"),
+    ("presence.days_ago.one", "widziany {n} dzień temu"),
+    ("presence.days_ago.few", "widziany {n} dni temu"),
+    ("presence.days_ago.many", "widziany {n} dni temu"),
+    ("privacy.title", "Prywatność"),
+    ("privacy.blocked", "Zablokowani (wiadomości i reakcje są ukryte):"),
+    ("privacy.unblock", "Odblokuj"),
+    ("privacy.muted", "Wyciszeni (wiadomości są zwinięte):"),
+    ("privacy.unmute", "Wyłącz wyciszenie"),
+    ("privacy.accounts", "Konta:"),
+    ("privacy.mute_selected", "Wycisz zaznaczone"),
+    ("privacy.block_selected", "Zablokuj zaznaczone"),
+    ("privacy.nobody", "Nikt"),
+
+    // -- Rooms
+    ("rooms.sort_activity", "Kolejność: aktywność"),
+    ("rooms.sort_manual", "Kolejność: ręczna"),
+    ("rooms.draft", "Szkic: {text}"),
+    ("rooms.no_msgs", "brak wiadomości.."),
+    ("rooms.pin", "Przypnij na górze"),
+    ("rooms.unpin", "Odepnij"),
+    ("rooms.move_up", "Przesuń w górę"),
+    ("rooms.move_down", "Przesuń w dół"),
+    ("room.new_title", "Nowy pokój"),
+    ("room.settings_title", "Ustawienia pokoju"),
+    ("room.select_avatar", "Wybierz awatar pokoju"),
+    ("room.name", "Nazwa pokoju"),
+    ("room.description", "Opis (opcjonalnie)"),
+    ("room.no_description", "Brak opisu"),
+    ("room.members", "Członkowie:"),
+    ("room.add_members", "Dodaj członków:"),
+    ("room.add_selected", "Dodaj zaznaczonych"),
+    ("room.owner", "właściciel"),
+    ("room.owner_name", "{name} (właściciel)"),
+    ("room.make_owner", "Przekaż własność"),
+    ("room.create", "Utwórz"),
+    ("room.delete", "Usuń pokój"),
+    ("room.delete_title", "Usunąć pokój {name}?"),
+    ("room.delete_info", "Jego wiadomości i oczekujące zaproszenia zostaną usunięte dla wszystkich członków."),
+    ("room.leave", "Opuść pokój"),
+    ("room.not_found", "Nie znaleziono pokoju"),
+    ("link.title", "Otworzyć link?"),
+    ("link.trust", "Zawsze ufaj {domain}"),
+    ("link.open", "Otwórz"),
+
+    // -- Invites
+    ("invites.title", "Zaproszenia"),
+    ("invites.none", "Brak oczekujących zaproszeń"),
+    ("invites.list", "Zaproszenia:"),
+    ("invites.from", "od {name}, {expires}"),
+    ("invites.expired", "wygasło"),
+    ("invites.expires_min", "wygasa za {n} min"),
+    ("invites.expires_h", "wygasa za {n} godz."),
+    ("invites.expires_days.one", "wygasa za {n} dzień"),
+    ("invites.expires_days.few", "wygasa za {n} dni"),
+    ("invites.expires_days.many", "wygasa za {n} dni"),
+    ("invites.accept", "Przyjmij"),
+    ("invites.decline", "Odrzuć"),
+    ("invites.revoke", "Unieważnij"),
+    ("invites.invite_selected", "Zaproś zaznaczonych"),
+    ("invites.create_link", "Utwórz link"),
+    ("invites.token", "Token linku z zaproszeniem"),
+    ("invites.join", "Dołącz"),
+
+    // -- Backend errors
+    ("backend.rejected", "Odrzucono: {reason}"),
+    ("backend.disconnected", "Brak połączenia z serwerem"),
+    ("backend.password_not_stored", "Nie udało się zapisać hasła"),
+    ("backend.username_empty", "Nazwa użytkownika nie może być pusta"),
+    ("backend.password_short", "Hasło musi mieć co najmniej 4 znaki"),
+    ("backend.username_taken", "Nazwa użytkownika jest już zajęta"),
+    ("backend.invalid_credentials", "Nieprawidłowa nazwa użytkownika lub hasło"),
+    ("backend.session_expired", "Sesja wygasła"),
+    ("backend.edit_other_account", "Nie można edytować cudzego konta"),
+    ("backend.room_name_empty", "Nazwa pokoju nie może być pusta"),
+    ("backend.owner_not_member", "Właściciel musi być członkiem pokoju"),
+    ("backend.owner_cant_leave", "Właściciel nie może opuścić pokoju"),
+    ("backend.unknown_room", "Nieznany pokój"),
+    ("backend.owner_only_invite", "Tylko właściciel może zapraszać"),
+    ("backend.already_member", "Konto jest już członkiem pokoju"),
+    ("backend.invite_not_found", "Nie znaleziono zaproszenia lub wygasło"),
+    ("backend.invite_other_account", "Zaproszenie nie jest dla tego konta"),
+    ("backend.room_gone", "Pokój już nie istnieje"),
+
+    // -- Msgs and editor
+    ("msgs.members.one", "{n} członek, {online} dostępnych"),
+    ("msgs.members.few", "{n} członków, {online} dostępnych"),
+    ("msgs.members.many", "{n} członków, {online} dostępnych"),
+    ("msgs.hidden", "Ukryta wiadomość (wyciszony użytkownik) - kliknij, aby pokazać"),
+    ("msg.reply", "Odpowiedz"),
+    ("msg.show_formatted", "Pokaż sformatowany"),
+    ("msg.show_raw", "Pokaż surowy tekst"),
+    ("msg.mute_user", "Wycisz użytkownika"),
+    ("msg.unmute_user", "Wyłącz wyciszenie użytkownika"),
+    ("msg.block_user", "Zablokuj użytkownika"),
+    ("msg.unblock_user", "Odblokuj użytkownika"),
+    ("msg.original_missing", "Oryginalna wiadomość jest niedostępna"),
+    ("typing.single", "{name} pisze…"),
+    ("typing.pair", "{name} i {other} piszą…"),
+    ("typing.several", "Kilka osób pisze…"),
+    ("code.show_all", "Pokaż wszystko"),
+    ("code.show_less", "Pokaż mniej"),
+    ("code.copy", "Kopiuj"),
+    ("code.more_lines.one", "… jeszcze {n} linia"),
+    ("code.more_lines.few", "… jeszcze {n} linie"),
+    ("code.more_lines.many", "… jeszcze {n} linii"),
+    ("editor.replying", "Odpowiedź do {name}: {text}"),
+    ("editor.editing", "Edycja wiadomości (wyślij pustą, aby anulować)"),
+    ("editor.send_enter", "Wysyłaj Enterem"),
+    ("editor.send_ctrl_enter", "Wysyłaj przez Ctrl+Enter"),
+    ("editor.send", "Wyślij"),
+    ("editor.attach", "Załącz"),
+
+    // -- Dates (months in the genitive, as in "3 maja 2024")
+    ("date.today", "Dzisiaj"),
+    ("date.yesterday", "Wczoraj"),
+    ("date.full", "{day} {month} {year}"),
+    ("date.yesterday_at", "Wczoraj, {time}"),
+    ("date.full_at", "{date}, {time}"),
+    ("date.january", "stycznia"),
+    ("date.february", "lutego"),
+    ("date.march", "marca"),
+    ("date.april", "kwietnia"),
+    ("date.may", "maja"),
+    ("date.june", "czerwca"),
+    ("date.july", "lipca"),
+    ("date.august", "sierpnia"),
+    ("date.september", "września"),
+    ("date.october", "października"),
+    ("date.november", "listopada"),
+    ("date.december", "grudnia"),
+
+    // -- Settings
+    ("settings.appearance", "Wygląd"),
+    ("settings.language", "Język"),
+    ("settings.notifications", "Powiadomienia"),
+    ("settings.keybindings", "Skróty klawiszowe"),
+    ("settings.storage", "Pliki"),
+    ("settings.privacy", "Prywatność"),
+    ("settings.reset", "Przywróć domyślne"),
+    ("settings.theme", "Motyw"),
+    ("theme.light", "Jasny"),
+    ("theme.dark", "Ciemny"),
+    ("theme.high_contrast", "Wysoki kontrast"),
+    ("settings.themes_dir", "Własne motywy są wczytywane z katalogu \"{dir}\""),
+    ("settings.scale", "Skala"),
+    ("settings.rooms_order", "Kolejność pokoi"),
+    ("settings.sort_activity", "Ostatnia aktywność"),
+    ("settings.sort_manual", "Ręczna"),
+    ("settings.unread_badges", "Pokazuj liczniki nieprzeczytanych na liście pokoi"),
+    ("settings.mentions_only", "Licz jako nieprzeczytane tylko wiadomości z moją wzmianką"),
+    ("settings.send_typing", "Pokazuj innym, że piszę"),
+    ("settings.editor_keys", "Edytor wiadomości (po przecinku, np. Ctrl+Enter)"),
+    ("settings.key_send", "Wyślij"),
+    ("settings.key_new_line", "Nowa linia"),
+    ("settings.key_edit_last", "Edytuj ostatnią wiadomość"),
+    ("settings.shortcuts", "Skróty"),
+    ("settings.config_path", "Ustawienia: {path}"),
+    ("settings.drafts_path", "Szkice: {path}"),
+    ("settings.window", "Okno: {width}×{height} w ({x}, {y})"),
+    ("settings.save_now", "Zapisz teraz"),
+    ("settings.forget_drafts", "Zapomnij zapisane szkice"),
+    ("settings.link_previews", "Pokazuj podglądy linków"),
+    ("settings.trusted_domains", "Zaufane domeny (linki otwierają się bez pytania)"),
+    ("settings.none", "Brak"),
+    ("settings.block_lists", "Listy zablokowanych i wyciszonych…")
+];
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::process::Command;
+
+use tracing_lite::{error, info};
+
+
+/// Chars which end the url when found at its end.
+const TRAILING_PUNCT: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '"'];
+
+
+/// Byte ranges of the urls (`http://`, `https://` and `www.`) in the text.
+pub fn find_links(text: &str) -> Vec<Range<usize>> {
+    let mut links = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = next_link_start(text, pos) {
+        let len = text[start..]
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '`'))
+            .unwrap_or(text.len() - start);
+        let end = start + trim_link(&text[start..start + len]).len();
+        // -- Bare scheme or `www.` is not a link
+        if text[start..end].split_once("://").map_or(end - start > 4, |(_, rest)| !rest.is_empty()) {
+            links.push(start..end);
+        }
+        pos = start + len.max(1);
+    }
+    links
+}
+
+fn next_link_start(text: &str, from: usize) -> Option<usize> {
+    ["https://", "http://", "www."]
+        .into_iter()
+        .filter_map(|prefix| {
+            let mut search = from;
+            // -- Prefix must start a word (not `awww.` or `xhttp://`)
+            while let Some(i) = text[search..].find(prefix).map(|i| search + i) {
+                if !text[..i].chars().next_back().is_some_and(char::is_alphanumeric) {
+                    return Some(i)
+                }
+                search = i + prefix.len();
+            }
+            None
+        })
+        .min()
+}
+
+/// Strip punctuation ending the sentence and unbalanced closing brackets.
+fn trim_link(mut link: &str) -> &str {
+    loop {
+        let trimmed = link.trim_end_matches(TRAILING_PUNCT);
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(t) if t.matches('(').count() < trimmed.matches(')').count() => t,
+            _ => trimmed
+        };
+        if trimmed.len() == link.len() { return link }
+        link = trimmed;
+    }
+}
+
+/// Url which can be opened (`www.` links get a scheme).
+pub fn normalize_url(link: &str) -> String {
+    match link.contains("://") {
+        true => link.into(),
+        false => format!("https://{link}")
+    }
+}
+
+/// Lowercase host of the url, without `www.` and port.
+pub fn domain_of(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest
+        .split(['/', '?', '#'])
+        .next()?
+        .rsplit('@')
+        .next()?
+        .split(':')
+        .next()?
+        .to_lowercase();
+    let host = host.strip_prefix("www.").map(String::from).unwrap_or(host);
+    (!host.is_empty()).then_some(host)
+}
+
+/// Check if the url is a web link (only those are handed to the system).
+fn is_web_url(url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, rest)| {
+        matches!(scheme.to_lowercase().as_str(), "http" | "https") && !rest.is_empty()
+    })
+}
+
+/// Open the url in the system browser.  
+/// Url is passed as a single argument to the opener (never through a shell).
+pub fn open_in_browser(url: &str) {
+    info!("fn: open_in_browser: {url}");
+    if !is_web_url(url) {
+        error!("Refused to open {url}: not a web link");
+        return
+    }
+    let result = if cfg!(target_os = "windows") {
+        Command::new("rundll32").args(["url.dll,FileProtocolHandler", url]).spawn()
+    } else if cfg!(target_os = "macos") {
+        Command::new("open").arg(url).spawn()
+    } else {
+        Command::new("xdg-open").arg(url).spawn()
+    };
+    if let Err(e) = result {
+        error!("Failed to open {url}: {e}");
+    }
+}
+
+
+// MARK: Previews
+
+/// Data shown on the link preview card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkPreview {
+    pub url: String,
+    pub site: String,
+    pub title: String,
+    pub description: Option<String>
+}
+
+/// Source of the link previews. Called while building msg views,
+/// so implementations should answer quickly (and cache slow lookups).
+pub trait PreviewFetcher {
+    fn preview(&self, url: &str) -> Option<LinkPreview>;
+}
+
+/// Previews disabled.
+#[derive(Debug, Default)]
+pub struct NoPreviews;
+
+impl PreviewFetcher for NoPreviews {
+    fn preview(&self, _url: &str) -> Option<LinkPreview> {
+        None
+    }
+}
+
+/// Offline previews made from the url itself: site and last path segment.
+#[derive(Debug, Default)]
+pub struct LocalPreviews;
+
+impl PreviewFetcher for LocalPreviews {
+    fn preview(&self, url: &str) -> Option<LinkPreview> {
+        let site = domain_of(url)?;
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let path = rest.split(['?', '#']).next().unwrap_or_default();
+        let title = path
+            .trim_end_matches('/')
+            .rsplit_once('/')
+            .map(|(_, last)| last.replace(['-', '_'], " "))
+            .filter(|last| !last.is_empty())
+            .unwrap_or_else(|| site.clone());
+        Some(LinkPreview { url: url.into(), site, title, description: None })
+    }
+}
+
+/// Fixed previews (for tests and demos), K: url.
+#[derive(Debug, Default)]
+pub struct StubPreviews(pub HashMap<String, LinkPreview>);
+
+impl PreviewFetcher for StubPreviews {
+    fn preview(&self, url: &str) -> Option<LinkPreview> {
+        self.0.get(url).cloned()
+    }
+}
+
+
+#[test]
+fn links_test() {
+    let found = |text: &str| find_links(text).into_iter().map(|r| text[r].to_string()).collect::<Vec<_>>();
+    assert_eq!(found("see https://example.com/a?b=1, and www.rust-lang.org."), vec!(
+        "https://example.com/a?b=1", "www.rust-lang.org"
+    ));
+    assert_eq!(found("(https://en.wikipedia.org/wiki/Rust_(language))"), vec!(
+        "https://en.wikipedia.org/wiki/Rust_(language)"
+    ));
+    assert_eq!(found("no links: http:// awww.x https www."), Vec::<String>::new());
+    assert_eq!(found("żółw http://żółw.pl!"), vec!("http://żółw.pl"));
+
+    assert_eq!(normalize_url("www.rust-lang.org"), "https://www.rust-lang.org");
+    assert_eq!(domain_of("https://user@WWW.Example.com:8080/path").as_deref(), Some("example.com"));
+    assert_eq!(domain_of("https://").as_deref(), None);
+    assert!(is_web_url("HTTPS://example.com/?a=1&b=2"));
+    assert!(!is_web_url("file:///etc/passwd"));
+    assert!(!is_web_url("https://"));
+
+    let preview = LocalPreviews.preview("https://github.com/lapce/floem/pull-requests/").unwrap();
+    assert_eq!((preview.site.as_str(), preview.title.as_str()), ("github.com", "pull requests"));
+    assert_eq!(LocalPreviews.preview("https://docs.rs").unwrap().title, "docs.rs");
+    assert_eq!(NoPreviews.preview("https://docs.rs"), None);
+}
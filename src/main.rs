@@ -1,23 +1,41 @@
 #![allow(unused)]
 
+use commands::register_default_commands;
 use config::launch_with_config;
+use drafts::flush_drafts;
+use floem::event::EventListener;
 use floem::prelude::*;
 use floem::reactive::{provide_context, Trigger};
-use floem::taffy::prelude::minmax;
-use floem::taffy::{
-    LengthPercentage, MaxTrackSizingFunction,
-    MinTrackSizingFunction, TrackSizingFunction
-};
 use tracing_lite::{Level, Subscriber};
 use ulid::Ulid;
 use util::Id;
 use view_data::editor::{editor_toolbar_view, EditorViewData};
+use view_data::msg::MsgViewData;
+use theme::colors;
 use view_data::MsgEvent;
+use views::dialogs::{dialog_view, Dialog};
+use view_data::session::APP;
+use views::login::{login_view, resume_stored_session};
 use views::msgs::msgs_view;
+use views::panes::{editor_splitter, sidebar_splitter, Panes};
+use views::presence::start_presence_polling;
 use views::rooms::rooms_view;
 use views::toolbar::toolbar_view;
 
 pub mod common;
+pub mod auth;
+pub mod avatar;
+pub mod backend;
+pub mod commands;
+pub mod drafts;
+pub mod highlight;
+pub mod i18n;
+pub mod links;
+pub mod markup;
+pub mod mentions;
+pub mod slash;
+pub mod storage;
+pub mod theme;
 pub mod view_data;
 pub mod config;
 pub mod cont {
@@ -27,18 +45,23 @@ pub mod cont {
 }
 pub mod util;
 pub mod views {
+    pub mod dialogs;
+    pub mod invites;
+    pub mod login;
     pub mod msgs;
+    pub mod palette;
+    pub mod panes;
+    pub mod presence;
+    pub mod privacy;
+    pub mod profile;
+    pub mod rich_text;
     pub mod rooms;
+    pub mod room_settings;
+    pub mod settings;
     pub mod toolbar;
 }
 pub mod chunks;
 
-pub const SIDEBAR_WIDTH: f64 = 150.0;
-pub const TOPBAR_HEIGHT: f64 = 35.0;
-pub const BG: Color = Color::rgb8(180, 180, 180);
-pub const BUTTON_HOVER: Color = Color::rgb8(250, 250, 0);
-pub const BUTTON_ACTIVE: Color = Color::rgb8(250, 0, 0);
-
 
 // MARK: MAIN
 
@@ -51,74 +74,56 @@ fn main() {
     provide_context(RwSignal::new(None::<Ulid>));   // New room id editor signal
     provide_context(Trigger::new());                // Msg send signal
     provide_context(RwSignal::new(false));          // Load more signal
+    provide_context(RwSignal::new(Dialog::None));   // Opened dialog
+    provide_context(RwSignal::new(None::<MsgViewData>)); // Msg replied to
     
     launch_with_config(app_view)
 }
 
 
+/// Shows login screen until there is an authenticated session.
 fn app_view() -> impl IntoView {
+    resume_stored_session();
+    start_presence_polling();
+    let token = APP.with(|app| app.token);
+    dyn_container(
+        move || token.with(|t| t.is_some()),
+        |signed_in| match signed_in {
+            true => main_view().into_any(),
+            false => login_view().into_any()
+        }
+    )
+        // -- Drafts typed just before closing are still waiting for their write
+        .on_event_cont(EventListener::WindowClosed, |_| APP.with(|app| flush_drafts(app.drafts)))
+        // -- Any input keeps the user shown as active (typing marks it too)
+        .on_event_cont(EventListener::PointerMove, |_| APP.with(|app| app.mark_active()))
+        .on_event_cont(EventListener::PointerDown, |_| APP.with(|app| app.mark_active()))
+        .on_event_cont(EventListener::KeyDown, |_| APP.with(|app| app.mark_active()))
+        .on_event_cont(EventListener::WindowGotFocus, |_| APP.with(|app| app.mark_active()))
+        .style(|s| s.size_full().background(colors().window_bg).color(colors().text))
+}
+
+
+fn main_view() -> impl IntoView {
+    // -- Provided first, commands and views read it
+    let panes = Panes::provide();
+    register_default_commands();
     stack((
         toolbar_view(),
         rooms_view(),
         msgs_view(),
         EditorViewData::new(), // OR: text_editor_view(send_msg),
         editor_toolbar_view(),
+        sidebar_splitter(),
+        editor_splitter(),
+        dialog_view(),
     ))
         .debug_name("grid container")
-        .style(|s| s
-            .grid()
-            .grid_template_columns(vec![
-                TrackSizingFunction::Single(
-                    minmax(
-                        MinTrackSizingFunction::Fixed(LengthPercentage::Length(200.0)),
-                        MaxTrackSizingFunction::Fraction(0.)
-                    ),
-                ),
-                TrackSizingFunction::Single(
-                    minmax(
-                        MinTrackSizingFunction::Auto,
-                        MaxTrackSizingFunction::Auto
-                    ),
-                ),
-                TrackSizingFunction::Single(
-                    minmax(
-                        MinTrackSizingFunction::Fixed(LengthPercentage::Length(60.0)),
-                        MaxTrackSizingFunction::Fraction(0.)
-                    ),
-                )
-            ])
-            .grid_template_rows(Vec::from([
-                TrackSizingFunction::Single(
-                    minmax(
-                        MinTrackSizingFunction::Fixed(LengthPercentage::Length(40.0)),
-                        MaxTrackSizingFunction::Fraction(0.)
-                    )
-                ),
-                    TrackSizingFunction::Single(
-                        minmax(
-                            MinTrackSizingFunction::Auto,
-                            MaxTrackSizingFunction::Auto
-                    )
-                ),
-                    TrackSizingFunction::Single(
-                        minmax(
-                            MinTrackSizingFunction::Auto,
-                            MaxTrackSizingFunction::Auto
-                    )
-                ),
-                    TrackSizingFunction::Single(
-                        minmax(
-                            MinTrackSizingFunction::Fixed(LengthPercentage::Length(80.0)),
-                            MaxTrackSizingFunction::FitContent(LengthPercentage::Length(100.))
-                    )
-                )
-            ]))
-            .column_gap(5.)
-            .row_gap(5.)
+        .on_resize(move |rect| panes.size.set(rect.size()))
+        .style(move |s| panes.grid_style(s)
             .size_full()
-            .padding(5.)
             .border(2.)
-            .border_color(Color::BLACK)
+            .border_color(colors().border)
             .border_radius(5.)
         )
-}
\ No newline at end of file
+}
@@ -0,0 +1,214 @@
+use std::ops::Range;
+
+
+/// Inline style of the text span.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub strike: bool,
+    pub code: bool
+}
+
+impl SpanStyle {
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Styled part of the source text; text between spans are markers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// Byte range in the source text.
+    pub range: Range<usize>,
+    pub style: SpanStyle
+}
+
+/// Block of the msg text (inline markup is not parsed yet).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// Consecutive plain lines.
+    Text(String),
+    /// Consecutive `> ` lines (without the markers).
+    Quote(String),
+    /// List item with its bullet (`•` or `N.`).
+    Item { bullet: String, text: String },
+    /// Fenced code with optional language name.
+    Code { lang: Option<String>, code: String }
+}
+
+
+// MARK: Blocks
+
+/// Split msg text into blocks: ```` ``` ```` fenced code, `> ` quotes,
+/// `- ` / `1. ` list items and plain text.
+pub fn parse_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let fence = line.trim_start().strip_prefix("```");
+        // -- Code closed in the same line (```code```)
+        if let Some(code) = fence.and_then(|rest| rest.trim_end().strip_suffix("```")).filter(|c| !c.trim().is_empty()) {
+            blocks.push(Block::Code { lang: None, code: code.into() });
+        // -- Fenced code (unclosed fence runs to the end), language name can't contain backticks
+        } else if let Some(lang) = fence.filter(|rest| !rest.contains('`')) {
+            let lang = Some(lang.trim()).filter(|l| !l.is_empty()).map(String::from);
+            let code = lines
+                .by_ref()
+                .take_while(|l| l.trim() != "```")
+                .collect::<Vec<_>>()
+                .join("\n");
+            blocks.push(Block::Code { lang, code });
+        } else if let Some(quote) = strip_quote(line) {
+            match blocks.last_mut() {
+                Some(Block::Quote(text)) => { text.push('\n'); text.push_str(quote) },
+                _ => blocks.push(Block::Quote(quote.into()))
+            }
+        } else if let Some((bullet, text)) = list_item(line) {
+            blocks.push(Block::Item { bullet, text: text.into() });
+        } else {
+            match blocks.last_mut() {
+                Some(Block::Text(text)) => { text.push('\n'); text.push_str(line) },
+                _ => blocks.push(Block::Text(line.into()))
+            }
+        }
+    }
+    blocks
+}
+
+fn strip_quote(line: &str) -> Option<&str> {
+    line.strip_prefix("> ").or_else(|| (line == ">").then_some(""))
+}
+
+/// Bullet and text of the list item line.
+pub fn list_item(line: &str) -> Option<(String, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(marker) {
+            return Some(("•".into(), text))
+        }
+    }
+    let (num, text) = line.split_once(". ")?;
+    (!num.is_empty() && num.len() < 4 && num.chars().all(|c| c.is_ascii_digit()))
+        .then(|| (format!("{num}."), text))
+}
+
+
+// MARK: Inline
+
+/// Parse inline markup: `**bold**`, `*italic*` / `_italic_`, `~~strike~~`
+/// and `` `code` ``. Returned spans are in order and cover all the text
+/// except of the markers. Markup does not continue past the end of the line.
+pub fn parse_inline(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    parse_inline_into(text, 0..text.len(), SpanStyle::default(), &mut spans);
+    spans
+}
+
+fn parse_inline_into(text: &str, range: Range<usize>, style: SpanStyle, spans: &mut Vec<Span>) {
+    let push = |range: Range<usize>, style: SpanStyle, spans: &mut Vec<Span>| {
+        if !range.is_empty() { spans.push(Span { range, style }) }
+    };
+    let mut plain_start = range.start;
+    let mut i = range.start;
+    while i < range.end {
+        if let Some(marker) = marker_at(text, i) {
+            let inner_start = i + marker.len();
+            if let Some(close) = find_closing(text, inner_start, range.end, marker) {
+                push(plain_start..i, style, spans);
+                let inner = inner_start..close;
+                match marker {
+                    "`" => push(inner, SpanStyle { code: true, ..style }, spans),
+                    "**" => parse_inline_into(text, inner, SpanStyle { bold: true, ..style }, spans),
+                    "~~" => parse_inline_into(text, inner, SpanStyle { strike: true, ..style }, spans),
+                    _ => parse_inline_into(text, inner, SpanStyle { italic: true, ..style }, spans)
+                }
+                i = close + marker.len();
+                plain_start = i;
+                continue
+            }
+        }
+        i += text[i..].chars().next().map_or(1, char::len_utf8);
+    }
+    push(plain_start..range.end, style, spans);
+}
+
+/// Opening marker at the byte `i` (if any).
+fn marker_at(text: &str, i: usize) -> Option<&'static str> {
+    let rest = &text[i..];
+    let marker = ["`", "**", "~~", "*", "_"].into_iter().find(|m| rest.starts_with(m))?;
+    // -- `_` inside of words (snake_case) or after `\` is not a marker
+    if marker == "_" && text[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '\\') {
+        return None
+    }
+    Some(marker)
+}
+
+/// Position of the closing `marker` after non empty content, within the same line.
+fn find_closing(text: &str, from: usize, end: usize, marker: &str) -> Option<usize> {
+    let line_end = text[from..end].find('\n').map_or(end, |n| from + n);
+    let mut search = from;
+    while let Some(pos) = text[search..line_end].find(marker).map(|p| search + p) {
+        let after = pos + marker.len();
+        let single_star = marker == "*" && (text[after..].starts_with('*') || text[..pos].ends_with('*'));
+        let in_word = marker == "_" && text[after..].chars().next().is_some_and(char::is_alphanumeric);
+        if pos > from && !single_star && !in_word {
+            return Some(pos)
+        }
+        search = after;
+    }
+    None
+}
+
+
+#[test]
+fn markup_inline_test() {
+    let show = |text: &str| parse_inline(text)
+        .into_iter()
+        .map(|s| {
+            let SpanStyle { bold, italic, strike, code } = s.style;
+            let flags: String = [(bold, 'b'), (italic, 'i'), (strike, 's'), (code, 'c')]
+                .into_iter()
+                .filter_map(|(on, f)| on.then_some(f))
+                .collect();
+            format!("{}[{flags}]", &text[s.range])
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    assert_eq!(show("plain text"), "plain text[]");
+    assert_eq!(show("a **bold** b"), "a [] bold[b]  b[]");
+    assert_eq!(show("*it **both** it*"), "it [i] both[bi]  it[i]");
+    assert_eq!(show("~~gone~~ and `**raw**`"), "gone[s]  and [] **raw**[c]");
+    assert_eq!(show("_under_ but snake_case_name"), "under[i]  but snake_case_name[]");
+    // -- Unclosed or empty markers are plain text
+    assert_eq!(show("2 * 3 = 6, **"), "2 * 3 = 6, **[]");
+    assert_eq!(show("****"), "****[]");
+    // -- Markup ends with the line
+    assert_eq!(show("**a\nb**"), "**a\nb**[]");
+    assert_eq!(show("zażółć *gęślą*"), "zażółć [] gęślą[i]");
+    assert_eq!(show("¯\\_(ツ)_/¯"), "¯\\_(ツ)_/¯[]");
+}
+
+#[test]
+fn markup_blocks_test() {
+    let text = "Hi\nthere\n> quoted\n> more\n- one\n2. two\n```rust\nfn main() {}\n\n```\nbye";
+    assert_eq!(parse_blocks(text), vec!(
+        Block::Text("Hi\nthere".into()),
+        Block::Quote("quoted\nmore".into()),
+        Block::Item { bullet: "•".into(), text: "one".into() },
+        Block::Item { bullet: "2.".into(), text: "two".into() },
+        Block::Code { lang: Some("rust".into()), code: "fn main() {}\n".into() },
+        Block::Text("bye".into())
+    ));
+    // -- Unclosed fence
+    assert_eq!(parse_blocks("```\ncode"), vec!(Block::Code { lang: None, code: "code".into() }));
+    // -- Fence closed in the same line
+    assert_eq!(parse_blocks("```let a = 1;```\nafter"), vec!(
+        Block::Code { lang: None, code: "let a = 1;".into() },
+        Block::Text("after".into())
+    ));
+    // -- Backticks after the fence are not a language name
+    assert_eq!(parse_blocks("```a`b\ntext"), vec!(Block::Text("```a`b\ntext".into())));
+    assert_eq!(parse_blocks("``````\ntext"), vec!(Block::Text("``````\ntext".into())));
+    assert_eq!(list_item("2024. year"), None);
+    assert_eq!(list_item("1.5 not a list"), None);
+}
@@ -0,0 +1,128 @@
+use std::ops::Range;
+
+use ulid::Ulid;
+
+use crate::cont::acc::Account;
+use crate::util::{Id, Tb};
+
+
+/// Max number of members suggested in the autocomplete popup.
+pub const MAX_SUGGESTIONS: usize = 5;
+
+
+/// Mention as stored in the msg text: `<@account:ULID>`.
+pub fn mention_token(acc: &Id) -> String {
+    format!("<@{acc}>")
+}
+
+/// Byte ranges of the mention tokens in the text with mentioned accounts.
+pub fn parse_mentions(text: &str) -> Vec<(Range<usize>, Id)> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = text[pos..].find("<@").map(|i| pos + i) {
+        let Some(end) = text[start..].find('>').map(|i| start + i + 1) else { break };
+        match text[start + 2..end - 1].parse::<Id>() {
+            Ok(id) if id.tb == Tb::Acc => { found.push((start..end, id)); pos = end },
+            _ => pos = start + 2
+        }
+    }
+    found
+}
+
+/// Accounts mentioned in the text.
+pub fn mentions_of(text: &str) -> Vec<Ulid> {
+    parse_mentions(text).into_iter().map(|(_, id)| id.id).collect()
+}
+
+/// Text with the mention tokens replaced by `@username`.
+pub fn display_text(text: &str, name_of: impl Fn(&Ulid) -> String) -> String {
+    let mut shown = String::with_capacity(text.len());
+    let mut pos = 0;
+    for (range, id) in parse_mentions(text) {
+        shown.push_str(&text[pos..range.start]);
+        shown.push('@');
+        shown.push_str(&name_of(&id.id));
+        pos = range.end;
+    }
+    shown.push_str(&text[pos..]);
+    shown
+}
+
+/// Replace `@username` of the `known` mentions (picked in the editor) with the tokens.
+pub fn encode_mentions(text: &str, known: &[(String, Id)]) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    let mut rest = text;
+    'outer: while let Some(at) = rest.find('@') {
+        let is_word_start = !rest[..at].chars().next_back().is_some_and(is_name_char);
+        if is_word_start {
+            // -- Longest name first, so `@ann` does not take `@anna`
+            let mut candidates: Vec<_> = known.iter().collect();
+            candidates.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+            for (name, id) in candidates {
+                let after = &rest[at + 1..];
+                if after.starts_with(name.as_str()) && !after[name.len()..].chars().next().is_some_and(is_name_char) {
+                    encoded.push_str(&rest[..at]);
+                    encoded.push_str(&mention_token(id));
+                    rest = &after[name.len()..];
+                    continue 'outer
+                }
+            }
+        }
+        encoded.push_str(&rest[..=at]);
+        rest = &rest[at + 1..];
+    }
+    encoded.push_str(rest);
+    encoded
+}
+
+/// Mention being typed: position of the `@` and the query after it,
+/// found in the text before the cursor.
+pub fn mention_query(before_cursor: &str) -> Option<(usize, &str)> {
+    let at = before_cursor.rfind('@')?;
+    let query = &before_cursor[at + 1..];
+    let is_word_start = !before_cursor[..at].chars().next_back().is_some_and(is_name_char);
+    (is_word_start && query.chars().all(is_name_char)).then_some((at, query))
+}
+
+/// Accounts matching the query: username prefix first, then containing it.
+pub fn match_members<'a>(members: impl IntoIterator<Item = &'a Account>, query: &str) -> Vec<&'a Account> {
+    let query = query.to_lowercase();
+    let mut matched: Vec<_> = members
+        .into_iter()
+        .filter_map(|acc| {
+            let name = acc.username.to_lowercase();
+            match name.find(&query)? {
+                0 => Some((0, acc)),
+                _ => Some((1, acc))
+            }
+        })
+        .collect();
+    matched.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.username.cmp(&b.1.username)));
+    matched.into_iter().map(|(_, acc)| acc).take(MAX_SUGGESTIONS).collect()
+}
+
+/// Char allowed in the mentioned username.
+pub fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+
+#[test]
+fn mentions_test() {
+    let ann = Id { tb: Tb::Acc, id: Ulid::from_parts(1, 1) };
+    let anna = Id { tb: Tb::Acc, id: Ulid::from_parts(1, 2) };
+    let known = vec!(("ann".to_string(), ann.clone()), ("anna".to_string(), anna.clone()));
+
+    let encoded = encode_mentions("hi @anna and @ann, not me@ann or @annabel", &known);
+    assert_eq!(encoded, format!("hi {} and {}, not me@ann or @annabel", mention_token(&anna), mention_token(&ann)));
+    assert_eq!(mentions_of(&encoded), vec!(anna.id, ann.id));
+    let name_of = |id: &Ulid| if *id == ann.id { "ann".into() } else { "Anna N.".to_string() };
+    assert_eq!(display_text(&encoded, name_of), "hi @Anna N. and @ann, not me@ann or @annabel");
+    // -- Broken and non account tokens are plain text
+    assert_eq!(parse_mentions("<@ <@room:01J00000000000000000000000> <@account:nope>"), vec!());
+
+    assert_eq!(mention_query("hello @an"), Some((6, "an")));
+    assert_eq!(mention_query("@"), Some((0, "")));
+    assert_eq!(mention_query("mail@an"), None);
+    assert_eq!(mention_query("@an na"), None);
+}
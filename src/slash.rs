@@ -0,0 +1,360 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use chrono_lite::Datetime;
+use tracing_lite::{error, info, trace};
+
+use crate::cont::acc::Account;
+use crate::cont::room::{InviteTarget, RoomInvite};
+use crate::i18n::{t, t_with};
+use crate::mentions::{is_name_char, match_members};
+use crate::util::{now_ms, Id, Tb};
+use crate::view_data::session::APP;
+use crate::views::invites::INVITE_VALID_FOR;
+
+
+thread_local! {
+    static SLASH_COMMANDS: RefCell<SlashCommands> = RefCell::new(SlashCommands::builtin());
+}
+
+
+/// What the editor does after the command ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlashOutcome {
+    /// Send this text as the msg.
+    Send(String),
+    /// Nothing to send, clear the editor.
+    Done
+}
+
+/// Room and accounts the command is typed in.
+#[derive(Debug, Clone)]
+pub struct SlashContext {
+    pub room: Id,
+    pub owner: Account,
+    /// Room members without the owner.
+    pub members: Vec<Account>,
+    pub user: Account,
+    /// All known accounts.
+    pub accounts: Vec<Account>
+}
+
+impl SlashContext {
+    /// Context of the active room (if there is one).
+    pub fn active() -> Option<Self> {
+        APP.with(|app| {
+            let active = app.active_room.get_untracked()?;
+            let (owner, members) = app.rooms.with_untracked(|rooms| rooms
+                .get(&active.idx)
+                .map(|r| (r.owner.clone(), r.members.values().cloned().collect()))
+            )?;
+            Some(Self {
+                room: active.id(),
+                owner,
+                members,
+                user: app.user.with_untracked(|u| (**u).clone()),
+                accounts: app.accounts.with_untracked(|accs| accs.values().cloned().collect())
+            })
+        })
+    }
+
+    pub fn is_member(&self, acc: &Id) -> bool {
+        self.owner.acc_id == *acc || self.members.iter().any(|m| m.acc_id == *acc)
+    }
+}
+
+/// `/`-prefixed editor command. Implement it and pass to
+/// [register_slash_command] to add custom commands.
+pub trait SlashCommand {
+    /// Name typed after the `/`.
+    fn name(&self) -> &str;
+    /// Arguments shown in the autocomplete, eg. `<text>`.
+    fn usage(&self) -> &str { "" }
+    /// Shown in the autocomplete, in the current language.
+    fn description(&self) -> String;
+    /// Run with the arguments typed after the name; `Err` is shown in the editor.
+    fn run(&self, args: &str, cx: &SlashContext) -> Result<SlashOutcome, String>;
+    /// Completed arguments for the partially typed ones.
+    fn complete(&self, _args: &str, _cx: &SlashContext) -> Vec<String> { Vec::new() }
+}
+
+/// Autocomplete item replacing the typed command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// New text before the cursor.
+    pub replacement: String,
+    pub label: String
+}
+
+
+/// Name and arguments of the typed command; `None` for plain text
+/// (also `//` escaped one and paths like `/usr/bin`).
+pub fn parse_slash(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix('/')?;
+    let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let is_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    is_name.then(|| (name, args.trim()))
+}
+
+/// Text with the `//` escape removed (`//me` is sent as `/me`).
+pub fn unescape_slash(text: &str) -> &str {
+    match text.starts_with("//") {
+        true => &text[1..],
+        false => text
+    }
+}
+
+/// Registered commands.
+pub struct SlashCommands(Vec<Rc<dyn SlashCommand>>);
+
+impl SlashCommands {
+    pub fn builtin() -> Self {
+        Self(vec!(Rc::new(Me), Rc::new(Shrug), Rc::new(Topic), Rc::new(Invite), Rc::new(Leave), Rc::new(Nick)))
+    }
+
+    /// Add the command (replaces the one with the same name).
+    pub fn register(&mut self, cmd: Rc<dyn SlashCommand>) {
+        match self.0.iter_mut().find(|c| c.name() == cmd.name()) {
+            Some(old) => *old = cmd,
+            None => self.0.push(cmd)
+        }
+    }
+
+    pub fn find(&self, name: &str) -> Option<Rc<dyn SlashCommand>> {
+        self.0.iter().find(|c| c.name() == name).cloned()
+    }
+
+    /// Run the command typed in the `text`, `None` if it's not a command.
+    pub fn run(&self, text: &str, cx: &SlashContext) -> Option<Result<SlashOutcome, String>> {
+        let (name, args) = parse_slash(text)?;
+        trace!("fn: SlashCommands::run: /{name}");
+        Some(match self.find(name) {
+            Some(cmd) => cmd.run(args, cx),
+            None => Err(t_with("slash.unknown", &[("name", &name)]))
+        })
+    }
+
+    /// Command names or their arguments completing the text before the cursor.
+    pub fn complete(&self, before_cursor: &str, cx: &SlashContext) -> Vec<Completion> {
+        if before_cursor.contains('\n') { return Vec::new() }
+        let Some(typed) = before_cursor.strip_prefix('/') else { return Vec::new() };
+        let completions = match typed.split_once(' ') {
+            None => self.0
+                .iter()
+                .filter(|c| c.name().starts_with(typed))
+                .map(|c| Completion {
+                    replacement: format!("/{} ", c.name()),
+                    label: format!("/{} {} – {}", c.name(), c.usage(), c.description())
+                })
+                .collect(),
+            Some((name, args)) => match self.find(name) {
+                Some(cmd) => cmd
+                    .complete(args.trim_start(), cx)
+                    .into_iter()
+                    .map(|arg| Completion { replacement: format!("/{name} {arg}"), label: arg })
+                    .collect(),
+                None => Vec::new()
+            }
+        };
+        // -- Already typed one is done, so Enter can send it
+        completions.into_iter().filter(|c| c.replacement.trim_end() != before_cursor.trim_end()).collect()
+    }
+}
+
+/// Add a custom command (replaces the one with the same name).
+pub fn register_slash_command(cmd: impl SlashCommand + 'static) {
+    SLASH_COMMANDS.with_borrow_mut(|cmds| cmds.register(Rc::new(cmd)))
+}
+
+/// Run the command typed in the active room, `None` if the text is not a command.
+pub fn run_slash(text: &str) -> Option<Result<SlashOutcome, String>> {
+    parse_slash(text)?;
+    let Some(cx) = SlashContext::active() else { return Some(Err(t("slash.no_room"))) };
+    // -- Copied out of the registry, so commands can register others
+    let cmds = SLASH_COMMANDS.with_borrow(|cmds| SlashCommands(cmds.0.clone()));
+    cmds.run(text, &cx)
+}
+
+/// Completions for the text typed in the active room.
+pub fn complete_slash(before_cursor: &str) -> Vec<Completion> {
+    if !before_cursor.starts_with('/') { return Vec::new() }
+    let Some(cx) = SlashContext::active() else { return Vec::new() };
+    SLASH_COMMANDS.with_borrow(|cmds| cmds.complete(before_cursor, &cx))
+}
+
+
+// MARK: Builtin
+
+struct Me;
+
+impl SlashCommand for Me {
+    fn name(&self) -> &str { "me" }
+    fn usage(&self) -> &str { "<action>" }
+    fn description(&self) -> String { t("slash.me") }
+
+    fn run(&self, args: &str, cx: &SlashContext) -> Result<SlashOutcome, String> {
+        if args.is_empty() { return Err(t("slash.me_usage")) }
+        Ok(SlashOutcome::Send(format!("*{} {args}*", cx.user.username)))
+    }
+}
+
+struct Shrug;
+
+impl SlashCommand for Shrug {
+    fn name(&self) -> &str { "shrug" }
+    fn usage(&self) -> &str { "[text]" }
+    fn description(&self) -> String { t("slash.shrug") }
+
+    fn run(&self, args: &str, _cx: &SlashContext) -> Result<SlashOutcome, String> {
+        Ok(SlashOutcome::Send(format!("{args} ¯\\_(ツ)_/¯").trim_start().to_string()))
+    }
+}
+
+struct Topic;
+
+impl SlashCommand for Topic {
+    fn name(&self) -> &str { "topic" }
+    fn usage(&self) -> &str { "<text>" }
+    fn description(&self) -> String { t("slash.topic") }
+
+    fn run(&self, args: &str, cx: &SlashContext) -> Result<SlashOutcome, String> {
+        if cx.owner.acc_id != cx.user.acc_id {
+            return Err(t("slash.topic_owner_only"))
+        }
+        let mut room = APP.with(|app| app.rooms.with_untracked(|rooms| rooms
+            .values()
+            .find(|r| r.room_id == cx.room)
+            .map(|r| r.to_room())
+        )).ok_or_else(|| t("room.not_found"))?;
+        room.description = (!args.is_empty()).then(|| args.to_string());
+        let room = APP.with(|app| app.backend().update_room(room)).map_err(|e| e.to_string())?;
+        APP.with(|app| app.apply_room(room));
+        Ok(SlashOutcome::Done)
+    }
+}
+
+struct Invite;
+
+impl SlashCommand for Invite {
+    fn name(&self) -> &str { "invite" }
+    fn usage(&self) -> &str { "@user" }
+    fn description(&self) -> String { t("slash.invite") }
+
+    fn run(&self, args: &str, cx: &SlashContext) -> Result<SlashOutcome, String> {
+        let name = args.strip_prefix('@').unwrap_or(args);
+        if name.is_empty() { return Err(t("slash.invite_usage")) }
+        let acc = cx.accounts
+            .iter()
+            .find(|acc| acc.username == name)
+            .ok_or_else(|| t_with("slash.no_such_user", &[("name", &name)]))?;
+        if cx.is_member(&acc.acc_id) {
+            return Err(t_with("slash.already_member", &[("name", &name)]))
+        }
+        let room_name = APP.with(|app| app.rooms.with_untracked(|rooms| rooms
+            .values()
+            .find(|r| r.room_id == cx.room)
+            .map(|r| r.name.get_untracked())
+        )).unwrap_or_default();
+        let invite = RoomInvite {
+            invite_id: Id::new(Tb::Inv),
+            room_id: cx.room.clone(),
+            room_name,
+            inviter: cx.user.acc_id.clone(),
+            target: InviteTarget::Account(acc.acc_id.clone()),
+            created: Datetime::default(),
+            expires: now_ms() + INVITE_VALID_FOR.as_millis() as u64
+        };
+        APP.with(|app| app.backend().create_invite(invite)).map_err(|e| e.to_string())?;
+        info!("Invited {name} to {}", cx.room);
+        Ok(SlashOutcome::Done)
+    }
+
+    fn complete(&self, args: &str, cx: &SlashContext) -> Vec<String> {
+        let outside = cx.accounts.iter().filter(|acc| !cx.is_member(&acc.acc_id) && acc.acc_id != cx.user.acc_id);
+        match_members(outside, args.strip_prefix('@').unwrap_or(args))
+            .into_iter()
+            .map(|acc| format!("@{}", acc.username))
+            .collect()
+    }
+}
+
+struct Leave;
+
+impl SlashCommand for Leave {
+    fn name(&self) -> &str { "leave" }
+    fn description(&self) -> String { t("slash.leave") }
+
+    fn run(&self, _args: &str, cx: &SlashContext) -> Result<SlashOutcome, String> {
+        APP.with(|app| app.backend().leave_room(&cx.room, &cx.user.acc_id)).map_err(|e| e.to_string())?;
+        APP.with(|app| app.remove_room(&cx.room.id));
+        Ok(SlashOutcome::Done)
+    }
+}
+
+struct Nick;
+
+impl SlashCommand for Nick {
+    fn name(&self) -> &str { "nick" }
+    fn usage(&self) -> &str { "<name>" }
+    fn description(&self) -> String { t("slash.nick") }
+
+    fn run(&self, args: &str, cx: &SlashContext) -> Result<SlashOutcome, String> {
+        if args.is_empty() || !args.chars().all(is_name_char) {
+            return Err(t("slash.nick_usage"))
+        }
+        let (backend, token) = APP.with(|app| (app.backend(), app.token.get_untracked()));
+        let token = token.ok_or_else(|| t("slash.not_signed_in"))?;
+        let mut acc = cx.user.clone();
+        acc.username = args.into();
+        match backend.update_profile(&token, acc) {
+            Ok(acc) => {
+                APP.with(|app| app.update_profile(acc));
+                Ok(SlashOutcome::Done)
+            },
+            Err(e) => {
+                error!("Failed to change username: {e}");
+                Err(e.to_string())
+            }
+        }
+    }
+}
+
+
+#[test]
+fn slash_test() {
+    let acc = |n: u16, name: &str| Account {
+        acc_id: Id { tb: Tb::Acc, id: ulid::Ulid::from_parts(1, n as u128) },
+        username: name.into(),
+        av: Rc::new(Vec::new()),
+    };
+    let cx = SlashContext {
+        room: Id { tb: Tb::Room, id: ulid::Ulid::from_parts(2, 1) },
+        owner: acc(1, "ann"),
+        members: vec!(acc(2, "bob")),
+        user: acc(2, "bob"),
+        accounts: vec!(acc(1, "ann"), acc(2, "bob"), acc(3, "anna"), acc(4, "carl"))
+    };
+    let cmds = SlashCommands::builtin();
+
+    assert_eq!(parse_slash("/topic  New one "), Some(("topic", "New one")));
+    assert_eq!(parse_slash("//me"), None);
+    assert_eq!(parse_slash("/usr/bin is a dir"), None);
+    assert_eq!(unescape_slash("//me waves"), "/me waves");
+    assert_eq!(cmds.run("hello", &cx), None);
+    assert_eq!(cmds.run("/me waves", &cx), Some(Ok(SlashOutcome::Send("*bob waves*".into()))));
+    assert_eq!(cmds.run("/shrug", &cx), Some(Ok(SlashOutcome::Send("¯\\_(ツ)_/¯".into()))));
+    assert!(cmds.run("/me", &cx).unwrap().is_err());
+    assert!(cmds.run("/nope", &cx).unwrap().unwrap_err().contains("/nope"));
+    // -- Permission is checked before any backend call
+    assert!(cmds.run("/topic x", &cx).unwrap().is_err());
+
+    let replacements = |typed: &str| cmds.complete(typed, &cx).into_iter().map(|c| c.replacement).collect::<Vec<_>>();
+    assert_eq!(replacements("/"), ["/me ", "/shrug ", "/topic ", "/invite ", "/leave ", "/nick "]);
+    assert_eq!(replacements("/s"), ["/shrug "]);
+    assert_eq!(replacements("/invite @an"), ["/invite @anna"]);
+    assert_eq!(replacements("/invite "), ["/invite @anna", "/invite @carl"]);
+    assert_eq!(replacements("/invite @anna"), Vec::<String>::new());
+    // -- Complete name is not suggested again, so Enter sends it
+    assert_eq!(replacements("/me"), Vec::<String>::new());
+    assert_eq!(replacements("/me "), Vec::<String>::new());
+}
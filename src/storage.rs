@@ -0,0 +1,95 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use tracing_lite::warn;
+
+use crate::util::now_ms;
+
+
+/// Name of the app dir inside the platform config dir.
+const APP_DIR: &str = "chat_floem";
+
+
+/// Platform config dir of the app (`None` if the home dir is unknown):
+/// - Linux: `$XDG_CONFIG_HOME/chat_floem` or `~/.config/chat_floem`
+/// - macOS: `~/Library/Application Support/chat_floem`
+/// - Windows: `%APPDATA%\chat_floem`
+pub fn config_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = if cfg!(windows) {
+        env_dir("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME")?.join("Library/Application Support")
+    } else {
+        // -- Relative XDG paths are invalid and should be ignored
+        env_dir("XDG_CONFIG_HOME")
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| env_dir("HOME").map(|home| home.join(".config")))?
+    };
+    Some(base.join(APP_DIR))
+}
+
+/// Path of the app file (or dir) in the [config_dir], in the working dir if there is none.
+pub fn app_path(name: &str) -> PathBuf {
+    // -- Tests (eg. touching `APP`) never read or write the real app files
+    if cfg!(test) {
+        return env::temp_dir().join(format!("{APP_DIR}_test_{}", std::process::id())).join(name)
+    }
+    match config_dir() {
+        Some(dir) => dir.join(name),
+        None => { warn!("Config dir not found, using the working dir for {name}"); name.into() }
+    }
+}
+
+/// Write the file through a temporary one renamed over it,
+/// so it's never left half written (eg. on crash or full disk).
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    let written = File::create(&tmp).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    match written.and_then(|_| fs::rename(&tmp, path)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            if let Err(e) = fs::remove_file(&tmp) {
+                warn!("Failed to remove {}: {e}", tmp.display());
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Move the unreadable file aside (to `<name>.corrupt-<ms>`) and return its new path.
+pub fn backup_corrupt(path: &Path) -> io::Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", now_ms()));
+    let backup = path.with_file_name(name);
+    fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+
+#[test]
+fn storage_test() {
+    let dir = env::temp_dir().join(format!("chat_floem_storage_{}", now_ms()));
+    let path = dir.join("nested/file.json");
+    write_atomic(&path, b"first").unwrap();
+    write_atomic(&path, b"second").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"second");
+    // -- No temporary file is left behind
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+    let backup = backup_corrupt(&path).unwrap();
+    assert!(!path.exists());
+    assert!(backup.file_name().unwrap().to_string_lossy().starts_with("file.json.corrupt-"));
+    assert_eq!(fs::read(&backup).unwrap(), b"second");
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use floem::prelude::*;
+use floem::reactive::create_memo;
+use serde::Deserialize;
+use tracing_lite::{debug, warn};
+
+use crate::config::ChatConfig;
+use crate::i18n::t;
+use crate::storage::app_path;
+use crate::view_data::session::APP;
+
+
+/// Directory (in the [config_dir](crate::storage::config_dir)) with the user themes (`*.json`).
+pub const THEMES_DIR: &str = "themes";
+pub const LIGHT: &str = "Light";
+pub const DARK: &str = "Dark";
+pub const HIGH_CONTRAST: &str = "High contrast";
+
+/// Bumped on every palette change (see [theme_rev]).
+static THEME_REV: AtomicU64 = AtomicU64::new(0);
+
+
+/// Semantic colors used by the views instead of the fixed ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    /// Dark background (editor and code highlighting follow it).
+    pub dark: bool,
+    pub window_bg: Color,
+    pub toolbar_bg: Color,
+    pub sidebar_bg: Color,
+    pub msgs_bg: Color,
+    pub editor_bg: Color,
+    pub editor_buttons_bg: Color,
+    /// Dialogs, popups and cards.
+    pub surface: Color,
+    /// Dimmed background behind the dialogs.
+    pub overlay: Color,
+    pub text: Color,
+    pub text_muted: Color,
+    pub border: Color,
+    /// Borders of the focused or active elements.
+    pub accent: Color,
+    pub selected: Color,
+    pub hover: Color,
+    pub danger: Color,
+    pub badge_bg: Color,
+    pub badge_text: Color,
+    pub link: Color,
+    pub mention: Color,
+    pub mention_self: Color,
+    /// Background of the msgs mentioning the user.
+    pub mention_bg: Color,
+    pub code_bg: Color,
+    pub code_text: Color,
+    pub online: Color,
+    pub away: Color
+}
+
+impl Palette {
+    pub fn light() -> Self {
+        Self {
+            dark: false,
+            window_bg: Color::WHITE,
+            toolbar_bg: Color::MEDIUM_ORCHID,
+            sidebar_bg: Color::LIGHT_BLUE,
+            msgs_bg: Color::LIGHT_GREEN,
+            editor_bg: Color::YELLOW,
+            editor_buttons_bg: Color::RED,
+            surface: Color::WHITE,
+            overlay: Color::rgba8(0, 0, 0, 80),
+            text: Color::BLACK,
+            text_muted: Color::GRAY,
+            border: Color::BLACK,
+            accent: Color::NAVY,
+            selected: Color::LIGHT_BLUE,
+            hover: Color::LIGHT_GRAY,
+            danger: Color::RED,
+            badge_bg: Color::CRIMSON,
+            badge_text: Color::WHITE,
+            link: Color::rgb8(20, 90, 200),
+            mention: Color::rgb8(90, 60, 170),
+            mention_self: Color::rgb8(200, 110, 0),
+            mention_bg: Color::rgb8(255, 245, 210),
+            code_bg: Color::rgb8(235, 235, 235),
+            code_text: Color::DARK_RED,
+            online: Color::LIME_GREEN,
+            away: Color::ORANGE
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            dark: true,
+            window_bg: Color::rgb8(30, 30, 34),
+            toolbar_bg: Color::rgb8(60, 40, 80),
+            sidebar_bg: Color::rgb8(35, 45, 60),
+            msgs_bg: Color::rgb8(30, 40, 35),
+            editor_bg: Color::rgb8(45, 45, 35),
+            editor_buttons_bg: Color::rgb8(70, 30, 30),
+            surface: Color::rgb8(45, 45, 50),
+            overlay: Color::rgba8(0, 0, 0, 140),
+            text: Color::rgb8(225, 225, 225),
+            text_muted: Color::rgb8(150, 150, 150),
+            border: Color::rgb8(90, 90, 90),
+            accent: Color::rgb8(110, 150, 230),
+            selected: Color::rgb8(50, 70, 110),
+            hover: Color::rgb8(65, 65, 70),
+            danger: Color::rgb8(240, 90, 90),
+            badge_bg: Color::rgb8(200, 50, 70),
+            badge_text: Color::WHITE,
+            link: Color::rgb8(110, 170, 255),
+            mention: Color::rgb8(180, 150, 255),
+            mention_self: Color::rgb8(255, 170, 70),
+            mention_bg: Color::rgb8(70, 60, 30),
+            code_bg: Color::rgb8(55, 55, 60),
+            code_text: Color::rgb8(240, 130, 120),
+            ..Self::light()
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            dark: true,
+            window_bg: Color::BLACK,
+            toolbar_bg: Color::BLACK,
+            sidebar_bg: Color::BLACK,
+            msgs_bg: Color::BLACK,
+            editor_bg: Color::BLACK,
+            editor_buttons_bg: Color::BLACK,
+            surface: Color::BLACK,
+            overlay: Color::rgba8(0, 0, 0, 200),
+            text: Color::WHITE,
+            text_muted: Color::rgb8(210, 210, 210),
+            border: Color::WHITE,
+            accent: Color::YELLOW,
+            selected: Color::rgb8(0, 60, 160),
+            hover: Color::rgb8(60, 60, 60),
+            danger: Color::rgb8(255, 80, 80),
+            badge_bg: Color::YELLOW,
+            badge_text: Color::BLACK,
+            link: Color::CYAN,
+            mention: Color::rgb8(255, 150, 255),
+            mention_self: Color::YELLOW,
+            mention_bg: Color::rgb8(60, 60, 0),
+            code_bg: Color::rgb8(30, 30, 30),
+            code_text: Color::rgb8(120, 255, 120),
+            ..Self::light()
+        }
+    }
+
+    /// Built-in palette with given name.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            LIGHT => Some(Self::light()),
+            DARK => Some(Self::dark()),
+            HIGH_CONTRAST => Some(Self::high_contrast()),
+            _ => None
+        }
+    }
+
+    /// Built-in or user theme, light one if there is no such theme.
+    pub fn named(name: &str) -> Self {
+        if let Some(palette) = Self::builtin(name) {
+            return palette
+        }
+        match user_themes().into_iter().find(|t| t.name == name) {
+            Some(theme) => theme.palette(),
+            None => { warn!("Theme {name:?} not found, using {LIGHT}"); Self::light() }
+        }
+    }
+
+    fn token_mut(&mut self, token: &str) -> Option<&mut Color> {
+        Some(match token {
+            "window_bg" => &mut self.window_bg,
+            "toolbar_bg" => &mut self.toolbar_bg,
+            "sidebar_bg" => &mut self.sidebar_bg,
+            "msgs_bg" => &mut self.msgs_bg,
+            "editor_bg" => &mut self.editor_bg,
+            "editor_buttons_bg" => &mut self.editor_buttons_bg,
+            "surface" => &mut self.surface,
+            "overlay" => &mut self.overlay,
+            "text" => &mut self.text,
+            "text_muted" => &mut self.text_muted,
+            "border" => &mut self.border,
+            "accent" => &mut self.accent,
+            "selected" => &mut self.selected,
+            "hover" => &mut self.hover,
+            "danger" => &mut self.danger,
+            "badge_bg" => &mut self.badge_bg,
+            "badge_text" => &mut self.badge_text,
+            "link" => &mut self.link,
+            "mention" => &mut self.mention,
+            "mention_self" => &mut self.mention_self,
+            "mention_bg" => &mut self.mention_bg,
+            "code_bg" => &mut self.code_bg,
+            "code_text" => &mut self.code_text,
+            "online" => &mut self.online,
+            "away" => &mut self.away,
+            _ => return None
+        })
+    }
+}
+
+
+// MARK: User themes
+
+/// Theme file, eg. `{"name": "Dusk", "base": "Dark", "colors": {"toolbar_bg": "#553366"}}`.
+/// Colors not listed are taken from the `base` built-in theme.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThemeFile {
+    pub name: String,
+    #[serde(default)]
+    pub base: Option<String>,
+    #[serde(default)]
+    pub colors: HashMap<String, String>
+}
+
+impl ThemeFile {
+    /// Palette of the theme; unknown tokens and bad colors are skipped.
+    pub fn palette(&self) -> Palette {
+        let base = self.base.as_deref().unwrap_or(LIGHT);
+        let mut palette = Palette::builtin(base).unwrap_or_else(|| {
+            warn!("Theme {:?}: unknown base {base:?}", self.name);
+            Palette::light()
+        });
+        for (token, value) in &self.colors {
+            match (palette.token_mut(token), parse_color(value)) {
+                (Some(color), Some(parsed)) => *color = parsed,
+                (None, _) => warn!("Theme {:?}: unknown color {token:?}", self.name),
+                (_, None) => warn!("Theme {:?}: bad value of {token}: {value:?}", self.name)
+            }
+        }
+        palette
+    }
+}
+
+/// Themes read from the [THEMES_DIR] (broken files are skipped).
+pub fn user_themes() -> Vec<ThemeFile> {
+    let Ok(entries) = fs::read_dir(app_path(THEMES_DIR)) else { return Vec::new() };
+    let mut themes: Vec<ThemeFile> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| read_theme(&path))
+        .collect();
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+    themes
+}
+
+fn read_theme(path: &Path) -> Option<ThemeFile> {
+    let file = File::open(path).ok()?;
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(theme) => Some(theme),
+        Err(e) => { warn!("Failed to read theme {}: {e}", path.display()); None }
+    }
+}
+
+/// Name of the theme shown to the user (built-in ones are translated).
+pub fn theme_title(name: &str) -> String {
+    match name {
+        LIGHT => t("theme.light"),
+        DARK => t("theme.dark"),
+        HIGH_CONTRAST => t("theme.high_contrast"),
+        _ => name.to_string()
+    }
+}
+
+/// Names of the built-in and user themes.
+pub fn available_themes() -> Vec<String> {
+    [LIGHT, DARK, HIGH_CONTRAST]
+        .into_iter()
+        .map(String::from)
+        .chain(user_themes().into_iter().map(|t| t.name))
+        .collect()
+}
+
+/// Color written as `#rgb`, `#rrggbb` or `#rrggbbaa`.
+pub fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.trim().strip_prefix('#')?;
+    if !hex.is_ascii() { return None }
+    let channel = |i: usize, len: usize| u8::from_str_radix(&hex[i * len..(i + 1) * len], 16).ok();
+    match hex.len() {
+        3 => Some(Color::rgb8(channel(0, 1)? * 17, channel(1, 1)? * 17, channel(2, 1)? * 17)),
+        6 => Some(Color::rgb8(channel(0, 2)?, channel(1, 2)?, channel(2, 2)?)),
+        8 => Some(Color::rgba8(channel(0, 2)?, channel(1, 2)?, channel(2, 2)?, channel(3, 2)?)),
+        _ => None
+    }
+}
+
+
+// MARK: Current
+
+/// Current palette; read in `style` closures, so views restyle on theme change.
+pub fn colors() -> Palette {
+    APP.with(|app| app.theme.get())
+}
+
+/// Keep the palette in sync with the theme chosen in the config.
+pub fn follow_config_theme(config: RwSignal<ChatConfig>) {
+    let name = create_memo(move |_| config.with(|cf| cf.theme.clone()));
+    create_effect(move |_| {
+        let name = name.get();
+        debug!("== effect(theme): {name}");
+        THEME_REV.fetch_add(1, Ordering::Relaxed);
+        APP.with(|app| app.theme.set(Palette::named(&name)));
+    });
+}
+
+/// Revision of the palette, for the cached styles which have to follow it.
+pub fn theme_rev() -> u64 {
+    THEME_REV.load(Ordering::Relaxed)
+}
+
+
+#[test]
+fn theme_test() {
+    assert_eq!(parse_color("#fff"), Some(Color::rgb8(255, 255, 255)));
+    assert_eq!(parse_color(" #1a2B3c "), Some(Color::rgb8(0x1a, 0x2b, 0x3c)));
+    assert_eq!(parse_color("#00000080"), Some(Color::rgba8(0, 0, 0, 128)));
+    assert_eq!(parse_color("1a2b3c"), None);
+    assert_eq!(parse_color("#12345"), None);
+    assert_eq!(parse_color("#żż"), None);
+
+    let file: ThemeFile = serde_json::from_str(r##"{
+        "name": "Dusk", "base": "Dark",
+        "colors": {"toolbar_bg": "#553366", "no_such": "#000", "link": "blue"}
+    }"##).unwrap();
+    let palette = file.palette();
+    assert_eq!(palette.toolbar_bg, Color::rgb8(0x55, 0x33, 0x66));
+    // -- Skipped values keep the base ones
+    assert_eq!(palette.link, Palette::dark().link);
+    assert!(palette.dark);
+    assert_eq!(Palette::named(HIGH_CONTRAST), Palette::high_contrast());
+    assert_eq!(Palette::named("Missing one"), Palette::light());
+}
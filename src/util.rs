@@ -1,10 +1,14 @@
 use std::fmt::Display;
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use chrono::{DateTime, Local, Offset, TimeZone};
 use serde::Deserialize;
 use serde::Serialize;
 use ulid::Ulid;
 
+use crate::i18n::{t, t_with};
+
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Id {
@@ -28,7 +32,7 @@ impl FromStr for Id {
     fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
 		let (table, ulid) = s.split_once(':').ok_or_else(|| ())?;
 		let tb = table.parse()?;
-		let id = Ulid::from_string(ulid).unwrap();
+		let id = Ulid::from_string(ulid).map_err(|_| ())?;
 		Ok(Self { tb, id })
     }
 }
@@ -47,7 +51,8 @@ pub enum Tb {
     Room,
     Msg,
     Com,
-    Rea
+    Rea,
+    Inv
 }
 
 impl Default for Tb {
@@ -66,7 +71,7 @@ impl Display for Tb {
 			Tb::Rea => "reaction",
 			Tb::Com => "msg_comment",
 			Tb::Empty => "empty",
-			// Tb::Inv => "room_invite",
+			Tb::Inv => "room_invite",
 			// Tb::SentTo => "sent_to",
 			// Tb::DelTo => "delivered_by",
 			// Tb::ViewBy => "viewed_by",
@@ -89,7 +94,7 @@ impl FromStr for Tb {
 			"msg" => Ok(Self::Msg),
 			"reaction" => Ok(Self::Rea),
 			"msg_comment" => Ok(Self::Com),
-			// "room_invite" => Ok(Self::Inv),
+			"room_invite" => Ok(Self::Inv),
 			// "sent_to" => Ok(Self::SentTo),
 			// "delivered_by" => Ok(Self::DelTo),
 			// "viewed_by" => Ok(Self::ViewBy),
@@ -101,4 +106,135 @@ impl FromStr for Tb {
 			_ => Err(())
 		}
 	}
-}
\ No newline at end of file
+}
+
+
+/// Lets an action through at most once per `interval` (ms) for each key.
+#[derive(Debug, Default)]
+pub struct Throttle<K> {
+    interval: u64,
+    last: std::collections::HashMap<K, u64>
+}
+
+impl<K: std::hash::Hash + Eq> Throttle<K> {
+    pub fn new(interval: u64) -> Self {
+        Self { interval, last: Default::default() }
+    }
+
+    /// Check if the action for `key` can run at `now` (and remember it if so).
+    pub fn ready(&mut self, key: K, now: u64) -> bool {
+        match self.last.get(&key) {
+            Some(last) if now.saturating_sub(*last) < self.interval => false,
+            _ => { self.last.insert(key, now); true }
+        }
+    }
+
+    /// Forget the last action for `key`, so the next one runs immediately.
+    pub fn reset(&mut self, key: &K) {
+        self.last.remove(key);
+    }
+}
+
+
+// MARK: Days
+
+const MS_IN_DAY: u64 = 86_400_000;
+/// Catalog keys of the month names.
+pub(crate) const MONTHS: [&str; 12] = [
+    "date.january", "date.february", "date.march", "date.april", "date.may", "date.june",
+    "date.july", "date.august", "date.september", "date.october", "date.november", "date.december"
+];
+
+/// Number of full days since the unix epoch for the given timestamp (in ms),
+/// counted in the local time zone.
+pub fn day_from_ms(ms: u64) -> u64 {
+    day_in_offset(ms, local_offset_ms(ms))
+}
+
+/// Day of the timestamp (in ms) in the time zone `offset` ms ahead of UTC.
+pub fn day_in_offset(ms: u64, offset: i64) -> u64 {
+    (ms as i64 + offset).max(0) as u64 / MS_IN_DAY
+}
+
+/// Offset (in ms) of the local time zone from UTC at the given timestamp (with DST).
+pub fn local_offset_ms(ms: u64) -> i64 {
+    DateTime::from_timestamp_millis(ms as i64)
+        .map(|utc| Local.offset_from_utc_datetime(&utc.naive_utc()).local_minus_utc() as i64 * 1000)
+        .unwrap_or(0)
+}
+
+/// Current unix timestamp in ms.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Number of full days since the unix epoch for the current moment (in the local time zone).
+pub fn today() -> u64 {
+    day_from_ms(now_ms())
+}
+
+/// Converts days since the unix epoch into (year, month, day).
+pub fn civil_from_days(days: u64) -> (i64, u32, u32) {
+    // Based on: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+/// Label for the day separator: "Today", "Yesterday" or full date.
+pub fn day_label(day: u64) -> String {
+    let today = today();
+    if day == today {
+        t("date.today")
+    } else if day + 1 == today {
+        t("date.yesterday")
+    } else {
+        full_date(day)
+    }
+}
+
+/// Local time of the msg: "14:05" today, "Yesterday, 14:05" or full date with time.
+pub fn time_label(ms: u64) -> String {
+    let minutes = (ms as i64 + local_offset_ms(ms)).rem_euclid(MS_IN_DAY as i64) / 60_000;
+    let time = format!("{:02}:{:02}", minutes / 60, minutes % 60);
+    let (day, today) = (day_from_ms(ms), today());
+    if day == today {
+        time
+    } else if day + 1 == today {
+        t_with("date.yesterday_at", &[("time", &time)])
+    } else {
+        t_with("date.full_at", &[("date", &full_date(day)), ("time", &time)])
+    }
+}
+
+/// Date like "3 March 2024".
+fn full_date(day: u64) -> String {
+    let (y, m, d) = civil_from_days(day);
+    t_with("date.full", &[("day", &d), ("month", &t(MONTHS[(m - 1) as usize])), ("year", &y)])
+}
+
+
+#[test]
+fn day_boundary_test() {
+    let hour: u64 = 3_600_000;
+    let new_year = 1_735_689_600_000; // 2025-01-01 00:00 UTC
+    // -- 23:59:59.999 and 00:00 at UTC+1 are on different days, and years
+    let (before, after) = (new_year - hour - 1, new_year - hour);
+    assert_eq!(day_in_offset(before, hour as i64) + 1, day_in_offset(after, hour as i64));
+    assert_eq!(civil_from_days(day_in_offset(before, hour as i64)), (2024, 12, 31));
+    assert_eq!(civil_from_days(day_in_offset(after, hour as i64)), (2025, 1, 1));
+    // -- Same moment is still the old year west of UTC, and in UTC itself
+    assert_eq!(civil_from_days(day_in_offset(after, -5 * hour as i64)), (2024, 12, 31));
+    assert_eq!(civil_from_days(day_in_offset(after, 0)), (2024, 12, 31));
+    assert_eq!(civil_from_days(day_in_offset(new_year, 0)), (2025, 1, 1));
+}
@@ -5,17 +5,32 @@ use chrono_lite::Datetime;
 use editor::text_document::TextDocument;
 use editor::Editor;
 use floem::{prelude::*, AnyView, ViewId};
+use floem::keyboard::Modifiers;
+use floem::menu::{Menu, MenuItem};
 use floem::taffy::{prelude::TaffyGridLine, GridPlacement, Line};
-use floem::reactive::{create_effect, use_context, Trigger, WriteSignal};
+use floem::reactive::{batch, create_effect, create_memo, use_context, Trigger, WriteSignal};
+use editor::command::{Command, CommandExecuted};
+use editor::keypress::{default_key_handler, key::KeyInput, press::KeyPress};
+use editor::core::command::{EditCommand, MoveCommand};
 use editor::core::{editor::EditType, selection::Selection};
-use editor::text::{default_light_theme, Document, SimpleStyling};
+use editor::text::{default_dark_color, default_light_theme, Document, PreCommand, SimpleStyling};
 use tracing_lite::{error, info, trace, warn};
 use ulid::Ulid;
 
+use crate::avatar::avatar_of;
 use crate::common::CommonData;
-use crate::view_data::msg::MsgViewData;
+use crate::cont::acc::Account;
+use crate::mentions::{display_text, encode_mentions, match_members, mention_query};
+use crate::slash::{complete_slash, run_slash, unescape_slash, Completion, SlashOutcome};
+use crate::drafts::save_debounced;
+use crate::config::{ChatConfig, EditorAction, KeyBinding, Keymap};
+use crate::view_data::msg::{msg_snippet, MsgViewData};
+use crate::views::panes::Panes;
+use crate::views::rich_text::{mention_name, MarkupStyling};
 use crate::util::{Id, Tb};
 use crate::cont::msg::{Msg, Text};
+use crate::theme::colors;
+use crate::i18n::{t, t_label, t_with};
 use super::session::APP;
 use super::MsgEvent;
 
@@ -30,6 +45,8 @@ pub struct EditorViewData {
     /// K: room id
     pub docs: RwSignal<HashMap<Ulid, Rc<dyn Document>>>,
     pub active_doc: RwSignal<Option<Rc<dyn Document>>>,
+    /// Msg (view id) being edited in the active doc.
+    pub editing: RwSignal<Option<Ulid>>,
     // TODO: more is needed..
     pub common_data: Rc<CommonData>
 }
@@ -42,6 +59,7 @@ impl EditorViewData {
             view_id: ViewId::new(),
             docs: cx.create_rw_signal(HashMap::new()),
             active_doc: cx.create_rw_signal(Some(active_doc.clone())),
+            editing: cx.create_rw_signal(None),
             common_data: APP.with(|app| app.common_data.clone()),
             editor: Editor::new(cx, active_doc, Rc::new(SimpleStyling::new()), false)
         }
@@ -59,58 +77,290 @@ impl IntoView for EditorViewData {
         let msg_event = use_context::<RwSignal<MsgEvent>>().unwrap();
         let send_msg = use_context::<Trigger>().unwrap();
         let new_room = use_context::<RwSignal<Option<Ulid>>>().unwrap();
+        let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+        let editing = self.editing;
+        let reply_to = use_context::<RwSignal<Option<MsgViewData>>>().unwrap();
+
+        // -- Keymap from the config, checked before the editor handles the key
+        //    (open autocomplete takes its keys in `on_command`)
+        let autocomplete_open = RwSignal::new(false);
+        let on_key = move |ed: RwSignal<Editor>, keypress: &KeyPress, mods: Modifiers| {
+            let action = match &keypress.key {
+                KeyInput::Keyboard(key, _) if !autocomplete_open.get_untracked() => KeyBinding::pressed(key, mods)
+                    .and_then(|pressed| config.with_untracked(|cf| cf.keymap.action(&pressed))),
+                _ => None
+            };
+            let handled = match action {
+                Some(EditorAction::Send) => {
+                    send_msg.notify();
+                    true
+                },
+                Some(EditorAction::NewLine) => {
+                    ed.with_untracked(|ed| ed.receive_char("\n"));
+                    true
+                },
+                Some(EditorAction::EditLast) => {
+                    let doc = ed.with_untracked(|ed| ed.doc());
+                    doc.text().is_empty() && edit_last_msg(doc, editing)
+                },
+                None => false
+            };
+            match handled {
+                true => CommandExecuted::Yes,
+                false => default_key_handler(ed)(keypress, mods)
+            }
+        };
 
         // let default_doc = Rc::new(TextDocument::new(Scope::current(), "def"));
-        let text_editor = text_editor("")
+        let text_editor = text_editor_keys("", on_key)
             // .placeholder("Type message..")
-            .styling(SimpleStyling::new())
             .style(|s| s.size_full())
-            .editor_style(default_light_theme)
+            .editor_style(|s| if colors().dark { default_dark_color(s) } else { default_light_theme(s) })
             .editor_style(|s| s.hide_gutter(true));
 
         let doc_signal = text_editor.editor().doc_signal();
         let ed_cx = text_editor.editor().cx.get();
+        let editor_id = text_editor.editor().id();
+        let cursor = text_editor.editor().cursor;
+        let text_editor = text_editor.styling(MarkupStyling::new(doc_signal));
+
+        // -- Slash command autocomplete and the error of the last run
+        let slash_error = RwSignal::new(None::<String>);
+        let slash_hints = create_memo(move |_| {
+            doc_signal.with(|doc| doc.cache_rev().track());
+            let offset = cursor.with(|c| c.offset());
+            let before = doc_signal.with_untracked(|doc| doc.rope_text().slice_to_cow(0..offset).to_string());
+            complete_slash(&before)
+        });
+        let accept_slash = move |hint: &Completion| {
+            let offset = cursor.with_untracked(|c| c.offset());
+            doc_signal.with_untracked(|doc| {
+                doc.edit_single(Selection::region(0, offset), &hint.replacement, EditType::InsertChars);
+            });
+            cursor.update(|c| c.set_offset(hint.replacement.len(), false, false));
+        };
+        create_effect(move |_| {
+            doc_signal.with(|doc| doc.cache_rev().track());
+            if slash_error.with_untracked(|e| e.is_some()) {
+                slash_error.set(None);
+            }
+        });
+
+        // -- Mention autocomplete: `@` position and query typed after it
+        //    (slash command completions go first)
+        let mention_at = RwSignal::new(None::<(usize, String)>);
+        let selected = RwSignal::new(0usize);
+        let suggestions = create_memo(move |_| match mention_at.get() {
+            Some((_, query)) if slash_hints.with(|h| h.is_empty()) => {
+                match_members(&active_room_members(), &query).into_iter().cloned().collect()
+            },
+            _ => Vec::<Account>::new()
+        });
+        let accept_mention = move |acc: &Account| {
+            let Some((at, _)) = mention_at.get_untracked() else { return };
+            let offset = cursor.with_untracked(|c| c.offset());
+            let mention = format!("@{} ", acc.username);
+            doc_signal.with_untracked(|doc| {
+                doc.edit_single(Selection::region(at, offset), &mention, EditType::InsertChars);
+            });
+            cursor.update(|c| c.set_offset(at + mention.len(), false, false));
+            mention_at.set(None);
+        };
+        create_effect(move |_| {
+            doc_signal.with(|doc| doc.cache_rev().track());
+            let offset = cursor.with(|c| c.offset());
+            let query = doc_signal.with_untracked(|doc| {
+                let before = doc.rope_text().slice_to_cow(0..offset).to_string();
+                mention_query(&before).map(|(at, query)| (at, query.to_string()))
+            });
+            if query != mention_at.get_untracked() {
+                batch(|| {
+                    mention_at.set(query);
+                    selected.set(0);
+                });
+            }
+        });
+        create_effect(move |_| {
+            slash_hints.track();
+            selected.set(0);
+        });
+        let accept_selected = move || {
+            let idx = selected.get_untracked();
+            if let Some(acc) = suggestions.with_untracked(|s| s.get(idx).cloned()) {
+                accept_mention(&acc);
+            } else if let Some(hint) = slash_hints.with_untracked(|h| h.get(idx).cloned()) {
+                accept_slash(&hint);
+            }
+        };
+
+        // -- Open autocomplete takes arrows, Enter and Tab
+        create_effect(move |_| {
+            let count = suggestions.with(|s| s.len()) + slash_hints.with(|h| h.len());
+            autocomplete_open.set(count > 0);
+        });
+        let on_command = move |pc: PreCommand| {
+            let count = suggestions.with_untracked(|s| s.len()) + slash_hints.with_untracked(|h| h.len());
+            if count == 0 { return CommandExecuted::No }
+            match pc.cmd {
+                Command::Move(MoveCommand::Up) => {
+                    selected.update(|i| *i = (*i + count - 1) % count);
+                    CommandExecuted::Yes
+                },
+                Command::Move(MoveCommand::Down) => {
+                    selected.update(|i| *i = (*i + 1) % count);
+                    CommandExecuted::Yes
+                },
+                Command::Edit(EditCommand::InsertNewLine | EditCommand::InsertTab) => {
+                    accept_selected();
+                    CommandExecuted::Yes
+                },
+                _ => CommandExecuted::No
+            }
+        };
+        let text_editor = text_editor.pre_command(on_command);
+
+        let drafts = APP.with(|app| app.drafts);
+        // -- Room doc, restored from the draft if there is one
+        let create_doc = move |room_id: Ulid| {
+            let draft = drafts.with_untracked(|d| d.get(&room_id).unwrap_or_default().to_string());
+            let doc = TextDocument::new(ed_cx, draft);
+            doc.add_pre_command(editor_id, on_command);
+            let doc: Rc<dyn Document> = Rc::new(doc);
+            self.docs.update(|docs| { docs.insert(room_id, doc.clone()); });
+            doc
+        };
 
         create_effect(move |_| {
             info!("->> effect: new room editor doc");
             if let Some(room_id) = new_room.get() {
-                self.docs.update(|docs| {
-                    docs.insert(room_id, Rc::new(TextDocument::new(ed_cx, "")));
-                });
+                create_doc(room_id);
             }
         });
         
+        let clear_doc = move || doc_signal.with_untracked(|doc| {
+            let text_len = doc.text().len();
+            doc.edit_single(Selection::region(0, text_len), "", EditType::DeleteSelection);
+        });
+
         create_effect(move |_| {
             info!("->> effect: switch doc");
             match active_room.get() {
                 Some(room) => {
-                    if let Some(doc) = self.docs.with_untracked(|d| d.get(&room.id).cloned()) {
-                        self.active_doc.set(Some(doc.clone()));
-                        doc_signal.set(doc);
+                    // -- Edit is dropped with the msg text (doc was empty before it), so the text
+                    //    isn't sent as a new msg later; cleared while still editing to keep the draft
+                    if editing.with_untracked(|e| e.is_some()) {
+                        clear_doc();
+                    }
+                    editing.set(None);
+                    // -- Reply belongs to the room it was started in
+                    if reply_to.with_untracked(|r| r.as_ref().is_some_and(|m| m.room.id != room.id)) {
+                        reply_to.set(None);
                     }
+                    // -- Rooms created outside the toolbar get their doc lazily
+                    let doc = self.docs
+                        .with_untracked(|d| d.get(&room.id).cloned())
+                        .unwrap_or_else(|| create_doc(room.id));
+                    self.active_doc.set(Some(doc.clone()));
+                    doc_signal.set(doc);
                 },
                 None => {}
             }
         });
+
+        // -- Keep the draft of the active room in sync with its doc
+        create_effect(move |_| {
+            doc_signal.with(|doc| doc.cache_rev().track());
+            if editing.with_untracked(|e| e.is_some()) { return }
+            let Some(room) = active_room.with_untracked(|ar| ar.as_ref().map(|ar| ar.id)) else { return };
+            let text = doc_signal.with_untracked(|doc| doc.rope_text().text.to_string());
+            let mut new_drafts = drafts.get_untracked();
+            if new_drafts.set(room, &text) {
+                trace!("effect: draft changed for {room}");
+                drafts.set(new_drafts);
+                save_debounced(drafts);
+            }
+        });
         
+        // -- Typing notifications for the active room
+        create_effect(move |prev_rev: Option<(Ulid, u64)>| {
+            let rev = doc_signal.with(|doc| doc.cache_rev().get());
+            let Some(room) = active_room.with_untracked(|ar| ar.as_ref().map(|ar| ar.id)) else {
+                return prev_rev.unwrap_or_default()
+            };
+            // -- Doc switch is not typing, only edits of the same doc are
+            let is_empty = doc_signal.with_untracked(|doc| doc.text().is_empty());
+            let send_typing = config.with_untracked(|cf| cf.notifications.send_typing);
+            if prev_rev.is_some_and(|(r, prev)| r == room && prev != rev) && !is_empty && send_typing {
+                trace!("effect: typing in {room}");
+                APP.with(|app| app.notify_typing(room));
+            }
+            (room, rev)
+        });
+
+
         create_effect(move |_| {
             info!("->> effect: create msg");
             send_msg.track();
-            let text = doc_signal.with_untracked(|doc| {
-                doc.rope_text().text.to_string()
-            });
+            let typed = doc_signal.with_untracked(|doc| doc.rope_text().text.to_string());
+            // -- Slash commands run instead of sending (edited msgs are saved as typed)
+            let typed = match editing.with_untracked(|e| e.is_some()) {
+                true => typed,
+                false => match run_slash(&typed) {
+                    None => unescape_slash(&typed).to_string(),
+                    Some(Ok(SlashOutcome::Send(text))) => text,
+                    Some(Ok(SlashOutcome::Done)) => { clear_doc(); return },
+                    Some(Err(e)) => {
+                        warn!("Slash command failed: {e}");
+                        slash_error.set(Some(e));
+                        return
+                    }
+                }
+            };
+            // -- `@username` of the room members are stored as mentions
+            let known: Vec<_> = active_room_members()
+                .into_iter()
+                .map(|acc| (acc.username, acc.acc_id))
+                .collect();
+            let text = encode_mentions(&typed, &known);
             if text.is_empty() {
                 warn!("Text is empty");
+                editing.set(None);
                 return
             };
             // -- Get active room
             if let Some(active_room) = active_room.get_untracked() {
                 info!("    ..for {}", active_room.id);
-                // -- Get message author (dummy for now)
+                // -- Save edited msg instead of creating a new one
+                if let Some(msg_id) = editing.get_untracked() {
+                    let edited = rooms.with_untracked(|rooms| {
+                        let room = rooms.get(&active_room.idx)?;
+                        let mut msg = room.msgs.with_untracked(|rc| rc.find_msg(msg_id).cloned())?;
+                        let mut new_msg = (*msg.msg).clone();
+                        new_msg.update_text(Datetime::default(), &text);
+                        msg.msg = Rc::new(new_msg);
+                        room.msgs.update(|rc| rc.update_one(&msg));
+                        Some(())
+                    });
+                    editing.set(None);
+                    if edited.is_some() {
+                        msg_event.set(MsgEvent::UpdatedFor { room: active_room.id, msg: msg_id });
+                        APP.with(|app| app.stop_typing(active_room.id));
+                        clear_doc();
+                    } else {
+                        error!("Edited msg not found in the room");
+                    }
+                    return
+                }
+                // -- Get message author (session user if in the room, dummy otherwise)
+                let user = APP.with(|app| app.user.get_untracked());
                 let (msg_author, owner) = {
                     let rooms_map = rooms.get_untracked();
                     let room = rooms_map.get(&active_room.idx).unwrap();
-                    if room.members.is_empty() {
+                    if room.owner.acc_id.id == user.acc_id.id {
+                        ((*user).clone(), true)
+                    } else if room.members.values().any(|m| m.acc_id.id == user.acc_id.id) {
+                        ((*user).clone(), false)
+                    } else if room.members.is_empty() {
                         (room.owner.clone(), true)
                     } else {
                         (room.members.values().last().unwrap().clone(), false)
@@ -133,6 +383,7 @@ impl IntoView for EditorViewData {
                     edited: None,
                     comments: None,
                     reactions: None,
+                    reply_to: reply_to.with_untracked(|r| r.as_ref().map(|m| m.msg.msg_id.clone())),
                     delivered_to_all: false,
                     viewed_by_all: false
                 };
@@ -150,31 +401,131 @@ impl IntoView for EditorViewData {
                     }
                 });
                 if is_success {
+                    reply_to.set(None);
                     msg_event.set(MsgEvent::NewFor(active_room.id));
-                    doc_signal.with_untracked(|doc| {
-                        let text_len = doc.text().len();
-                        doc.edit_single(Selection::region(0, text_len), "", EditType::DeleteSelection);
-                    });
+                    APP.with(|app| app.stop_typing(active_room.id));
+                    clear_doc();
                 }
             }
         });
             
-        stack((container(text_editor)
-            .style(|s| s
-                .flex_grow(1.)
-                .flex_shrink(2.)
-                .flex_basis(300.)
-                .min_width(100.)
-                .border(0.5)
-                .border_color(Color::NAVY)
+        let mention_popup = dyn_stack(
+            move || suggestions.get().into_iter().enumerate(),
+            |(idx, acc)| (*idx, acc.acc_id.id),
+            move |(idx, acc)| {
+                let av = avatar_of(&acc);
+                (
+                    img(move || av.to_vec()).style(|s| s.size(18., 18.).border_radius(9.)),
+                    label(move || acc.username.clone())
+                )
+                    .h_stack()
+                    .on_click_stop(move |_| {
+                        if let Some(acc) = suggestions.with_untracked(|s| s.get(idx).cloned()) {
+                            accept_mention(&acc);
+                        }
+                    })
+                    .style(move |s| s
+                        .gap(5.)
+                        .padding(3.)
+                        .items_center()
+                        .apply_if(selected.get() == idx, |s| s.background(colors().selected))
+                    )
+            }
+        )
+            .debug_name("mention popup")
+            .style(move |s| s
+                .flex_col()
+                .absolute()
+                .inset_left(5.)
+                .inset_bottom_pct(100.)
+                .z_index(5)
+                .min_width(150.)
+                .background(colors().surface)
+                .border(1.)
+                .border_color(colors().accent)
                 .border_radius(5.)
-                .padding(5.)
-            ),
+                .apply_if(suggestions.with(|s| s.is_empty()), |s| s.hide())
+            );
+
+        let slash_popup = dyn_stack(
+            move || slash_hints.get().into_iter().enumerate(),
+            |(idx, hint)| (*idx, hint.replacement.clone()),
+            move |(idx, hint)| label(move || hint.label.clone())
+                .on_click_stop(move |_| {
+                    if let Some(hint) = slash_hints.with_untracked(|h| h.get(idx).cloned()) {
+                        accept_slash(&hint);
+                    }
+                })
+                .style(move |s| s
+                    .padding(3.)
+                    .apply_if(selected.get() == idx, |s| s.background(colors().selected))
+                )
+        )
+            .debug_name("slash popup")
+            .style(move |s| s
+                .flex_col()
+                .absolute()
+                .inset_left(5.)
+                .inset_bottom_pct(100.)
+                .z_index(5)
+                .min_width(150.)
+                .background(colors().surface)
+                .border(1.)
+                .border_color(colors().accent)
+                .border_radius(5.)
+                .apply_if(slash_hints.with(|h| h.is_empty()), |s| s.hide())
+            );
+
+        let reply_banner = (
+            label(move || reply_to.with(|r| match r {
+                Some(msg) => t_with("editor.replying", &[("name", &msg.author.with(|a| a.username.clone())), ("text", &msg_snippet(msg))]),
+                None => String::new()
+            }))
+                .style(|s| s.flex_grow(1.).text_ellipsis().min_width(0.)),
+            button("✕").action(move || reply_to.set(None))
+        )
+            .h_stack()
+            .debug_name("reply banner")
+            .style(move |s| s
+                .gap(5.)
+                .padding_horiz(5.)
+                .items_center()
+                .border_left(3.)
+                .border_color(colors().text_muted)
+                .apply_if(reply_to.with(|r| r.is_none()), |s| s.hide())
+            );
+
+        stack((
+            mention_popup,
+            slash_popup,
+            reply_banner,
+            label(move || slash_error.get().unwrap_or_default())
+                .style(move |s| s
+                    .color(colors().danger)
+                    .apply_if(slash_error.with(|e| e.is_none()), |s| s.hide())
+                ),
+            t_label("editor.editing")
+                .style(move |s| s
+                    .color(colors().text_muted)
+                    .apply_if(editing.with(|e| e.is_none()), |s| s.hide())
+                ),
+            container(text_editor)
+                .style(|s| s
+                    .flex_grow(1.)
+                    .flex_shrink(2.)
+                    .flex_basis(300.)
+                    .min_width(100.)
+                    .border(0.5)
+                    .border_color(colors().accent)
+                    .border_radius(5.)
+                    .padding(5.)
+                ),
         )).debug_name("text editor")
         .style(|s| s
             // .padding(5.)
-            .background(Color::YELLOW)
-            .border_color(Color::BLACK)
+            .flex_col()
+            .background(colors().editor_bg)
+            .border_color(colors().border)
             .border(1.)
             .grid_column(Line {
                 start: GridPlacement::from_line_index(2),
@@ -188,21 +539,69 @@ impl IntoView for EditorViewData {
     }
 }
 
+/// Owner and members of the active room.
+fn active_room_members() -> Vec<Account> {
+    APP.with(|app| {
+        let Some(active) = app.active_room.get_untracked() else { return Vec::new() };
+        app.rooms.with_untracked(|rooms| rooms
+            .get(&active.idx)
+            .map(|room| std::iter::once(&room.owner).chain(room.members.values()).cloned().collect())
+            .unwrap_or_default()
+        )
+    })
+}
+
+/// Load the last msg of the session user in the active room into the `doc`.
+fn edit_last_msg(doc: Rc<dyn Document>, editing: RwSignal<Option<Ulid>>) -> bool {
+    let user = APP.with(|app| app.user.with_untracked(|u| u.acc_id.id));
+    let last = APP.with(|app| {
+        let active = app.active_room.get_untracked()?;
+        app.rooms.with_untracked(|rooms| {
+            rooms.get(&active.idx)?.msgs.with_untracked(|rc| {
+                rc.last_msg_matching(|m| m.msg.author.id == user).cloned()
+            })
+        })
+    });
+    match last {
+        Some(msg) => {
+            trace!("fn: edit_last_msg: {}", msg.id.id);
+            let text = display_text(&msg.msg.text.current, mention_name);
+            doc.edit_single(Selection::region(0, 0), &text, EditType::InsertChars);
+            editing.set(Some(msg.ulid()));
+            true
+        },
+        None => false
+    }
+}
+
 // MARK: ed_toolbar
 
 pub fn editor_toolbar_view() -> impl IntoView {
     let send_msg = use_context::<Trigger>().unwrap();
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let panes = use_context::<Panes>().unwrap();
+    let send_mode_menu = move || {
+        let ctrl_enter = config.with_untracked(|cf| cf.keymap == Keymap::ctrl_enter_to_send());
+        let mark = |on: bool, key: &str| if on { format!("✓ {}", t(key)) } else { t(key) };
+        Menu::new("")
+            .entry(MenuItem::new(mark(!ctrl_enter, "editor.send_enter"))
+                .action(move || config.update(|cf| cf.keymap = Keymap::enter_to_send())))
+            .entry(MenuItem::new(mark(ctrl_enter, "editor.send_ctrl_enter"))
+                .action(move || config.update(|cf| cf.keymap = Keymap::ctrl_enter_to_send())))
+    };
     stack((
         v_stack((
-            button("Send").action(move || send_msg.notify()),
-            button("Attach")
+            button(t_label("editor.send"))
+                .action(move || send_msg.notify())
+                .context_menu(send_mode_menu),
+            button(t_label("editor.attach"))
         )).style(|s| s.gap(5.)),
     )).debug_name("editor buttons")
-    .style(|s| s
+    .style(move |s| s
         .justify_center()
         .padding(5.)
-        .background(Color::RED)
-        .border_color(Color::BLACK)
+        .background(colors().editor_buttons_bg)
+        .border_color(colors().border)
         .border(1.)
         .grid_column(Line {
             start: GridPlacement::from_line_index(3),
@@ -212,5 +611,7 @@ pub fn editor_toolbar_view() -> impl IntoView {
             start: GridPlacement::from_line_index(4),
             end: GridPlacement::Span(1)
         })
+        // -- Keys still send in the compact layout
+        .apply_if(panes.is_compact(), |s| s.hide())
     )
 }
\ No newline at end of file
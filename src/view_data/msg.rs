@@ -1,25 +1,34 @@
 use std::rc::Rc;
-use std::sync::atomic::AtomicU16;
-use std::sync::atomic::Ordering;
-use std::time::Duration;
 
-use chrono_lite::Datetime;
+use floem::menu::{Menu, MenuItem};
 use floem::prelude::*;
+use floem::reactive::use_context;
 use floem::ViewId;
 use im::{vector, Vector};
 use tracing_lite::info;
 use ulid::Ulid;
 
-use crate::cont::msg::Text;
-use crate::util::{Id, Tb};
+use crate::util::{time_label, Id};
 // use crate::views::msg::{ComCtx, ReaCtx};
 use crate::common::CommonData;
+use crate::avatar::avatar_of;
+use crate::chunks::display::MsgLayout;
+use crate::config::{ChatConfig, Privacy};
+use crate::mentions::{display_text, mentions_of};
+use crate::views::presence::{status_of, with_presence};
+use crate::views::msgs::RoomMsgUpt;
+use crate::views::rich_text::{link_preview_view, mention_name, msg_text_view};
 use crate::cont::acc::Account;
 use crate::cont::msg::Msg;
+use crate::theme::colors;
+use crate::i18n::t;
 use super::session::APP;
+#[cfg(test)]
+use crate::views::toolbar::test_msg;
 
 
-static MSG_VIEW_COUNTER: AtomicU16 = AtomicU16::new(1);
+/// Max number of chars in the [msg_snippet].
+const SNIPPET_LEN: usize = 60;
 
 
 /// Contains data needed to display msg widget on the msgs list.
@@ -27,7 +36,8 @@ static MSG_VIEW_COUNTER: AtomicU16 = AtomicU16::new(1);
 pub struct MsgViewData {
     pub view_id: ViewId,
     pub id: Id,
-    pub author: Rc<Account>,
+    /// Live author profile (shared with other msgs of the author).
+    pub author: RwSignal<Rc<Account>>,
     pub room: Id,
     pub room_owner: bool,
     pub msg: Rc<Msg>,
@@ -40,63 +50,26 @@ impl MsgViewData {
     pub fn new(msg: Msg, author: &Account, owner: bool) -> Self {
         let cx = APP.with(|app| app.provide_scope());
         Self {
-            author: Rc::new(author.clone()),
+            id: msg.msg_id.clone(),
+            author: APP.with(|app| app.profile(author)),
             com: cx.create_rw_signal(vector!()),
             rea: cx.create_rw_signal(vector!()),
             room: msg.room_id.clone(),
             msg: Rc::new(msg),
             room_owner: owner,
             view_id: ViewId::new(),
-            id: Id::new(Tb::Msg),
             common_data: APP.with(|gs| gs.common_data.clone())
         }
     }
     
+    /// Msg with a numbered text (see [test_msg](crate::views::toolbar::test_msg)).
+    #[cfg(test)]
     pub fn new_from_click(room: Id, author: &Account) -> Self {
-        let msg_id = Id::new(Tb::Msg);
-        let m = Msg {
-            msg_id: msg_id.clone(),
-            room_id: room.clone(),
-            author: author.acc_id.clone(),
-            created: Datetime::default().sub_from(Duration::from_secs(5)),
-            sent: Some(Datetime::default()),
-            text: Text {
-                current: String::from(format!(
-                    "Really important message no: {}",
-                    MSG_VIEW_COUNTER.fetch_add(1, Ordering::Relaxed)
-                )),
-                edits: None,
-                last_edited: None
-            },
-            media: None,
-            edited: None,
-            comments: None,
-            reactions: None,
-            delivered_to_all: true,
-            viewed_by_all: true,
-        };
         let room_owner = APP.with(|app| app.active_room.with_untracked(|act_room| {
-            if let Some(ar) = act_room {
-                app.rooms.with_untracked(|rooms| {
-                    if let Some(r) = rooms.get(&ar.idx) {
-                        r.owner.acc_id.id == author.acc_id.id
-                    } else { false }
-                })
-            } else { false }
+            let Some(ar) = act_room else { return false };
+            app.rooms.with_untracked(|rooms| rooms.get(&ar.idx).is_some_and(|r| r.owner.acc_id.id == author.acc_id.id))
         }));
-        
-        let cx = APP.with(|app| app.provide_scope());
-        Self {
-            id: Id::new(Tb::Msg),
-            author: Rc::new(author.clone()),
-            room: room.clone(),
-            com: cx.create_rw_signal(vector!()),
-            rea: cx.create_rw_signal(vector!()),
-            msg: Rc::new(m),
-            room_owner,
-            view_id: ViewId::new(),
-            common_data: APP.with(|gs| gs.common_data.clone())
-        }
+        Self::new(test_msg(room, author), author, room_owner)
     }
 
     pub fn ulid(&self) -> Ulid {
@@ -105,35 +78,94 @@ impl MsgViewData {
 }
 
 
-impl IntoView for MsgViewData {
-    type V = floem::AnyView;
-    
-    fn into_view(self) -> Self::V {
+impl MsgViewData {
+    /// Build msg widget, showing author header only if required by the [MsgLayout].
+    pub fn view_with_layout(self, layout: MsgLayout) -> floem::AnyView {
         info!("->> into_view(msg) | {}", self.id.id);
         let text = self.msg.text.current.clone();
-        let time = self.msg.created.clone();
-        let author = self.author.username.clone();
+        let user = APP.with(|app| app.user.with_untracked(|u| u.acc_id.id));
+        let mentions_user = mentions_of(&text).contains(&user);
+        let preview = link_preview_view(&text);
+        let reply_to = use_context::<RwSignal<Option<MsgViewData>>>().unwrap();
+        let this = self.clone();
+        let raw = RwSignal::new(false);
+        let body = dyn_container(
+            move || raw.get(),
+            move |raw| match raw {
+                true => {
+                    let text = text.clone();
+                    label(move || text.clone()).into_any()
+                },
+                false => msg_text_view(&text)
+            }
+        ).context_menu(move || {
+            let title = t(if raw.get_untracked() { "msg.show_formatted" } else { "msg.show_raw" });
+            let this = this.clone();
+            Menu::new("")
+                .entry(MenuItem::new(t("msg.reply")).action(move || reply_to.set(Some(this.clone()))))
+                .entry(MenuItem::new(title).action(move || raw.update(|r| *r = !*r)))
+        });
+        let quote = match self.msg.reply_to.as_ref() {
+            Some(original) => reply_quote_view(self.room.id, original.id).into_any(),
+            None => empty().into_any()
+        };
+        let created = self.ulid().timestamp_ms();
+        let author = self.author;
+        let show_header = layout.show_header;
+        let header = (
+            with_presence(
+                img(move || author.with(avatar_of).to_vec()).style(|s| s.size(24., 24.).border_radius(12.)),
+                author.with_untracked(|a| a.acc_id.id)
+            ),
+            label(move || author.with(|a| a.username.clone())).style(|s| s.color(colors().text_muted)),
+            label(move || status_of(author.with(|a| a.acc_id.id)).unwrap_or_default())
+                .style(|s| s.color(colors().text_muted).font_size(11.))
+        )
+            .h_stack()
+            .debug_name("msg header")
+            .style(move |s| s
+                .gap(5.)
+                .items_center()
+                .apply_if(!show_header, |s| s.hide())
+            )
+            .context_menu(move || author_menu(author.with_untracked(|a| a.acc_id.id)));
         (
-            author.style(|s| s.color(Color::GRAY)),
-            text,
-            time.human_formatted().style(|s| s.color(Color::GRAY))
+            header,
+            quote,
+            body,
+            preview,
+            label(move || time_label(created)).style(|s| s.color(colors().text_muted))
         )
             .v_stack()
             .debug_name("msg")
             .style(move |s| s
                 .justify_between()
                 .border(1.)
-                .border_color(Color::BLACK)
+                .border_color(colors().border)
                 .border_radius(5.)
                 .padding(5.)
                 .min_height(40.)
                 // .min_width_pct(20.)
                 .max_width_pct(80.)
+                .apply_if(!show_header, |s| s.min_height(20.))
+                .apply_if(mentions_user, |s| s
+                    .background(colors().mention_bg)
+                    .border_color(colors().mention_self)
+                )
             )
             .into_any()
     }
 }
 
+
+impl IntoView for MsgViewData {
+    type V = floem::AnyView;
+    
+    fn into_view(self) -> Self::V {
+        self.view_with_layout(MsgLayout::default())
+    }
+}
+
 impl PartialOrd for MsgViewData {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.id.id.cmp(&other.id.id))
@@ -154,6 +186,63 @@ impl PartialEq for MsgViewData {
 
 impl Eq for MsgViewData {}
 
+/// Mute / block actions for the msg author (empty for the session user).
+fn author_menu(author: Ulid) -> Menu {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let user = APP.with(|app| app.user.with_untracked(|u| u.acc_id.id));
+    if author == user {
+        return Menu::new("")
+    }
+    let privacy = config.with_untracked(|cf| cf.privacy_of(&user));
+    let toggle = move |change: fn(&mut Privacy, Ulid)| config.update(|cf| {
+        change(cf.privacy.entry(user).or_default(), author)
+    });
+    Menu::new("")
+        .entry(MenuItem::new(t(if privacy.is_muted(&author) { "msg.unmute_user" } else { "msg.mute_user" }))
+            .action(move || toggle(|p, acc| p.toggle_mute(acc))))
+        .entry(MenuItem::new(t(if privacy.is_blocked(&author) { "msg.unblock_user" } else { "msg.block_user" }))
+            .action(move || toggle(|p, acc| p.toggle_block(acc))))
+}
+
+/// One line summary of the msg, eg. for reply quotes.
+pub fn msg_snippet(msg: &MsgViewData) -> String {
+    let text = display_text(&msg.msg.text.current, mention_name);
+    let line = text.lines().next().unwrap_or_default();
+    match line.char_indices().nth(SNIPPET_LEN) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None if text.lines().nth(1).is_some() => format!("{line}…"),
+        None => line.to_string()
+    }
+}
+
+/// Compact quote of the replied msg; click scrolls to the original.
+fn reply_quote_view(room: Ulid, original: Ulid) -> impl IntoView {
+    let found = APP.with(|app| app.rooms.with_untracked(|rooms| rooms
+        .values()
+        .find(|r| r.room_id.id == room)
+        .and_then(|r| r.msgs.with_untracked(|rc| rc.find_msg(original).cloned()))
+    ));
+    let text = match found {
+        Some(msg) => format!("↪ {}: {}", msg.author.with_untracked(|a| a.username.clone()), msg_snippet(&msg)),
+        None => format!("↪ {}", t("msg.original_missing"))
+    };
+    label(move || text.clone())
+        .on_click_stop(move |_| APP.with(|app| app.rooms_tabs.with_untracked(|rt| {
+            if let Some(tab) = rt.get(&room) {
+                tab.2.set(RoomMsgUpt::ScrollTo(original));
+            }
+        })))
+        .style(|s| s
+            .font_size(12.)
+            .color(colors().text_muted)
+            .padding_horiz(5.)
+            .border_left(3.)
+            .border_color(colors().text_muted)
+            .text_ellipsis()
+            .max_width_full()
+        )
+}
+
 // MARK: Com Rea
 
 #[derive(Clone, Debug)]
@@ -4,18 +4,30 @@ use std::ops::Range;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
+use chrono_lite::Datetime;
+use floem::menu::{Menu, MenuItem};
 use floem::peniko::{Blob, Format};
-use floem::reactive::{batch, create_effect, provide_context, Memo, ReadSignal, Scope, Trigger};
+use floem::style::{Background, Transition};
+use floem::reactive::{batch, create_effect, create_memo, provide_context, use_context, Memo, ReadSignal, Scope, Trigger};
 use floem::{prelude::*, ViewId};
 use tracing_lite::{debug, trace, warn};
 use ulid::Ulid;
 
-use crate::cont::acc::Account;
+use crate::avatar::{avatar_of, initials_avatar};
+use crate::config::ChatConfig;
+use crate::cont::acc::{Account, Member};
+use crate::cont::room::Room;
+use crate::mentions::{display_text, mentions_of};
 use crate::util::{Id, Tb};
 use crate::common::CommonData;
 use crate::chunks::RoomMsgChunks;
 use crate::views::msgs::RoomMsgUpt;
+use crate::views::rich_text::mention_name;
+use crate::views::rooms::visible_rooms_order;
+use crate::theme::colors;
+use crate::i18n::{t, t_with};
 
 use super::msg::MsgViewData;
 use super::session::APP;
@@ -30,6 +42,8 @@ pub struct RoomViewData {
     pub view_id: ViewId,
     pub room_id: Id,
     pub room_idx: RoomTabIdx,
+    pub name: RwSignal<String>,
+    pub avatar: RwSignal<Option<Rc<Vec<u8>>>>,
 
     pub owner: Account,
     pub members: HashMap<Ulid, Account>,
@@ -44,44 +58,71 @@ pub struct RoomViewData {
     // pub display_state: RwSignal<DisplayState>,
     pub unread: RwSignal<bool>,
     pub num_unread: RwSignal<u16>,
+    /// Unread msgs mentioning the session user.
+    pub num_mentions: RwSignal<u16>,
+    /// Notified when the room moves up the rooms list (starts its animation).
+    pub moved: Trigger,
+    /// Scope of the room signals, disposed when the room is removed.
+    pub cx: Scope,
 
     pub common_data: Rc<CommonData>
 }
 
 impl RoomViewData {
-    pub fn new_from_click() -> Self {
+    /// Create view data from the [Room] confirmed by the backend.
+    /// Accounts are resolved from the [UISession](super::session::UISession).
+    pub fn from_room(room: Room) -> Self {
         let cx = APP.with(|app| app.provide_scope().create_child());
-        let mut accs_list = Vec::new();
-        APP.with(|app| {
-            app.accounts.with_untracked(|accs|
-                accs_list = accs.values().cloned().collect::<Vec<Account>>()
-            );
-            accs_list.push(app.user.as_ref().clone());
-        });
-        let id = Id::new(Tb::Room);
-        let msgs = cx.create_rw_signal(RoomMsgChunks::new(id.clone()));
-        let msgs_count = cx.create_rw_signal(0);
-        let owner = accs_list.remove(0);
-        let _msgs_id = SignalGet::id(&msgs);
+        let id = room.room_id.clone();
+        let (owner, members) = APP.with(|app| app.resolve_members(&room));
         Self {
             room_idx: RoomTabIdx::new(id.id),
-            msgs,
+            name: cx.create_rw_signal(room.name),
+            avatar: cx.create_rw_signal(room.avatar.map(Rc::new)),
+            msgs: cx.create_rw_signal(RoomMsgChunks::new(id.clone())),
             num_unread: cx.create_rw_signal(0),
+            num_mentions: cx.create_rw_signal(0),
             unread: cx.create_rw_signal(false),
-            description: cx.create_rw_signal(None),
+            description: cx.create_rw_signal(room.description),
             owner,
-            members: HashMap::from_iter(accs_list.into_iter().map(|acc | (acc.acc_id.id, acc))),
+            members,
             view_id: ViewId::new(),
             room_id: id,
             last_msg: cx.create_rw_signal(None),
             common_data: APP.with(|gs| gs.common_data.clone()),
             get_update: cx.create_rw_signal(RoomMsgUpt::NoUpdate),
-            msgs_count,
+            msgs_count: cx.create_rw_signal(0),
             is_active: cx.create_rw_signal(Cell::new(false)),
-            // display_state: todo!(),
+            moved: cx.create_trigger(),
+            cx
+        }
+    }
+
+    /// Recreate [Room] from the current view data state.
+    pub fn to_room(&self) -> Room {
+        let since = Datetime::default();
+        let members = std::iter::once(&self.owner)
+            .chain(self.members.values())
+            .map(|acc| Member { member_id: acc.acc_id.clone(), since: since.clone() })
+            .collect();
+        Room {
+            room_id: self.room_id.clone(),
+            name: self.name.get_untracked(),
+            members,
+            active_members: Vec::new(),
+            active_invites: None,
+            owner: self.owner.acc_id.clone(),
+            created: since,
+            description: self.description.get_untracked(),
+            avatar: self.avatar.get_untracked().map(|av| av.to_vec())
         }
     }
 
+    /// Check if given account is the room owner.
+    pub fn is_owner(&self, acc: &Ulid) -> bool {
+        self.owner.acc_id.id == *acc
+    }
+
     /// Return room index value.
     pub fn idx(&self) -> usize {
         self.room_idx.idx
@@ -98,6 +139,39 @@ impl RoomViewData {
         })
     }
 
+    /// Timestamp (ms) of the last room activity: last msg or room creation.
+    pub fn last_activity(&self) -> u64 {
+        self.last_msg.with(|lm| match lm {
+            Some(msg) => msg.ulid().timestamp_ms(),
+            None => self.room_id.id.timestamp_ms()
+        })
+    }
+
+    /// Make this room the active one and mark its msgs as read.
+    pub fn select(&self) {
+        let active = APP.with(|a| a.active_room);
+        batch(|| {
+            self.num_unread.set(0);
+            self.num_mentions.set(0);
+        });
+        // -- If this room is not selected, select it
+        match active.get_untracked() {
+            Some(id) if id.id == self.room_id.id => {
+                trace!("fn: select: already selected: Some({})", id.idx);
+            },
+            Some(_id) => {
+                trace!("fn: select: new room selected: {}", self.room_idx.idx);
+                active.set(Some(self.room_idx.clone()));
+                self.is_active.update(|cell| cell.set(true));
+            },
+            None => {
+                warn!("fn: select: active_room is None, selecting current: {}", self.room_idx.idx);
+                active.set(Some(self.room_idx.clone()));
+                self.is_active.update(|cell| cell.set(true));
+            }
+        };
+    }
+
     /// Compare chunks msg count and update [Cell] if does not match.
     pub fn update_msg_count(&self) {
         let count = self.msgs.with_untracked(|chunks| chunks.total_msgs);
@@ -105,6 +179,25 @@ impl RoomViewData {
             self.msgs_count.set(count)
         }
     }
+
+    /// Count new msgs as unread for the `user` (and those mentioning them as mentions).  
+    /// Msgs of the user and of the accounts they blocked are not counted.
+    pub fn count_unread(&self, new_msgs: &[MsgViewData], user: &Ulid, config: &ChatConfig) {
+        let privacy = config.privacy_of(user);
+        let (mut unread, mut mentions) = (0u16, 0u16);
+        for msg in new_msgs {
+            let author = msg.msg.author.id;
+            if author == *user || privacy.is_blocked(&author) { continue }
+            let mentioned = mentions_of(&msg.msg.text.current).contains(user);
+            if mentioned { mentions = mentions.saturating_add(1) }
+            if mentioned || !config.notifications.mentions_only { unread = unread.saturating_add(1) }
+        }
+        if unread == 0 { return }
+        batch(|| {
+            self.num_unread.update(|n| *n = n.saturating_add(unread));
+            if mentions > 0 { self.num_mentions.update(|n| *n = n.saturating_add(mentions)) }
+        });
+    }
 }
 
 
@@ -137,11 +230,16 @@ impl IntoView for RoomViewData {
     /// - [x] Tracks and updates last msg status
     ///     - [ ] updates it in fine-grained way
     fn into_view(self) -> Self::V {
-        let _this_room = self.room_id.id;
+        let this_room = self.room_id.id;
         let active = APP.with(|a| a.active_room);
+        let config = use_context::<RwSignal<ChatConfig>>().unwrap();
         let last_msg = self.last_msg;
         let msgs = self.msgs;
         let get_upt = self.get_update;
+        let num_unread = self.num_unread;
+        let num_mentions = self.num_mentions;
+        let moved = self.moved;
+        let pinned = create_memo(move |_| config.with(|cf| cf.rooms_order.is_pinned(&this_room)));
         let need_avatar_change = Trigger::new();
         let need_label_change = Trigger::new();
         let need_text_change = Trigger::new();
@@ -151,17 +249,21 @@ impl IntoView for RoomViewData {
         create_effect(move |_| {
             need_last_msg_upt.track();
             debug!("== effect(room_into_view): need_last_msg_upt");
+            // -- Preview skips msgs of blocked accounts (and follows list changes)
+            let user = APP.with(|app| app.user.with(|u| u.acc_id.id));
+            let privacy = config.with(|cf| cf.privacy_of(&user));
             msgs.with_untracked(|msgs| {
-                if let Some(msg) = msgs.last_msg() {
-                    self.last_msg.set(Some(msg.clone()));
-                    batch(|| {
-                        need_avatar_change.notify();
-                        need_label_change.notify();
-                        need_text_change.notify();
-                    })
-                } else {
+                let last = msgs.last_msg_matching(|m| !privacy.is_blocked(&m.msg.author.id));
+                if last.is_none() && msgs.total_msgs == 0 {
                     warn!("fn: update_last_msg: unable to update `last_msg` field");
+                    return
                 }
+                self.last_msg.set(last.cloned());
+                batch(|| {
+                    need_avatar_change.notify();
+                    need_label_change.notify();
+                    need_text_change.notify();
+                })
             });
         });
         
@@ -169,6 +271,7 @@ impl IntoView for RoomViewData {
         create_effect(move |_| {
             debug!("== effect(room_view_data): msg event");
             match get_upt.get() {
+                // -- Unread msgs are counted when received (see UISession::receive_msgs)
                 RoomMsgUpt::New => {
                     trace!("effect | room_view_data | get_update: New");
                     need_last_msg_upt.notify();
@@ -181,7 +284,7 @@ impl IntoView for RoomViewData {
                     last_msg.with_untracked(|lm| {
                         if let Some(last_msg) = lm {
                             if last_msg.id.id == msg {
-                                need_text_change.notify();
+                                need_last_msg_upt.notify();
                             }
                         } else {
                             msgs.with_untracked(|msgs| {
@@ -201,12 +304,19 @@ impl IntoView for RoomViewData {
 
         // --------- views builders ---------- //
 
+        let drafts = APP.with(|app| app.drafts);
         let last_msg_text = label(move || {
             need_text_change.track();
             trace!("label: last_msg_text");
+            // -- Unsent text takes precedence over last msg (except in the active room)
+            if !active.with(|a| a.as_ref().is_some_and(|a| a.id == this_room)) {
+                if let Some(draft) = drafts.with(|d| d.preview(&this_room)) {
+                    return t_with("rooms.draft", &[("text", &draft)])
+                }
+            }
             last_msg.with_untracked(|msg| {
                 if let Some(msg) = msg {
-                    let text = msg.msg.text.current.clone();
+                    let text = display_text(&msg.msg.text.current, mention_name);
                     let more_than_two_columns = text.lines().count() > 2;
                     // -- trim msg if needed
                     if more_than_two_columns {
@@ -216,22 +326,30 @@ impl IntoView for RoomViewData {
                     } else {
                         text
                     }
-                } else { "no msgs yet..".into() }
+                } else { t("rooms.no_msgs") }
             })
         }).style(|s| s.max_size_full().text_ellipsis());
 
+        let room_avatar = self.avatar;
+        let room_name = self.name;
         let last_msg_avatar = dyn_view(move || {
             need_avatar_change.track();
             trace!("dyn_view for avatar");
+            // -- Room avatar takes precedence over last msg author avatar
+            let room_av = room_avatar.get();
             img({
                 move || {
+                    if let Some(av) = &room_av {
+                        return av.to_vec()
+                    }
                     let img_data = last_msg.with_untracked(|last_msg| {
                         if let Some(msg) = last_msg {
-                            let new_av = msg.author.av.clone();
+                            // -- Tracked, so avatar follows profile changes
+                            let new_av = msg.author.with(avatar_of);
                             trace!("img author");
                             return new_av
                         } else {
-                            Rc::new(Vec::with_capacity(0))
+                            initials_avatar(&this_room, &room_name.get())
                         }
                     });
                     img_data.to_vec()
@@ -240,7 +358,7 @@ impl IntoView for RoomViewData {
             .style(|s| s.size(50., 50.))
         }).style(|s| s
             .border(1.)
-            .border_color(Color::NAVY)
+            .border_color(colors().accent)
             .border_radius(5.)
         );
         
@@ -250,19 +368,42 @@ impl IntoView for RoomViewData {
             trace!("label: last_msg_author");
             last_msg.with_untracked(|last_msg| {
                 if let Some(msg) = last_msg {
-                    msg.author.username.clone()
+                    msg.author.with(|a| a.username.clone())
                 } else {
                     String::with_capacity(0)
                 }
             })
-        }).style(|s| s
-            .padding(5.)
-            .font_bold()
-            .font_size(22.)
-        );
+        }).style(|s| s.color(colors().text_muted));
+
+        let room_title = (
+            label(move || room_name.get()).style(|s| s
+                .font_bold()
+                .font_size(22.)
+                .text_ellipsis()
+            ),
+            last_msg_author
+        )
+            .v_stack()
+            .style(|s| s.padding(5.).min_width(0.));
         
 
-        let top_view = (last_msg_avatar, last_msg_author)
+        let pin_mark = label(move || {
+            if pinned.get() { "📌" } else { "" }
+        }).style(|s| s.margin_left_auto());
+
+        let unread_badge = label(move || match num_mentions.get() {
+            0 => num_unread.get().to_string(),
+            m => format!("{} @{m}", num_unread.get())
+        })
+            .style(move |s| s
+                .padding_horiz(5.)
+                .border_radius(8.)
+                .color(colors().badge_text)
+                .background(colors().badge_bg)
+                .apply_if(num_unread.get() == 0 || !config.with(|cf| cf.notifications.unread_badges), |s| s.hide())
+            );
+
+        let top_view = (last_msg_avatar, room_title, pin_mark, unread_badge)
             .h_stack()
             .debug_name("top_room")
             .style(|s| s.gap(10.).items_center());
@@ -277,34 +418,38 @@ impl IntoView for RoomViewData {
             .style(move |s| s
                 .max_width(200.)
                 .padding(2.)
+                .transition(Background, Transition::linear(Duration::from_millis(150)))
                 .max_height(100.)
                 .border(0.5)
-                .border_color(Color::NAVY)
+                .border_color(colors().accent)
                 .apply_if(active.get().is_some_and(|r| r.id == self.room_id.id), |s| s
-                    .background(Color::LIGHT_GRAY)
+                    .background(colors().hover)
                     .border(2)
-                    .border_color(Color::DARK_BLUE)
+                    .border_color(colors().accent)
                 )
             )
-            .on_click_stop(move |_| {
-                // -- If this room is not selected, select it
-                match active.get_untracked() {
-                    Some(id) if id.id == self.room_id.id => {
-                        trace!("effect: select_room: already selected: Some({})", id.idx);
-                        // APP.with(|app| app.rooms_tabs)
-                    },
-                    Some(_id) => {
-                        trace!("effect: select_room: new room selected: {}", self.room_idx.idx);
-                        active.set(Some(self.room_idx.clone()));
-                        self.is_active.update(|cell| cell.set(true));
-                    },
-                    None => {
-                        warn!("effect: select_room: fetched active_room is None, selecting current: {}", self.room_idx.idx);
-                        active.set(Some(self.room_idx.clone()));
-                        self.is_active.update(|cell| cell.set(true));
-                        // self.is_active.set(true);
-                    }
+            .animation(move |a| a
+                .run_on_create(false)
+                .trigger(moved)
+                .duration(Duration::from_millis(250))
+                .ease_in_out()
+                .keyframe(0, |f| f.style(|s| s.translate_y(20.).background(colors().hover)))
+                .keyframe(100, |f| f.computed_style())
+            )
+            .on_click_stop(move |_| self.select())
+            .context_menu(move || {
+                let pinned = pinned.get_untracked();
+                let move_room = move |up: bool| {
+                    let current = config.with_untracked(|cf| visible_rooms_order(&cf.rooms_order));
+                    config.update(|cf| cf.rooms_order.move_room(this_room, up, &current));
                 };
+                Menu::new("")
+                    .entry(MenuItem::new(t(if pinned { "rooms.unpin" } else { "rooms.pin" })).action(move || {
+                        config.update(|cf| cf.rooms_order.toggle_pin(this_room));
+                    }))
+                    .separator()
+                    .entry(MenuItem::new(t("rooms.move_up")).action(move || move_room(true)))
+                    .entry(MenuItem::new(t("rooms.move_down")).action(move || move_room(false)))
             })
             .into_any()
     }
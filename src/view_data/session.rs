@@ -1,17 +1,34 @@
 use std::collections::{BTreeMap, HashMap};
-use std::cell::LazyCell;
+use std::cell::{Cell, LazyCell, RefCell};
 use std::rc::Rc;
+use std::time::Duration;
 
-use floem::reactive::Scope;
+use floem::action::exec_after;
+use floem::reactive::{batch, use_context, Scope};
 use floem::ViewId;
 use floem::{prelude::*, reactive::Memo};
+use tracing_lite::{debug, error, info, warn};
 use ulid::Ulid;
 
+use crate::auth::{AuthSession, StoredSession};
+use crate::backend::{Backend, LocalBackend};
+use crate::config::ChatConfig;
+use crate::drafts::{save_debounced, Drafts};
+use crate::links::{LocalPreviews, PreviewFetcher};
+use crate::theme::Palette;
+use crate::cont::acc::{Presence, PresenceInfo};
+use crate::cont::room::Room;
+use crate::util::{now_ms, Id, Tb, Throttle};
 use crate::views::msgs::RoomMsgUpt;
 use crate::{common::CommonData, cont::acc::Account};
+use super::msg::MsgViewData;
 use super::room::{RoomTabIdx, RoomViewData};
 
 
+/// Min time (ms) between typing notifications sent for the room.
+pub const TYPING_THROTTLE: u64 = 3_000;
+
+
 thread_local! {
     /// Central structure for the UI thread.
     pub static APP: LazyCell<UISession> = LazyCell::new(UISession::new);
@@ -21,8 +38,10 @@ thread_local! {
 
 /// Contains all data needed to manage user session.
 pub struct UISession {
-    /// Account of the session user.
-    pub user: Rc<Account>,
+    /// Account of the session user (placeholder until signed in).
+    pub user: RwSignal<Rc<Account>>,
+    /// Token of the authenticated session (`None` when signed out).
+    pub token: RwSignal<Option<String>>,
     /// List of all users.
     pub accounts: RwSignal<HashMap<Ulid, Account>>,
     /// List of all user rooms.
@@ -35,6 +54,27 @@ pub struct UISession {
     // /// The index of the active tab.
     // pub active_tab: RwSignal<usize>,
     pub common_data: Rc<CommonData>,
+    /// Backend connection of the active account.
+    backend: RefCell<Rc<dyn Backend>>,
+    /// Shared, live profiles of the accounts shown in msgs and rooms.
+    profiles: RefCell<HashMap<Ulid, RwSignal<Rc<Account>>>>,
+    /// Known presence of the accounts.
+    pub presence: RwSignal<HashMap<Ulid, PresenceInfo>>,
+    /// Status lines of the accounts (shown with their presence).
+    pub statuses: RwSignal<HashMap<Ulid, String>>,
+    /// Accounts typing in the rooms, K: room id.
+    pub typing: RwSignal<HashMap<Ulid, Vec<Ulid>>>,
+    /// Time (unix ms) of the last user activity.
+    activity: Cell<u64>,
+    typing_throttle: RefCell<Throttle<Ulid>>,
+    /// Other signed in accounts, waiting to be switched to.
+    pub parked: RwSignal<Vec<AccountSession>>,
+    /// Source of the link previews.
+    previews: RefCell<Rc<dyn PreviewFetcher>>,
+    /// Unsent editor text of the rooms.
+    pub drafts: RwSignal<Drafts>,
+    /// Colors of the current theme (see [crate::theme::colors]).
+    pub theme: RwSignal<Palette>,
     // /// Stores info what range of its msgs is loaded.
     // pub active_room_msgs_data: RwSignal<RoomMsgChunks>,
     pub scope: Scope
@@ -45,19 +85,31 @@ impl UISession {
     pub fn new() -> Self {
         let cx = Scope::new();
         // println!("UISession scope: {cx:#?}");
+        // -- Known accounts (dummy for now)
         let mut accs = Vec::with_capacity(3);
         while let Some(acc) = Account::new_from_click() {
             accs.push(acc);
         }
-        let user = Rc::new(accs.remove(0));
         Self {
-            user,
+            user: cx.create_rw_signal(Rc::new(Account::signed_out())),
+            token: cx.create_rw_signal(None),
             accounts: cx.create_rw_signal(HashMap::from_iter(accs.into_iter().map(|acc| (acc.acc_id.id, acc)))),
             rooms: cx.create_rw_signal(BTreeMap::new()),
             rooms_tabs: cx.create_rw_signal(HashMap::new()),
             rooms_tabs_count: cx.create_memo(|_| 0),
             active_room: cx.create_rw_signal(None),
             common_data: Rc::new(CommonData::default()),
+            backend: RefCell::new(Rc::new(LocalBackend::new())),
+            profiles: RefCell::new(HashMap::new()),
+            presence: cx.create_rw_signal(HashMap::new()),
+            statuses: cx.create_rw_signal(HashMap::new()),
+            typing: cx.create_rw_signal(HashMap::new()),
+            activity: Cell::new(now_ms()),
+            typing_throttle: RefCell::new(Throttle::new(TYPING_THROTTLE)),
+            parked: cx.create_rw_signal(Vec::new()),
+            previews: RefCell::new(Rc::new(LocalPreviews)),
+            drafts: cx.create_rw_signal(Drafts::load()),
+            theme: cx.create_rw_signal(Palette::light()),
             scope: cx
         }
     }
@@ -66,4 +118,382 @@ impl UISession {
     pub fn provide_scope(&self) -> Scope {
         self.scope
     }
-}
\ No newline at end of file
+
+    /// Backend connection of the active account.
+    pub fn backend(&self) -> Rc<dyn Backend> {
+        self.backend.borrow().clone()
+    }
+
+    /// Source of the link previews.
+    pub fn previews(&self) -> Rc<dyn PreviewFetcher> {
+        self.previews.borrow().clone()
+    }
+
+    /// Replace the source of the link previews (eg. with [NoPreviews](crate::links::NoPreviews)).
+    pub fn set_previews(&self, previews: Rc<dyn PreviewFetcher>) {
+        *self.previews.borrow_mut() = previews;
+    }
+
+    /// Check if there is an authenticated session.
+    pub fn is_signed_in(&self) -> bool {
+        self.token.with(|t| t.is_some())
+    }
+
+    /// Populate session with the authenticated account.
+    /// When `remember` is set, session is stored to be resumed on the next start,
+    /// otherwise the session stored earlier for the account is forgotten.
+    pub fn sign_in(&self, auth: AuthSession, remember: bool) {
+        info!("Signed in as {}", auth.account.username);
+        match remember {
+            true => {
+                StoredSession {
+                    acc_id: auth.account.acc_id.id,
+                    username: auth.account.username.clone(),
+                    token: auth.token.clone()
+                }.save();
+            },
+            false => StoredSession::forget(&auth.account.acc_id.id)
+        }
+        self.mark_active();
+        if let Err(e) = self.backend().set_presence(&auth.account.acc_id, Presence::Online) {
+            warn!("Failed to set presence: {e}");
+        }
+        batch(|| {
+            self.accounts.update(|accs| { accs.remove(&auth.account.acc_id.id); });
+            self.user.set(Rc::new(auth.account));
+            self.token.set(Some(auth.token));
+        });
+    }
+
+    /// Sign in another account (using its own backend connection) and make it active.
+    /// Currently active account is parked with all its state.
+    pub fn add_account(&self, auth: AuthSession, backend: Rc<dyn Backend>, remember: bool) {
+        let already_in = self.user.with_untracked(|u| u.acc_id == auth.account.acc_id)
+            || self.parked.with_untracked(|p| p.iter().any(|s| s.user.acc_id == auth.account.acc_id));
+        if already_in {
+            warn!("fn: add_account: {} is already signed in", auth.account.username);
+            return
+        }
+        let contacts = self.accounts.get_untracked();
+        batch(|| {
+            if let Some(current) = self.take_active() {
+                self.parked.update(|p| p.push(current));
+            }
+            self.restore(AccountSession {
+                user: Rc::new(Account::signed_out()),
+                token: String::new(),
+                accounts: contacts,
+                rooms: BTreeMap::new(),
+                rooms_tabs: HashMap::new(),
+                active_room: None,
+                backend
+            });
+            self.sign_in(auth, remember);
+        });
+    }
+
+    /// Make the parked account active, parking the current one.
+    pub fn switch_account(&self, acc: &Ulid) {
+        let mut target = None;
+        self.parked.update(|p| {
+            if let Some(pos) = p.iter().position(|s| s.user.acc_id.id == *acc) {
+                target = Some(p.remove(pos));
+            }
+        });
+        let Some(target) = target else {
+            debug!("fn: switch_account: {acc} is not parked");
+            return
+        };
+        info!("Switched to {}", target.user.username);
+        batch(|| {
+            if let Some(current) = self.take_active() {
+                self.parked.update(|p| p.push(current));
+            }
+            self.restore(target);
+        });
+    }
+
+    /// Invalidate the active session and forget it.  
+    /// Next parked account (if any) becomes active, otherwise all rooms are cleared.
+    pub fn sign_out(&self) {
+        let Some(token) = self.token.get_untracked() else { return };
+        let acc = self.user.with_untracked(|u| u.acc_id.clone());
+        if let Err(e) = self.backend().set_presence(&acc, Presence::Offline) {
+            warn!("Failed to set presence: {e}");
+        }
+        if let Err(e) = self.backend().logout(&token) {
+            warn!("Failed to sign out on the backend: {e}");
+        }
+        StoredSession::forget(&acc.id);
+        let mut next = None;
+        self.parked.update(|p| if !p.is_empty() { next = Some(p.remove(0)) });
+        batch(|| match next {
+            Some(next) => self.restore(next),
+            None => {
+                self.active_room.set(None);
+                self.rooms_tabs.set(HashMap::new());
+                self.rooms.set(BTreeMap::new());
+                self.user.set(Rc::new(Account::signed_out()));
+                self.token.set(None);
+            }
+        });
+    }
+
+    /// Remember the user is active (not away).
+    pub fn mark_active(&self) {
+        self.activity.set(now_ms());
+    }
+
+    /// Time (ms) since the last user activity.
+    pub fn idle_for(&self) -> u64 {
+        now_ms().saturating_sub(self.activity.get())
+    }
+
+    /// Tell the backend the user is typing in the room (at most once per [TYPING_THROTTLE]).
+    pub fn notify_typing(&self, room: Ulid) {
+        self.mark_active();
+        if !self.typing_throttle.borrow_mut().ready(room, now_ms()) { return }
+        let acc = self.user.with_untracked(|u| u.acc_id.clone());
+        if let Err(e) = self.backend().send_typing(&Id { tb: Tb::Room, id: room }, &acc) {
+            warn!("Failed to send typing notification: {e}");
+        }
+    }
+
+    /// Tell the backend the user stopped typing in the room (the msg was sent),
+    /// so the next edit notifies again right away.
+    pub fn stop_typing(&self, room: Ulid) {
+        self.typing_throttle.borrow_mut().reset(&room);
+        let acc = self.user.with_untracked(|u| u.acc_id.clone());
+        if let Err(e) = self.backend().stop_typing(&Id { tb: Tb::Room, id: room }, &acc) {
+            warn!("Failed to send typing stop: {e}");
+        }
+    }
+
+    /// Live profile of the account, shared by every view showing it.
+    pub fn profile(&self, acc: &Account) -> RwSignal<Rc<Account>> {
+        *self.profiles
+            .borrow_mut()
+            .entry(acc.acc_id.id)
+            .or_insert_with(|| self.scope.create_rw_signal(Rc::new(acc.clone())))
+    }
+
+    /// Propagate changed profile onto the session user, known accounts, rooms and msgs.
+    pub fn update_profile(&self, acc: Account) {
+        let id = acc.acc_id.id;
+        StoredSession::rename(&id, &acc.username);
+        let profile = self.profiles.borrow().get(&id).copied();
+        let replace = |rooms: &mut BTreeMap<usize, RoomViewData>| {
+            for room in rooms.values_mut() {
+                if room.owner.acc_id.id == id {
+                    room.owner = acc.clone();
+                }
+                if let Some(member) = room.members.get_mut(&id) {
+                    *member = acc.clone();
+                }
+            }
+        };
+        batch(|| {
+            if self.user.with_untracked(|u| u.acc_id.id == id) {
+                self.user.set(Rc::new(acc.clone()));
+            }
+            if self.accounts.with_untracked(|accs| accs.contains_key(&id)) {
+                self.accounts.update(|accs| { accs.insert(id, acc.clone()); });
+            }
+            self.rooms.update(|rooms| replace(rooms));
+            self.parked.update(|parked| for session in parked.iter_mut() {
+                if session.user.acc_id.id == id {
+                    session.user = Rc::new(acc.clone());
+                }
+                if let Some(known) = session.accounts.get_mut(&id) {
+                    *known = acc.clone();
+                }
+                replace(&mut session.rooms);
+            });
+            if let Some(profile) = profile {
+                profile.set(Rc::new(acc.clone()));
+            }
+        });
+    }
+
+    /// Signed in accounts with their unread msgs count, active account first.
+    pub fn signed_in_accounts(&self) -> Vec<(Rc<Account>, u16)> {
+        let mut accs = Vec::new();
+        if self.is_signed_in() {
+            let unread = self.rooms.with(|rooms| count_unread(rooms));
+            accs.push((self.user.get(), unread));
+        }
+        self.parked.with(|p| accs.extend(p.iter().map(|s| (s.user.clone(), count_unread(&s.rooms)))));
+        accs
+    }
+
+    /// Unread msgs count across all signed in accounts.
+    pub fn total_unread(&self) -> u16 {
+        self.signed_in_accounts().iter().fold(0u16, |sum, (_, unread)| sum.saturating_add(*unread))
+    }
+
+    /// Take the state of the active account out of the session signals.
+    fn take_active(&self) -> Option<AccountSession> {
+        let token = self.token.get_untracked()?;
+        Some(AccountSession {
+            user: self.user.get_untracked(),
+            token,
+            accounts: self.accounts.get_untracked(),
+            rooms: self.rooms.get_untracked(),
+            rooms_tabs: self.rooms_tabs.get_untracked(),
+            active_room: self.active_room.get_untracked(),
+            backend: self.backend()
+        })
+    }
+
+    /// Put the account state into the session signals.
+    fn restore(&self, session: AccountSession) {
+        *self.backend.borrow_mut() = session.backend;
+        batch(|| {
+            self.active_room.set(None);
+            self.accounts.set(session.accounts);
+            self.rooms_tabs.set(session.rooms_tabs);
+            self.rooms.set(session.rooms);
+            self.user.set(session.user);
+            self.token.set((!session.token.is_empty()).then_some(session.token));
+            self.active_room.set(session.active_room);
+        });
+    }
+
+    /// Insert room into `rooms` and `rooms_tabs`.
+    pub fn insert_room(&self, room_view: RoomViewData) {
+        batch(|| {
+            self.rooms.update(|rooms| {
+                debug!("room: app.rooms.update");
+                if let Some(ret) = rooms.insert(room_view.idx(), room_view.clone()) {
+                    error!("value returned when attempted to insert {}", ret.room_id)
+                }
+            });
+            self.rooms_tabs.update(|tabs| {
+                tabs.insert(
+                    room_view.room_id.id,
+                    (room_view.idx(), room_view.view_id, room_view.get_update)
+                );
+            });
+        });
+    }
+
+    /// Apply room state confirmed by the backend onto the session,
+    /// so both sidebar and open room tab reflect it.
+    pub fn apply_room(&self, room: Room) {
+        let Some(idx) = self.rooms_tabs.with_untracked(|tabs| tabs.get(&room.room_id.id).map(|t| t.0)) else {
+            warn!("fn: apply_room: room {} not found", room.room_id);
+            return
+        };
+        let (owner, members) = self.resolve_members(&room);
+        let mut signals = None;
+        self.rooms.update(|rooms| {
+            if let Some(r) = rooms.get_mut(&idx) {
+                r.owner = owner;
+                r.members = members;
+                signals = Some((r.name, r.description, r.avatar));
+            }
+        });
+        if let Some((name, description, avatar)) = signals {
+            batch(|| {
+                name.set(room.name);
+                description.set(room.description);
+                avatar.set(room.avatar.map(Rc::new));
+            });
+        }
+    }
+
+    /// Append msgs received for the room to its data of every signed in account.
+    /// They're counted as unread, except in the room open by the active account.
+    /// Returns `false` when no account has the room.
+    pub fn receive_msgs(&self, room: &Ulid, msgs: Vec<MsgViewData>) -> bool {
+        let Some(config) = use_context::<RwSignal<ChatConfig>>() else {
+            error!("fn: receive_msgs: config context is missing");
+            return false
+        };
+        let receive = |room_view: &RoomViewData, user: &Ulid, open: bool| {
+            room_view.msgs.update(|chunks| for msg in msgs.iter().cloned() {
+                chunks.append_new_msg(msg);
+            });
+            room_view.update_msg_count();
+            if !open {
+                config.with_untracked(|cf| room_view.count_unread(&msgs, user, cf));
+            }
+        };
+        let mut received = false;
+        if let Some((idx, _, _)) = self.rooms_tabs.with_untracked(|tabs| tabs.get(room).cloned()) {
+            let user = self.user.with_untracked(|u| u.acc_id.id);
+            let open = self.active_room.with_untracked(|ar| ar.as_ref().is_some_and(|ar| ar.id == *room));
+            self.rooms.with_untracked(|rooms| if let Some(room_view) = rooms.get(&idx) {
+                receive(room_view, &user, open);
+                received = true;
+            });
+        }
+        // -- Parked accounts aren't shown, so even their last open room gets unread msgs
+        self.parked.with_untracked(|parked| for session in parked {
+            let Some((idx, _, _)) = session.rooms_tabs.get(room) else { continue };
+            if let Some(room_view) = session.rooms.get(idx) {
+                receive(room_view, &session.user.acc_id.id, false);
+                received = true;
+            }
+        });
+        received
+    }
+
+    /// Remove room from `rooms` and `rooms_tabs` (deselecting it if active) with its draft.
+    pub fn remove_room(&self, room: &Ulid) {
+        let Some((idx, _, _)) = self.rooms_tabs.with_untracked(|tabs| tabs.get(room).cloned()) else { return };
+        let mut removed = None;
+        batch(|| {
+            if self.active_room.with_untracked(|ar| ar.as_ref().is_some_and(|ar| ar.id == *room)) {
+                self.active_room.set(None);
+            }
+            self.rooms_tabs.update(|tabs| { tabs.remove(room); });
+            self.rooms.update(|rooms| removed = rooms.remove(&idx));
+        });
+        // -- Its signals go once the views reading them are rebuilt
+        if let Some(removed) = removed {
+            exec_after(Duration::ZERO, move |_| removed.cx.dispose());
+        }
+        if self.drafts.with_untracked(|d| d.get(room).is_some()) {
+            self.drafts.update(|d| { d.remove(room); });
+            save_debounced(self.drafts);
+        }
+    }
+
+    /// Resolve room owner and other members into [Account]s.
+    pub fn resolve_members(&self, room: &Room) -> (Account, HashMap<Ulid, Account>) {
+        let owner = self.get_account(&room.owner.id).unwrap_or_else(|| self.user.with_untracked(|u| u.as_ref().clone()));
+        let members = room.members
+            .iter()
+            .filter(|m| m.member_id.id != owner.acc_id.id)
+            .filter_map(|m| self.get_account(&m.member_id.id))
+            .map(|acc| (acc.acc_id.id, acc))
+            .collect();
+        (owner, members)
+    }
+
+    /// Find account by its id (including session user).
+    pub fn get_account(&self, id: &Ulid) -> Option<Account> {
+        if let Some(user) = self.user.with_untracked(|u| (u.acc_id.id == *id).then(|| u.as_ref().clone())) {
+            return Some(user)
+        }
+        self.accounts.with_untracked(|accs| accs.get(id).cloned())
+    }
+}
+
+
+/// State of the signed in account, kept aside while another account is active.
+pub struct AccountSession {
+    pub user: Rc<Account>,
+    pub token: String,
+    pub accounts: HashMap<Ulid, Account>,
+    pub rooms: BTreeMap<usize, RoomViewData>,
+    pub rooms_tabs: HashMap<Ulid, (usize, ViewId, RwSignal<RoomMsgUpt>)>,
+    pub active_room: Option<RoomTabIdx>,
+    pub backend: Rc<dyn Backend>
+}
+
+
+fn count_unread(rooms: &BTreeMap<usize, RoomViewData>) -> u16 {
+    rooms.values().fold(0u16, |sum, room| sum.saturating_add(room.num_unread.get()))
+}
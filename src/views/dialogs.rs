@@ -0,0 +1,300 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use chrono_lite::Datetime;
+use floem::action::open_file;
+use floem::file::{FileDialogOptions, FileSpec};
+use floem::prelude::*;
+use floem::reactive::use_context;
+use floem::taffy::AlignItems;
+use tracing_lite::{error, info, trace};
+use ulid::Ulid;
+
+use crate::avatar::avatar_of;
+use crate::config::ChatConfig;
+use crate::cont::acc::{Account, Member};
+use crate::links::{domain_of, open_in_browser};
+use crate::cont::room::Room;
+use crate::i18n::{t, t_label, t_with};
+use crate::util::{Id, Tb};
+use crate::view_data::room::RoomViewData;
+use crate::view_data::session::APP;
+use crate::theme::colors;
+use super::invites::invitations_dialog;
+use super::presence::with_presence;
+use super::login::add_account_dialog;
+use super::palette::palette_dialog;
+use super::privacy::privacy_dialog;
+use super::profile::profile_dialog;
+use super::room_settings::{delete_room_dialog, room_settings_dialog};
+use super::settings::settings_dialog;
+
+
+
+/// Currently opened dialog (shown over the main view).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dialog {
+    None,
+    /// New room, with the given account preselected as a member.
+    NewRoom(Option<Ulid>),
+    /// Settings of the room with given id.
+    RoomSettings(Ulid),
+    /// Confirm deleting the room with given id.
+    DeleteRoom(Ulid),
+    /// Invites addressed to the session user.
+    Invitations,
+    /// Sign in another account.
+    AddAccount,
+    /// Profile of the session user.
+    Profile,
+    /// Block and mute lists.
+    Privacy,
+    /// Confirm opening link to the not trusted domain.
+    OpenLink(String),
+    /// Search of commands, rooms and people.
+    Palette,
+    /// Settings of the app.
+    Settings,
+    /// Message for the user (eg. why the stored config was not used).
+    Notice(String)
+}
+
+
+/// Overlay presenting the active [Dialog] (hidden when there is none).
+pub fn dialog_view() -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    dyn_container(
+        move || dialog.get(),
+        move |d| match d {
+            Dialog::None => empty().into_any(),
+            Dialog::NewRoom(member) => new_room_dialog(member).into_any(),
+            Dialog::RoomSettings(room) => room_settings_dialog(room).into_any(),
+            Dialog::DeleteRoom(room) => delete_room_dialog(room).into_any(),
+            Dialog::Invitations => invitations_dialog().into_any(),
+            Dialog::AddAccount => add_account_dialog().into_any(),
+            Dialog::Profile => profile_dialog().into_any(),
+            Dialog::Privacy => privacy_dialog().into_any(),
+            Dialog::OpenLink(url) => open_link_dialog(url).into_any(),
+            Dialog::Palette => palette_dialog().into_any(),
+            Dialog::Settings => settings_dialog().into_any(),
+            Dialog::Notice(text) => notice_dialog(text).into_any()
+        }
+    )
+        .debug_name("dialog")
+        .style(|s| s
+            .padding(15.)
+            .min_width(320.)
+            .background(colors().surface)
+            .border(1.)
+            .border_color(colors().border)
+            .border_radius(5.)
+        )
+        .container()
+        .debug_name("dialog overlay")
+        .style(move |s| s
+            .absolute()
+            .inset(0.)
+            .z_index(10)
+            .items_center()
+            .justify_center()
+            .background(colors().overlay)
+            .apply_if(dialog.with(|d| *d == Dialog::None), |s| s.hide())
+        )
+}
+
+
+/// Row with the "Cancel" and confirmation buttons (title of the `confirm_key`),
+/// closing the dialog on cancel.
+pub fn dialog_buttons(confirm_key: &'static str, on_confirm: impl Fn() + 'static) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    (
+        button(t_label("common.cancel")).action(move || dialog.set(Dialog::None)),
+        button(t_label(confirm_key)).action(on_confirm)
+    )
+        .h_stack()
+        .style(|s| s.gap(5.).justify_end().width_full())
+}
+
+
+/// Selectable list of accounts (without the session user) passing the `filter`.
+pub fn accounts_picker(
+    selected: RwSignal<HashSet<Ulid>>,
+    filter: impl Fn(&Account) -> bool + 'static
+) -> impl IntoView {
+    let accounts = APP.with(|app| app.accounts);
+    dyn_stack(
+        move || {
+            let mut accs = accounts.with(|accs| accs
+                .values()
+                .filter(|acc| filter(acc))
+                .cloned()
+                .collect::<Vec<_>>()
+            );
+            accs.sort_by(|a, b| a.username.cmp(&b.username));
+            accs
+        },
+        |acc| acc.acc_id.id,
+        move |acc| {
+            let id = acc.acc_id.id;
+            let av = avatar_of(&acc);
+            (
+                label(move || if selected.with(|s| s.contains(&id)) { "☑" } else { "☐" }),
+                with_presence(img(move || av.to_vec()).style(|s| s.size(20., 20.).border_radius(10.)), id),
+                label(move || acc.username.clone())
+            )
+                .h_stack()
+                .style(|s| s.gap(5.).padding(2.).hover(|s| s.background(colors().hover)))
+                .on_click_stop(move |_| {
+                    selected.update(|s| if !s.remove(&id) { s.insert(id); });
+                })
+        }
+    )
+        .style(|s| s.flex_col())
+        .scroll()
+        .style(|s| s.max_height(150.).width_full())
+}
+
+
+/// Open file dialog for an image and pass its content (or read error) to `on_read`.
+pub fn pick_image(title: &str, on_read: impl Fn(Result<Vec<u8>, String>) + 'static) {
+    open_file(
+        FileDialogOptions::new()
+            .title(title)
+            .allowed_types(vec![FileSpec { name: "Image", extensions: &["png", "jpg", "jpeg"] }]),
+        move |file| {
+            let Some(path) = file.and_then(|f| f.path.first().cloned()) else { return };
+            on_read(
+                std::fs::read(&path).map_err(|e| t_with("common.read_failed", &[("path", &path.display()), ("error", &e)]))
+            )
+        }
+    )
+}
+
+
+/// Message with the "Close" button.
+fn notice_dialog(text: String) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    (
+        label(move || text.clone()).style(|s| s.max_width(400.)),
+        button(t_label("common.close")).action(move || dialog.set(Dialog::None)).style(|s| s.align_self(AlignItems::End))
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
+
+
+/// Confirmation of opening the link, with an option to trust its domain.
+fn open_link_dialog(url: String) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let domain = domain_of(&url).unwrap_or_default();
+    let trust = {
+        let (url, domain) = (url.clone(), domain.clone());
+        move || {
+            config.update(|cf| cf.trusted_domains.push(domain.clone()));
+            open_in_browser(&url);
+            dialog.set(Dialog::None);
+        }
+    };
+    let open = {
+        let url = url.clone();
+        move || {
+            open_in_browser(&url);
+            dialog.set(Dialog::None);
+        }
+    };
+    (
+        t_label("link.title").style(|s| s.font_bold().font_size(18.)),
+        label(move || url.clone()).style(|s| s.color(colors().accent).max_width(400.)),
+        (
+            button(t_label("common.cancel")).action(move || dialog.set(Dialog::None)),
+            button(label(move || t_with("link.trust", &[("domain", &domain)]))).action(trust),
+            button(t_label("link.open")).action(open)
+        )
+            .h_stack()
+            .style(|s| s.gap(5.).justify_end().width_full())
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
+
+
+// MARK: new room
+
+/// Dialog for creating a new room.
+/// Room is inserted into the session only after the backend confirms it.
+fn new_room_dialog(member: Option<Ulid>) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let new_room_editor_doc = use_context::<RwSignal<Option<Ulid>>>().unwrap();
+    let name = RwSignal::new(String::new());
+    let description = RwSignal::new(String::new());
+    let avatar = RwSignal::new(None::<Rc<Vec<u8>>>);
+    let selected = RwSignal::new(HashSet::<Ulid>::from_iter(member));
+    let error_msg = RwSignal::new(None::<String>);
+
+    let pick_avatar = move || pick_image(&t("room.select_avatar"), move |res| match res {
+        Ok(data) => avatar.set(Some(Rc::new(data))),
+        Err(e) => error_msg.set(Some(e))
+    });
+
+    let create_room = move || {
+        let user = APP.with(|app| app.user.get_untracked());
+        let now = Datetime::default();
+        // -- Owner is always a member
+        let mut members = vec![Member { member_id: user.acc_id.clone(), since: now.clone() }];
+        members.extend(selected.get_untracked().into_iter().map(|id| Member {
+            member_id: Id { tb: Tb::Acc, id },
+            since: now.clone()
+        }));
+        let description = description.get_untracked();
+        let room = Room {
+            room_id: Id::new(Tb::Room),
+            name: name.get_untracked().trim().to_string(),
+            members,
+            active_members: Vec::new(),
+            active_invites: None,
+            owner: user.acc_id.clone(),
+            created: now,
+            description: (!description.trim().is_empty()).then_some(description),
+            avatar: avatar.get_untracked().map(|av| av.to_vec())
+        };
+        // -- Send it to the backend and insert only confirmed room
+        match APP.with(|app| app.backend().create_room(room)) {
+            Ok(room) => {
+                let room_view = RoomViewData::from_room(room);
+                let room_id = room_view.room_id.id;
+                trace!("Confirmed new room - idx: {} | room: {}", room_view.idx(), room_id);
+                APP.with(|app| app.insert_room(room_view));
+                new_room_editor_doc.set(Some(room_id));
+                dialog.set(Dialog::None);
+            },
+            Err(e) => {
+                error!("Failed to create room: {e}");
+                error_msg.set(Some(e.to_string()));
+            }
+        }
+    };
+
+    (
+        t_label("room.new_title").style(|s| s.font_bold().font_size(18.)),
+        text_input(name).placeholder(t("room.name")).style(|s| s.width_full()),
+        text_input(description).placeholder(t("room.description")).style(|s| s.width_full()),
+        (
+            img(move || avatar.get().map(|av| av.to_vec()).unwrap_or_default())
+                .style(|s| s.size(50., 50.).border(1.).border_color(colors().accent).border_radius(5.)),
+            button(t_label("common.choose_avatar")).action(pick_avatar)
+        )
+            .h_stack()
+            .style(|s| s.gap(10.).items_center()),
+        t_label("room.members"),
+        accounts_picker(selected, |_| true),
+        label(move || error_msg.get().unwrap_or_default())
+            .style(move |s| s
+                .color(colors().danger)
+                .apply_if(error_msg.with(|e| e.is_none()), |s| s.hide())
+            ),
+        dialog_buttons("room.create", create_room)
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use chrono_lite::Datetime;
+use floem::prelude::*;
+use floem::reactive::use_context;
+use floem::taffy::AlignItems;
+use tracing_lite::{error, info, trace};
+use ulid::Ulid;
+
+use crate::backend::InviteRef;
+use crate::cont::room::{InviteTarget, RoomInvite};
+use crate::i18n::{t, t_label, t_plural, t_with};
+use crate::util::{now_ms, Id, Tb};
+use crate::view_data::room::RoomViewData;
+use crate::view_data::session::APP;
+use crate::theme::colors;
+use super::dialogs::{accounts_picker, Dialog};
+
+
+
+/// How long newly created invite stays valid.
+pub const INVITE_VALID_FOR: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+
+/// Human readable time left until the invite expires.
+fn expires_label(expires: u64) -> String {
+    let left = expires.saturating_sub(now_ms()) / 1000;
+    match left {
+        0 => t("invites.expired"),
+        1..3600 => t_with("invites.expires_min", &[("n", &(left / 60))]),
+        3600..86_400 => t_with("invites.expires_h", &[("n", &(left / 3600))]),
+        _ => t_plural("invites.expires_days", left / 86_400, &[])
+    }
+}
+
+fn target_label(target: &InviteTarget) -> String {
+    match target {
+        InviteTarget::Account(acc) => {
+            let name = APP.with(|app| app.get_account(&acc.id)).map(|a| a.username);
+            format!("→ {}", name.unwrap_or_else(|| acc.to_string()))
+        },
+        InviteTarget::Link(token) => format!("🔗 {token}")
+    }
+}
+
+
+// MARK: owner
+
+/// Room settings section, where the owner can create and revoke invites.
+pub fn room_invites_view(room_id: Ulid, room_idx: usize, error_msg: RwSignal<Option<String>>) -> impl IntoView {
+    let rooms = APP.with(|app| app.rooms);
+    let room = Id { tb: Tb::Room, id: room_id };
+    let invites = RwSignal::new(Vec::<RoomInvite>::new());
+    let to_invite = RwSignal::new(HashSet::<Ulid>::new());
+
+    let refresh = {
+        let room = room.clone();
+        move || match APP.with(|app| app.backend().room_invites(&room)) {
+            Ok(list) => invites.set(list),
+            Err(e) => error_msg.set(Some(e.to_string()))
+        }
+    };
+    refresh();
+
+    let create = {
+        let room = room.clone();
+        let refresh = refresh.clone();
+        move |target: InviteTarget| {
+            let (inviter, room_name) = APP.with(|app| (
+                app.user.with_untracked(|u| u.acc_id.clone()),
+                rooms.with_untracked(|r| r.get(&room_idx).map(|r| r.name.get_untracked()).unwrap_or_default())
+            ));
+            let invite = RoomInvite {
+                invite_id: Id::new(Tb::Inv),
+                room_id: room.clone(),
+                room_name,
+                inviter,
+                target,
+                created: Datetime::default(),
+                expires: now_ms() + INVITE_VALID_FOR.as_millis() as u64
+            };
+            if let Err(e) = APP.with(|app| app.backend().create_invite(invite)) {
+                error!("Failed to create invite: {e}");
+                error_msg.set(Some(e.to_string()));
+            }
+            refresh();
+        }
+    };
+
+    let invite_selected = {
+        let create = create.clone();
+        move || {
+            for id in to_invite.get_untracked() {
+                create(InviteTarget::Account(Id { tb: Tb::Acc, id }));
+            }
+            to_invite.set(HashSet::new());
+        }
+    };
+
+    let list = dyn_stack(
+        move || invites.get(),
+        |inv| inv.invite_id.id,
+        move |inv| {
+            let refresh = refresh.clone();
+            let invite_id = inv.invite_id.clone();
+            (
+                target_label(&inv.target).style(|s| s.flex_grow(1.).text_ellipsis().min_width(0.)),
+                expires_label(inv.expires).style(|s| s.color(colors().text_muted)),
+                button(t_label("invites.revoke")).action(move || {
+                    if let Err(e) = APP.with(|app| app.backend().revoke_invite(&invite_id)) {
+                        error_msg.set(Some(e.to_string()));
+                    }
+                    refresh();
+                })
+            )
+                .h_stack()
+                .style(|s| s.gap(5.).items_center().width_full())
+        }
+    ).style(|s| s.flex_col().gap(3.).width_full());
+
+    (
+        t_label("invites.list"),
+        list,
+        accounts_picker(to_invite, move |acc| rooms.with(|r| r.get(&room_idx).is_some_and(|r| {
+            !r.is_owner(&acc.acc_id.id) && !r.members.contains_key(&acc.acc_id.id)
+        }))),
+        (
+            button(t_label("invites.invite_selected")).action(invite_selected),
+            button(t_label("invites.create_link")).action(move || create(InviteTarget::Link(Ulid::new().to_string())))
+        )
+            .h_stack()
+            .style(|s| s.gap(5.))
+    )
+        .v_stack()
+        .style(|s| s.gap(5.).width_full())
+}
+
+
+// MARK: recipient
+
+/// Dialog with invites addressed to the session user.
+pub fn invitations_dialog() -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let new_room_editor_doc = use_context::<RwSignal<Option<Ulid>>>().unwrap();
+    let invites = RwSignal::new(Vec::<RoomInvite>::new());
+    let token = RwSignal::new(String::new());
+    let error_msg = RwSignal::new(None::<String>);
+
+    let refresh = move || {
+        let user = APP.with(|app| app.user.with_untracked(|u| u.acc_id.clone()));
+        match APP.with(|app| app.backend().pending_invites(&user)) {
+            Ok(list) => invites.set(list),
+            Err(e) => error_msg.set(Some(e.to_string()))
+        }
+    };
+    refresh();
+
+    // -- Accept and insert (or update) the joined room
+    let accept = move |invite: InviteRef| {
+        let user = APP.with(|app| app.user.with_untracked(|u| u.acc_id.clone()));
+        match APP.with(|app| app.backend().accept_invite(invite, &user)) {
+            Ok(room) => {
+                info!("Joined room {}", room.room_id);
+                let known = APP.with(|app| app.rooms_tabs.with_untracked(|t| t.contains_key(&room.room_id.id)));
+                if known {
+                    APP.with(|app| app.apply_room(room));
+                } else {
+                    let room_view = RoomViewData::from_room(room);
+                    let room_id = room_view.room_id.id;
+                    APP.with(|app| app.insert_room(room_view));
+                    new_room_editor_doc.set(Some(room_id));
+                }
+                error_msg.set(None);
+            },
+            Err(e) => {
+                error!("Failed to accept invite: {e}");
+                error_msg.set(Some(e.to_string()));
+            }
+        }
+        refresh();
+    };
+
+    let list = dyn_stack(
+        move || invites.get(),
+        |inv| inv.invite_id.id,
+        move |inv| {
+            let (accept_id, decline_id) = (inv.invite_id.clone(), inv.invite_id.clone());
+            let inviter = APP.with(|app| app.get_account(&inv.inviter.id))
+                .map(|a| a.username)
+                .unwrap_or_else(|| inv.inviter.to_string());
+            (
+                (
+                    inv.room_name.clone().style(|s| s.font_bold()),
+                    t_with("invites.from", &[("name", &inviter), ("expires", &expires_label(inv.expires))]).style(|s| s.color(colors().text_muted))
+                )
+                    .v_stack()
+                    .style(|s| s.flex_grow(1.)),
+                button(t_label("invites.accept")).action(move || accept(InviteRef::Id(accept_id.clone()))),
+                button(t_label("invites.decline")).action(move || {
+                    let user = APP.with(|app| app.user.with_untracked(|u| u.acc_id.clone()));
+                    if let Err(e) = APP.with(|app| app.backend().decline_invite(&decline_id, &user)) {
+                        error_msg.set(Some(e.to_string()));
+                    }
+                    refresh();
+                })
+            )
+                .h_stack()
+                .style(|s| s.gap(5.).items_center().width_full())
+        }
+    ).style(|s| s.flex_col().gap(5.).width_full());
+
+    (
+        t_label("invites.title").style(|s| s.font_bold().font_size(18.)),
+        t_label("invites.none").style(move |s| s
+            .color(colors().text_muted)
+            .apply_if(invites.with(|i| !i.is_empty()), |s| s.hide())
+        ),
+        list,
+        (
+            text_input(token).placeholder(t("invites.token")).style(|s| s.flex_grow(1.)),
+            button(t_label("invites.join")).action(move || {
+                let t = token.get_untracked().trim().to_string();
+                if t.is_empty() { return }
+                trace!("Joining with token");
+                accept(InviteRef::Token(t));
+                token.set(String::new());
+            })
+        )
+            .h_stack()
+            .style(|s| s.gap(5.).width_full()),
+        label(move || error_msg.get().unwrap_or_default())
+            .style(move |s| s
+                .color(colors().danger)
+                .apply_if(error_msg.with(|e| e.is_none()), |s| s.hide())
+            ),
+        button(t_label("common.close")).action(move || dialog.set(Dialog::None)).style(|s| s.align_self(AlignItems::End))
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
@@ -0,0 +1,185 @@
+use std::rc::Rc;
+
+use floem::event::{Event, EventListener, EventPropagation};
+use floem::keyboard::{Key, NamedKey};
+use floem::prelude::*;
+use floem::reactive::use_context;
+use floem::style::CursorStyle;
+use tracing_lite::{info, warn};
+
+use crate::auth::{AuthSession, Credentials, StoredSession};
+use crate::backend::Backend;
+use crate::i18n::{t, t_label};
+use crate::view_data::session::APP;
+use crate::theme::colors;
+use super::dialogs::Dialog;
+
+
+
+/// Try to sign in all remembered sessions.
+/// First resumed account becomes active, others get their own backend connection.
+pub fn resume_stored_session() -> bool {
+    let mut resumed = false;
+    for stored in StoredSession::load_all() {
+        let backend = match resumed {
+            false => APP.with(|app| app.backend()),
+            true => APP.with(|app| app.backend().new_connection())
+        };
+        match backend.resume_session(&stored.token) {
+            Ok(auth) => {
+                info!("Resumed session of {}", stored.username);
+                match resumed {
+                    false => APP.with(|app| app.sign_in(auth, true)),
+                    true => APP.with(|app| app.add_account(auth, backend, true))
+                }
+                resumed = true;
+            },
+            Err(e) => {
+                warn!("Unable to resume session of {}: {e}", stored.username);
+                stored.discard();
+            }
+        }
+    }
+    // -- Start with the account signed in first
+    if let Some(first) = APP.with(|app| app.parked.with_untracked(|p| p.first().map(|s| s.user.acc_id.id))) {
+        APP.with(|app| app.switch_account(&first));
+    }
+    resumed
+}
+
+
+/// Sign in / sign up screen, shown until there is an authenticated session.
+pub fn login_view() -> impl IntoView {
+    let backend = APP.with(|app| app.backend());
+    login_form(backend, |auth, remember| APP.with(|app| app.sign_in(auth, remember)))
+        .style(|s| s
+            .width(300.)
+            .padding(15.)
+            .border(1.)
+            .border_color(colors().border)
+            .border_radius(5.)
+        )
+        .container()
+        .style(|s| s
+            .size_full()
+            .items_center()
+            .justify_center()
+        )
+}
+
+
+/// Dialog signing in another account, which becomes the active one.
+pub fn add_account_dialog() -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let backend = APP.with(|app| app.backend().new_connection());
+    let conn = backend.clone();
+    (
+        login_form(backend, move |auth, remember| {
+            APP.with(|app| app.add_account(auth, conn.clone(), remember));
+            dialog.set(Dialog::None);
+        }),
+        button(t_label("common.cancel"))
+            .action(move || dialog.set(Dialog::None))
+            .style(|s| s.margin_left_auto())
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
+
+
+/// Credentials form using `backend` to sign in or register.
+fn login_form(
+    backend: Rc<dyn Backend>,
+    on_signed_in: impl Fn(AuthSession, bool) + 'static
+) -> impl IntoView {
+    let username = RwSignal::new(String::new());
+    let password = RwSignal::new(String::new());
+    let remember = RwSignal::new(true);
+    let error_msg = RwSignal::new(None::<String>);
+
+    let on_signed_in = Rc::new(on_signed_in);
+    let submit = move |register: bool| {
+        let cred = Credentials {
+            username: username.get_untracked(),
+            password: password.get_untracked()
+        };
+        let res = match register {
+            true => backend.register(&cred),
+            false => backend.login(&cred)
+        };
+        match res {
+            Ok(auth) => {
+                password.set(String::new());
+                error_msg.set(None);
+                on_signed_in(auth, remember.get_untracked());
+            },
+            Err(e) => error_msg.set(Some(e.to_string()))
+        }
+    };
+    let submit2 = submit.clone();
+
+    (
+        t_label("login.title").style(|s| s.font_bold().font_size(22.)),
+        text_input(username).placeholder(t("login.username")).style(|s| s.width_full()),
+        password_input(password),
+        (
+            label(move || if remember.get() { "☑" } else { "☐" }),
+            t_label("login.remember")
+        )
+            .h_stack()
+            .style(|s| s.gap(5.))
+            .on_click_stop(move |_| remember.update(|r| *r = !*r)),
+        label(move || error_msg.get().unwrap_or_default())
+            .style(move |s| s
+                .color(colors().danger)
+                .apply_if(error_msg.with(|e| e.is_none()), |s| s.hide())
+            ),
+        (
+            button(t_label("login.register")).action(move || submit(true)),
+            button(t_label("login.sign_in")).action(move || submit2(false))
+        )
+            .h_stack()
+            .style(|s| s.gap(5.).justify_end().width_full())
+    )
+        .v_stack()
+        .debug_name("login")
+        .style(|s| s.gap(8.).width_full())
+}
+
+
+/// Input showing the password as dots; typed chars are appended and Backspace removes the last one.
+fn password_input(password: RwSignal<String>) -> impl IntoView {
+    let text = label(move || password.with(|p| match p.is_empty() {
+        true => t("login.password"),
+        false => "•".repeat(p.chars().count())
+    }));
+    let input = text.container();
+    let input_id = input.id();
+    input
+        .keyboard_navigable()
+        .on_click_stop(move |_| input_id.request_focus())
+        .on_event(EventListener::KeyDown, move |ev| {
+            let Event::KeyDown(ke) = ev else { return EventPropagation::Continue };
+            let typing = !ke.modifiers.control() && !ke.modifiers.alt();
+            match &ke.key.logical_key {
+                Key::Named(NamedKey::Backspace) => password.update(|p| { p.pop(); }),
+                Key::Named(NamedKey::Space) if typing => password.update(|p| p.push(' ')),
+                Key::Character(c) if typing => password.update(|p| p.push_str(c)),
+                // -- Tab, Enter and shortcuts are handled by the parents
+                _ => return EventPropagation::Continue
+            }
+            EventPropagation::Stop
+        })
+        .debug_name("password")
+        .style(move |s| s
+            .width_full()
+            .padding_horiz(6.)
+            .padding_vert(4.)
+            .border(1.)
+            .border_radius(3.)
+            .border_color(colors().border)
+            .cursor(CursorStyle::Text)
+            .focus(|s| s.border_color(colors().accent))
+            .apply_if(password.with(|p| p.is_empty()), |s| s.color(colors().text_muted))
+        )
+}
@@ -1,19 +1,31 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
+use std::time::Duration;
 
+use floem::action::exec_after;
 use floem::peniko::Color;
 use floem::prelude::*;
 use floem::reactive::{create_effect, use_context, Trigger};
 use floem::taffy::prelude::TaffyGridLine;
 use floem::taffy::{AlignItems, FlexDirection, GridPlacement, Line};
 use floem::views::{dyn_stack, stack, tab, Decorators, ScrollExt};
+use floem::ViewId;
 use im::Vector;
 use tracing_lite::{debug, info, trace, warn};
 use ulid::Ulid;
 
-use crate::chunks::display::{DisplayChunks, DisplayStatus};
+use crate::chunks::display::{DisplayChunks, DisplayStatus, MsgLayout};
+use crate::config::ChatConfig;
+use crate::cont::acc::Presence;
+use crate::util::day_label;
+use crate::view_data::msg::MsgViewData;
+use crate::view_data::room::RoomViewData;
 use crate::view_data::session::APP;
 use crate::view_data::MsgEvent;
+use crate::theme::colors;
+use crate::i18n::{t_label, t_plural};
+use super::dialogs::Dialog;
+use super::presence::typing_view;
 
 
 
@@ -24,7 +36,9 @@ pub enum RoomMsgUpt {
     NoUpdate,
     Changed(Ulid),
     Deleted(Ulid),
-    LoadMore
+    LoadMore,
+    /// Show the msg (loading older chunks if needed).
+    ScrollTo(Ulid)
 }
 
 
@@ -36,6 +50,7 @@ pub fn msgs_view() -> impl View {
     let rooms = APP.with(|app| app.rooms);
     let active_room = APP.with(|app| app.active_room);
     let rooms_tabs = APP.with(|app| app.rooms_tabs);
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
     // let show_load_more_button = use_context::<RwSignal<bool>>().unwrap();
 
     // -- Effect and derives needed for the view
@@ -103,8 +118,12 @@ pub fn msgs_view() -> impl View {
 // MARK: tab
             move |(idx, room)| {
                 let scroll_to_end = Trigger::new();
+                // -- Views of the displayed msgs, to scroll to the replied ones
+                let msg_views = RwSignal::new(HashMap::<Ulid, ViewId>::new());
+                let scroll_target = RwSignal::new(None::<Ulid>);
                 // -- Tab logic and state
                 let this_room = Rc::new(room);
+                let room_id = this_room.room_id.id;
                 let get_upt = this_room.get_update;
                 // -- Room messages
                 let room_chunks = this_room.msgs;
@@ -172,28 +191,75 @@ pub fn msgs_view() -> impl View {
                         RoomMsgUpt::Deleted(msg_id) => {
                             debug!("RoomMsgUpt::Deleted: tab{idx} with {msg_id}");
                             display_chunks.update(|dc| dc.msg_removed(msg_id));
+                        },
+                        RoomMsgUpt::ScrollTo(msg_id) => {
+                            debug!("RoomMsgUpt::ScrollTo: tab{idx} to {msg_id}");
+                            if room_chunks.with_untracked(|rc| rc.find_msg(msg_id).is_none()) {
+                                warn!("RoomMsgUpt::ScrollTo: msg {msg_id} not found");
+                                return
+                            }
+                            // -- Load older chunks until the msg is displayed
+                            let is_displayed = move || display_chunks.with_untracked(|dc| dc.vec.iter().any(|m| m.ulid() == msg_id));
+                            while !is_displayed() {
+                                let stored = display_chunks.with_untracked(|dc| dc.total_stored);
+                                if room.msgs_count.get_untracked() <= stored { break }
+                                display_chunks.update(|dc| {
+                                    room_chunks.with_untracked(|chunks| dc.append_older_chunk(chunks.load_older_chunk()))
+                                });
+                                if display_chunks.with_untracked(|dc| dc.total_stored) == stored { break }
+                            }
+                            scroll_target.set(Some(msg_id));
                         }
                     }
                 });
+                let header = room_header_view(&this_room);
 // MARK: dyn_stack
-                dyn_stack(
+                let msgs_list = dyn_stack(
                     move || {
                         reload_trigger.track();
-                        let chunks = display_chunks.get(); // FIXME: called twice during new msg
-                        let vis_idx = chunks.get_visible_indicies();
-                        info!("->> dyn_stack: msg(each_fn) (with {} msg/s) and {}", chunks.total_stored, vis_idx.len());
-                        vis_idx.into_iter().zip(chunks.into_iter())
+                        let user = APP.with(|app| app.user.with(|u| u.acc_id.id));
+                        let privacy = config.with(|cf| cf.privacy_of(&user));
+                        display_chunks.with(|dc| {
+                            let msgs = dc.visible_with_layout(&privacy); // FIXME: called twice during new msg
+                            info!("->> dyn_stack: msg(each_fn) (with {} msg/s) and {}", dc.total_stored, msgs.len());
+                            msgs
+                        })
                     },
-                    move |(idx, msg)| {
+                    move |(idx, layout, msg)| {
                         info!("dyn_stack: msg(key_fn): {idx} - {}", msg.id.id);
-                        *idx
+                        (*idx, *layout)
                     },
-                    |(i, msg)| {
+                    move |(i, layout, msg)| {
                         trace!("dyn_stack: msg(view_fn): {}", msg.id);
+                        let msg_id = msg.ulid();
                         let is_owner = msg.room_owner;
-                        msg.style(move |s| s.apply_if(is_owner,
-                            |s| s.align_self(AlignItems::End)
-                        ))
+                        let separator = match layout.day_separator {
+                            Some(day) => day_separator_view(day).into_any(),
+                            None => empty().into_any()
+                        };
+                        let msg_view = match layout.collapsed {
+                            true => hidden_msg_view(msg, layout).into_any(),
+                            false => msg.view_with_layout(layout)
+                        };
+                        let view_id = msg_view.id();
+                        msg_views.update(|views| { views.insert(msg_id, view_id); });
+                        (
+                            separator,
+                            msg_view.style(move |s| s.apply_if(is_owner,
+                                |s| s.align_self(AlignItems::End)
+                            ))
+                        )
+                            .v_stack()
+                            // -- Msg can be already shown by a newer view (eg. with other layout)
+                            .on_cleanup(move || msg_views.update(|views| {
+                                if views.get(&msg_id) == Some(&view_id) {
+                                    views.remove(&msg_id);
+                                }
+                            }))
+                            .style(|s| s
+                                .width_full()
+                                .align_items(AlignItems::Start)
+                            )
                     }
                 ).debug_name("msgs list")
                 .style(|s| s
@@ -229,6 +295,15 @@ pub fn msgs_view() -> impl View {
                     trace!("scroll_to_end notified for {}", room_idx);
                     100.0
                 })
+                .scroll_to_view(move || {
+                    let target = scroll_target.get()?;
+                    // -- Scroll once, later list updates shouldn't bring the msg back
+                    exec_after(Duration::ZERO, move |_| scroll_target.set(None));
+                    msg_views.with_untracked(|views| views.get(&target).copied())
+                });
+                (header, msgs_list, typing_view(room_id))
+                    .v_stack()
+                    .style(|s| s.size_full())
         }).debug_name("msgs tabs")
         .style(|s| s.size_full())
         // .on_resize(move |_rect| {
@@ -238,8 +313,8 @@ pub fn msgs_view() -> impl View {
     )).debug_name("msgs stack")
     .style(|s| s
         .padding(5.)
-        .background(Color::LIGHT_GREEN)
-        .border_color(Color::BLACK)
+        .background(colors().msgs_bg)
+        .border_color(colors().border)
         .border(1.)
         .grid_column(Line {
             start: GridPlacement::from_line_index(2),
@@ -250,4 +325,87 @@ pub fn msgs_view() -> impl View {
             end: GridPlacement::Span(2)
         })
     )
-}
\ No newline at end of file
+}
+
+
+/// Top bar of the room tab with name, description, members count and settings button.
+fn room_header_view(room: &RoomViewData) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let rooms = APP.with(|app| app.rooms);
+    let (name, description) = (room.name, room.description);
+    let (room_id, room_idx) = (room.room_id.id, room.idx());
+    (
+        label(move || name.get()).style(|s| s.font_bold().font_size(16.)),
+        label(move || description.get().unwrap_or_default())
+            .style(|s| s.color(colors().text_muted).text_ellipsis().flex_grow(1.).min_width(0.)),
+        label(move || {
+            let ids = rooms.with(|r| r.get(&room_idx).map(|r| {
+                std::iter::once(r.owner.acc_id.id).chain(r.members.keys().copied()).collect::<Vec<_>>()
+            }).unwrap_or_default());
+            let online = APP.with(|app| app.presence.with(|p| ids
+                .iter()
+                .filter(|id| p.get(id).is_some_and(|p| p.presence != Presence::Offline))
+                .count()
+            ));
+            t_plural("msgs.members", ids.len() as u64, &[("online", &online)])
+        }).style(|s| s.color(colors().text_muted)),
+        button("⚙").action(move || dialog.set(Dialog::RoomSettings(room_id)))
+    )
+        .h_stack()
+        .debug_name("room header")
+        .style(|s| s
+            .width_full()
+            .gap(10.)
+            .items_center()
+            .padding_bottom(5.)
+            .border_bottom(1.)
+            .border_color(colors().border)
+        )
+}
+
+/// Row standing for the msg of muted author, expanded to the msg on click.
+fn hidden_msg_view(msg: MsgViewData, layout: MsgLayout) -> impl IntoView {
+    let expanded = RwSignal::new(false);
+    dyn_container(
+        move || expanded.get(),
+        move |expanded_now| match expanded_now {
+            true => msg.clone().view_with_layout(MsgLayout { collapsed: false, ..layout }),
+            false => t_label("msgs.hidden")
+                .style(|s| s
+                    .color(colors().text_muted)
+                    .font_size(12.)
+                    .padding(3.)
+                    .border(1.)
+                    .border_color(colors().hover)
+                    .border_radius(5.)
+                )
+                .on_click_stop(move |_| expanded.set(true))
+                .into_any()
+        }
+    )
+}
+
+/// Horizontal line with the day label, drawn above first msg of the day.
+fn day_separator_view(day: u64) -> impl IntoView {
+    let line = || empty().style(|s| s
+        .flex_grow(1.)
+        .height(1.)
+        .background(colors().border)
+    );
+    (
+        line(),
+        label(move || day_label(day)).style(|s| s
+            .padding_horiz(8.)
+            .color(colors().border)
+            .font_size(12.)
+        ),
+        line()
+    )
+        .h_stack()
+        .debug_name("day separator")
+        .style(|s| s
+            .width_full()
+            .items_center()
+            .padding_vert(5.)
+        )
+}
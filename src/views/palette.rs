@@ -0,0 +1,161 @@
+use floem::keyboard::{Key, NamedKey};
+use floem::prelude::*;
+use floem::reactive::{create_effect, create_memo, use_context};
+use tracing_lite::trace;
+use ulid::Ulid;
+
+use crate::commands::{commands, fuzzy_filter, run_command};
+use crate::i18n::{t, t_label};
+use crate::view_data::session::APP;
+use crate::theme::colors;
+use super::dialogs::Dialog;
+
+
+/// Max number of items shown in the palette.
+const MAX_RESULTS: usize = 10;
+
+
+/// What happens when the palette item is picked.
+#[derive(Debug, Clone, PartialEq)]
+enum PaletteTarget {
+    Command(&'static str),
+    Room(Ulid),
+    Person(Ulid)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PaletteItem {
+    /// Key binding or kind of the item.
+    hint: String,
+    target: PaletteTarget
+}
+
+/// Commands, rooms and people (without the session user), K: searched title.
+fn palette_items() -> Vec<(String, PaletteItem)> {
+    let mut items: Vec<_> = commands()
+        .into_iter()
+        .filter(|cmd| cmd.id != "palette.open")
+        .map(|cmd| (cmd.title(), PaletteItem {
+            hint: cmd.key.map(|k| k.to_string()).unwrap_or_default(),
+            target: PaletteTarget::Command(cmd.id)
+        }))
+        .collect();
+    APP.with(|app| {
+        app.rooms.with_untracked(|rooms| {
+            items.extend(rooms.values().map(|room| (room.name.get_untracked(), PaletteItem {
+                hint: t("palette.room"),
+                target: PaletteTarget::Room(room.room_id.id)
+            })));
+        });
+        let user = app.user.with_untracked(|u| u.acc_id.id);
+        let mut people: Vec<_> = app.accounts.with_untracked(|accs| accs
+            .values()
+            .filter(|acc| acc.acc_id.id != user)
+            .map(|acc| (format!("@{}", acc.username), PaletteItem {
+                hint: t("palette.person"),
+                target: PaletteTarget::Person(acc.acc_id.id)
+            }))
+            .collect()
+        );
+        people.sort_by(|a, b| a.0.cmp(&b.0));
+        items.extend(people);
+    });
+    items
+}
+
+/// Run the picked item: command, select the room or the room shared with the person
+/// (new room with the person is started if there is none).
+fn pick(target: &PaletteTarget, dialog: RwSignal<Dialog>) {
+    trace!("fn: pick: {target:?}");
+    match target {
+        PaletteTarget::Command(id) => { run_command(id); },
+        PaletteTarget::Room(id) => APP.with(|app| app.rooms.with_untracked(|rooms| {
+            if let Some(room) = rooms.values().find(|r| r.room_id.id == *id) {
+                room.select();
+            }
+        })),
+        PaletteTarget::Person(acc) => {
+            // -- Smallest shared room is the most direct one
+            let shared = APP.with(|app| app.rooms.with_untracked(|rooms| rooms
+                .values()
+                .filter(|r| r.is_owner(acc) || r.members.contains_key(acc))
+                .min_by_key(|r| r.members.len())
+                .cloned()
+            ));
+            match shared {
+                Some(room) => room.select(),
+                None => dialog.set(Dialog::NewRoom(Some(*acc)))
+            }
+        }
+    }
+}
+
+
+/// Ctrl+K palette: fuzzy search of commands, rooms and people.
+pub fn palette_dialog() -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let query = RwSignal::new(String::new());
+    let selected = RwSignal::new(0_usize);
+    let items = palette_items();
+    let found = create_memo(move |_| {
+        let query = query.get();
+        let mut found = fuzzy_filter(&query, items.clone());
+        found.truncate(MAX_RESULTS);
+        found
+    });
+    // -- New search starts from the best match
+    create_effect(move |_| {
+        query.track();
+        selected.set(0);
+    });
+    // -- Dialog is closed first, so the command can open another one
+    let pick_at = move |idx: usize| {
+        if let Some((_, item)) = found.with_untracked(|f| f.get(idx).cloned()) {
+            dialog.set(Dialog::None);
+            pick(&item.target, dialog);
+        }
+    };
+    let move_selection = move |down: bool| {
+        let len = found.with_untracked(|f| f.len());
+        selected.update(|i| *i = match down {
+            true => (*i + 1).min(len.saturating_sub(1)),
+            false => i.saturating_sub(1)
+        });
+    };
+
+    let input = text_input(query)
+        .placeholder(t("palette.search"))
+        .style(|s| s.width_full());
+    let input_id = input.id();
+    let input = input
+        .on_key_down(Key::Named(NamedKey::ArrowDown), |m| m.is_empty(), move |_| move_selection(true))
+        .on_key_down(Key::Named(NamedKey::ArrowUp), |m| m.is_empty(), move |_| move_selection(false))
+        .on_key_down(Key::Named(NamedKey::Enter), |m| m.is_empty(), move |_| pick_at(selected.get_untracked()))
+        .on_key_down(Key::Named(NamedKey::Escape), |m| m.is_empty(), move |_| dialog.set(Dialog::None));
+    input_id.request_focus();
+
+    let results = dyn_stack(
+        move || found.get().into_iter().enumerate(),
+        |(idx, (title, item))| (*idx, title.clone(), item.target.clone()),
+        move |(idx, (title, item))| (
+            label(move || title.clone()).style(|s| s.flex_grow(1.)),
+            label(move || item.hint.clone()).style(|s| s.color(colors().text_muted))
+        )
+            .h_stack()
+            .style(move |s| s
+                .gap(10.)
+                .padding(3.)
+                .width_full()
+                .hover(|s| s.background(colors().hover))
+                .apply_if(selected.get() == idx, |s| s.background(colors().selected))
+            )
+            .on_click_stop(move |_| pick_at(idx))
+    )
+        .style(|s| s.flex_col().width_full());
+    let nothing = t_label("palette.nothing_found")
+        .style(move |s| s.color(colors().text_muted).apply_if(found.with(|f| !f.is_empty()), |s| s.hide()));
+
+    (input, results, nothing)
+        .v_stack()
+        .style(|s| s.gap(5.).width(400.))
+}
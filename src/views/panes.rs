@@ -0,0 +1,170 @@
+use floem::event::{Event, EventListener};
+use floem::kurbo::{Point, Size};
+use floem::prelude::*;
+use floem::reactive::{create_effect, provide_context, use_context};
+use floem::style::{CursorStyle, Style};
+use floem::taffy::prelude::minmax;
+use floem::taffy::{LengthPercentage, MaxTrackSizingFunction, MinTrackSizingFunction, TrackSizingFunction};
+use tracing_lite::debug;
+
+use crate::config::ChatConfig;
+use crate::view_data::session::APP;
+use crate::theme::colors;
+
+
+pub const TOPBAR_HEIGHT: f64 = 40.;
+/// Column with the editor buttons (hidden in the compact layout).
+const BUTTONS_WIDTH: f64 = 60.;
+/// Main view narrower than that gets the compact layout.
+pub const COMPACT_WIDTH: f64 = 600.;
+/// Padding of the main view and gaps between the panes (splitters fill the gaps).
+pub const GAP: f64 = 5.;
+
+
+/// Main view panes: rooms list, msgs and editor, with the sizes from the
+/// config [PaneLayout](crate::config::PaneLayout) and the splitters between them.
+#[derive(Clone, Copy)]
+pub struct Panes {
+    config: RwSignal<ChatConfig>,
+    /// Size of the main view.
+    pub size: RwSignal<Size>,
+    /// Rooms list opened in the compact layout (closed when a room is picked).
+    pub drawer_open: RwSignal<bool>
+}
+
+impl Panes {
+    /// Panes of the main view, provided as a context for its children.
+    pub fn provide() -> Self {
+        let panes = Self {
+            config: use_context::<RwSignal<ChatConfig>>().unwrap(),
+            size: RwSignal::new(Size::ZERO),
+            drawer_open: RwSignal::new(false)
+        };
+        create_effect(move |_| {
+            APP.with(|app| app.active_room.with(|_| ()));
+            panes.drawer_open.set(false);
+        });
+        provide_context(panes);
+        panes
+    }
+
+    /// Narrow window: rooms list is hidden until opened and editor buttons are not shown.
+    pub fn is_compact(self) -> bool {
+        self.size.with(|s| s.width > 0. && s.width < COMPACT_WIDTH)
+    }
+
+    pub fn sidebar_shown(self) -> bool {
+        match self.is_compact() {
+            true => self.drawer_open.get(),
+            false => !self.config.with(|cf| cf.layout.sidebar_collapsed)
+        }
+    }
+
+    /// Collapse or expand the rooms list (in the compact layout until a room is picked).
+    pub fn toggle_sidebar(self) {
+        debug!("fn: Panes::toggle_sidebar");
+        match self.is_compact() {
+            true => self.drawer_open.update(|open| *open = !*open),
+            false => self.config.update(|cf| cf.layout.sidebar_collapsed = !cf.layout.sidebar_collapsed)
+        }
+    }
+
+    /// Widths of the rooms list and editor buttons columns (msgs take the rest).
+    fn columns(self) -> (f64, f64) {
+        let sidebar = match self.sidebar_shown() {
+            true => self.config.with(|cf| cf.layout.sidebar_width),
+            false => 0.
+        };
+        (sidebar, if self.is_compact() { 0. } else { BUTTONS_WIDTH })
+    }
+
+    /// Grid of the main view: toolbar on top, rooms list on the left,
+    /// msgs in the middle and the editor with its buttons at the bottom.
+    pub fn grid_style(self, s: Style) -> Style {
+        let (sidebar, buttons) = self.columns();
+        let editor = self.config.with(|cf| cf.layout.editor_height);
+        s.grid()
+            .grid_template_columns(vec![fixed_track(sidebar), auto_track(), fixed_track(buttons)])
+            .grid_template_rows(vec![fixed_track(TOPBAR_HEIGHT), auto_track(), auto_track(), fixed_track(editor)])
+            .column_gap(GAP)
+            .row_gap(GAP)
+            .padding(GAP)
+    }
+}
+
+
+fn fixed_track(px: f64) -> TrackSizingFunction {
+    TrackSizingFunction::Single(minmax(
+        MinTrackSizingFunction::Fixed(LengthPercentage::Length(px as f32)),
+        MaxTrackSizingFunction::Fraction(0.)
+    ))
+}
+
+fn auto_track() -> TrackSizingFunction {
+    TrackSizingFunction::Single(minmax(MinTrackSizingFunction::Auto, MaxTrackSizingFunction::Auto))
+}
+
+
+// MARK: Splitters
+
+/// Gap between the rooms list and msgs, dragged to resize the list (double click collapses it).
+pub fn sidebar_splitter() -> impl IntoView {
+    let panes = use_context::<Panes>().unwrap();
+    let config = panes.config;
+    splitter(true, move |by| config.update(|cf| cf.layout.resize_sidebar(by)))
+        .on_double_click_stop(move |_| panes.toggle_sidebar())
+        .debug_name("sidebar splitter")
+        .style(move |s| s
+            .inset_left(GAP + panes.columns().0)
+            .inset_top(GAP + TOPBAR_HEIGHT)
+            .inset_bottom(GAP)
+            .width(GAP)
+            .cursor(CursorStyle::ColResize)
+            .apply_if(!panes.sidebar_shown(), |s| s.hide())
+        )
+}
+
+/// Gap between the msgs and editor, dragged to resize the editor.
+pub fn editor_splitter() -> impl IntoView {
+    let panes = use_context::<Panes>().unwrap();
+    let config = panes.config;
+    // -- Editor grows when dragged up
+    splitter(false, move |by| config.update(|cf| cf.layout.resize_editor(-by)))
+        .debug_name("editor splitter")
+        .style(move |s| {
+            let (sidebar, buttons) = panes.columns();
+            s.inset_left(GAP + sidebar + GAP)
+                .inset_right(GAP + buttons + GAP)
+                .inset_bottom(GAP + config.with(|cf| cf.layout.editor_height))
+                .height(GAP)
+                .cursor(CursorStyle::RowResize)
+        })
+}
+
+/// Absolutely placed handle calling the `resize` with the distance it was dragged by
+/// (`vertical` one is dragged horizontally). It moves with the resized pane,
+/// so the distance is measured from the point it was grabbed at.
+fn splitter(vertical: bool, resize: impl Fn(f64) + 'static) -> impl IntoView {
+    let grabbed_at = RwSignal::new(None::<Point>);
+    let handle = empty();
+    let handle_id = handle.id();
+    handle
+        .on_event_stop(EventListener::PointerDown, move |ev| if let Event::PointerDown(pe) = ev {
+            grabbed_at.set(Some(pe.pos));
+            // -- Keep receiving pointer moves when it leaves the handle
+            handle_id.request_active();
+        })
+        .on_event_stop(EventListener::PointerMove, move |ev| {
+            if let (Event::PointerMove(pe), Some(start)) = (ev, grabbed_at.get_untracked()) {
+                resize(if vertical { pe.pos.x - start.x } else { pe.pos.y - start.y });
+            }
+        })
+        .on_event_stop(EventListener::PointerUp, move |_| grabbed_at.set(None))
+        .style(move |s| s
+            .absolute()
+            .z_index(5)
+            .border_radius(2.)
+            .hover(|s| s.background(colors().accent))
+            .apply_if(grabbed_at.get().is_some(), |s| s.background(colors().accent))
+        )
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use floem::action::exec_after;
+use floem::prelude::*;
+use tracing_lite::{trace, warn};
+use ulid::Ulid;
+
+use crate::cont::acc::{Presence, PresenceInfo};
+use crate::util::{now_ms, Id, Tb, Throttle};
+use crate::view_data::session::APP;
+use crate::theme::colors;
+use crate::i18n::{t, t_with};
+
+
+
+/// How often presence and typing state is fetched from the backend.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Idle time (ms) after which the user is shown as away.
+const AWAY_AFTER: u64 = 5 * 60_000;
+
+
+/// Keep presence of known accounts and typing state of the rooms up to date.
+/// Reschedules itself, so it must be started only once.
+pub fn start_presence_polling() {
+    poll_presence();
+    exec_after(POLL_INTERVAL, move |_| start_presence_polling());
+}
+
+fn poll_presence() {
+    APP.with(|app| {
+        if !app.is_signed_in() { return }
+        let backend = app.backend();
+        let user = app.user.with_untracked(|u| u.acc_id.clone());
+        // -- Own presence (sent only when changed)
+        let own = match app.idle_for() > AWAY_AFTER {
+            true => Presence::Away,
+            false => Presence::Online
+        };
+        let known = app.presence.with_untracked(|p| p.get(&user.id).map(|p| p.presence));
+        if known != Some(own) {
+            if let Err(e) = backend.set_presence(&user, own) {
+                warn!("Failed to set presence: {e}");
+            }
+        }
+        // -- Presence of everyone visible to the user
+        let mut accs = app.accounts.with_untracked(|accs| accs.values().map(|a| a.acc_id.clone()).collect::<Vec<_>>());
+        accs.push(user);
+        match backend.presence(&accs) {
+            Ok(presence) => if app.presence.with_untracked(|p| *p != presence) {
+                app.presence.set(presence);
+            },
+            Err(e) => warn!("Failed to fetch presence: {e}")
+        }
+        match backend.statuses(&accs) {
+            Ok(statuses) => if app.statuses.with_untracked(|s| *s != statuses) {
+                app.statuses.set(statuses);
+            },
+            Err(e) => warn!("Failed to fetch statuses: {e}")
+        }
+        // -- Typing in the rooms of the active account
+        let rooms = app.rooms_tabs.with_untracked(|tabs| tabs.keys().copied().collect::<Vec<_>>());
+        let typing = rooms
+            .into_iter()
+            .filter_map(|room| match backend.typing(&Id { tb: Tb::Room, id: room }) {
+                Ok(accs) => Some((room, accs.into_iter().map(|acc| acc.id).collect::<Vec<_>>())),
+                Err(e) => { warn!("Failed to fetch typing state: {e}"); None }
+            })
+            .filter(|(_, accs)| !accs.is_empty())
+            .collect::<HashMap<_, _>>();
+        if app.typing.with_untracked(|t| *t != typing) {
+            trace!("fn: poll_presence: typing changed");
+            app.typing.set(typing);
+        }
+    });
+}
+
+
+fn presence_color(presence: Presence) -> Color {
+    let palette = colors();
+    match presence {
+        Presence::Online => palette.online,
+        Presence::Away => palette.away,
+        Presence::Offline => palette.text_muted
+    }
+}
+
+fn presence_of(acc: Ulid) -> PresenceInfo {
+    APP.with(|app| app.presence.with(|p| p.get(&acc).copied().unwrap_or_default()))
+}
+
+/// Status line of the account (`None` if it has none).
+pub fn status_of(acc: Ulid) -> Option<String> {
+    APP.with(|app| app.statuses.with(|s| s.get(&acc).cloned()))
+}
+
+/// Avatar with the presence dot in its bottom right corner.
+pub fn with_presence(avatar: impl IntoView + 'static, acc: Ulid) -> impl IntoView {
+    let dot = empty().style(move |s| s
+        .absolute()
+        .inset_right(0.)
+        .inset_bottom(0.)
+        .size(8., 8.)
+        .border_radius(4.)
+        .border(1.)
+        .border_color(colors().surface)
+        .background(presence_color(presence_of(acc).presence))
+    );
+    stack((avatar, dot))
+}
+
+/// Gray label with the account status and presence, e.g. "at lunch · last seen 5 min ago".
+pub fn presence_label(acc: Ulid) -> impl IntoView {
+    label(move || match status_of(acc) {
+        Some(status) => format!("{status} · {}", presence_of(acc).describe(now_ms())),
+        None => presence_of(acc).describe(now_ms())
+    })
+        .style(|s| s.color(colors().text_muted).font_size(11.))
+}
+
+
+/// "Alice is typing…" line for the room (without the session user).
+pub fn typing_view(room: Ulid) -> impl IntoView {
+    let text = move || APP.with(|app| {
+        let user = app.user.with(|u| u.acc_id.id);
+        let names = app.typing.with(|t| t
+            .get(&room)
+            .map(|accs| accs
+                .iter()
+                .filter(|acc| **acc != user)
+                .filter_map(|acc| app.get_account(acc).map(|a| a.username))
+                .collect::<Vec<_>>()
+            )
+            .unwrap_or_default()
+        );
+        typing_label(&names)
+    });
+    label(move || text().unwrap_or_default())
+        .debug_name("typing")
+        .style(move |s| s
+            .padding_horiz(5.)
+            .font_size(12.)
+            .color(colors().text_muted)
+            .apply_if(text().is_none(), |s| s.hide())
+        )
+}
+
+/// Text of the typing indicator (`None` when nobody is typing).
+pub fn typing_label(names: &[String]) -> Option<String> {
+    match names {
+        [] => None,
+        [one] => Some(t_with("typing.single", &[("name", one)])),
+        [one, two] => Some(t_with("typing.pair", &[("name", one), ("other", two)])),
+        _ => Some(t("typing.several"))
+    }
+}
+
+
+#[test]
+fn presence_labels_test() {
+    // -- Typing
+    let names = ["Alice", "Bob", "Carol"].map(String::from);
+    assert_eq!(typing_label(&names[..0]), None);
+    assert_eq!(typing_label(&names[..1]).unwrap(), "Alice is typing…");
+    assert_eq!(typing_label(&names[..2]).unwrap(), "Alice and Bob are typing…");
+    assert_eq!(typing_label(&names).unwrap(), "Several people are typing…");
+    // -- Presence
+    let now = 10 * 86_400_000;
+    let seen = |presence, ago: u64| PresenceInfo { presence, last_seen: now - ago }.describe(now);
+    assert_eq!(seen(Presence::Online, 0), "online");
+    assert_eq!(seen(Presence::Away, 60_000), "away");
+    assert_eq!(seen(Presence::Offline, 30_000), "last seen just now");
+    assert_eq!(seen(Presence::Offline, 5 * 60_000), "last seen 5 min ago");
+    assert_eq!(seen(Presence::Offline, 3 * 3_600_000), "last seen 3 h ago");
+    assert_eq!(seen(Presence::Offline, 2 * 86_400_000), "last seen 2 days ago");
+    assert_eq!(PresenceInfo::default().describe(now), "offline");
+    // -- Typing notifications throttle
+    let mut throttle = Throttle::new(3_000);
+    let room = Ulid::new();
+    assert!(throttle.ready(room, 1_000));
+    assert!(!throttle.ready(room, 3_999));
+    assert!(throttle.ready(Ulid::new(), 3_999));
+    assert!(throttle.ready(room, 4_000));
+    throttle.reset(&room);
+    assert!(throttle.ready(room, 4_001));
+}
@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use floem::prelude::*;
+use floem::reactive::use_context;
+use ulid::Ulid;
+
+use crate::avatar::avatar_of;
+use crate::config::{ChatConfig, Privacy};
+use crate::i18n::t_label;
+use crate::view_data::session::APP;
+use crate::theme::colors;
+use super::dialogs::{accounts_picker, Dialog};
+
+
+
+/// Privacy settings page with block and mute lists of the session user.
+pub fn privacy_dialog() -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let user = APP.with(|app| app.user.with_untracked(|u| u.acc_id.id));
+    let selected = RwSignal::new(HashSet::<Ulid>::new());
+
+    let apply = move |change: fn(&mut Privacy, Ulid)| {
+        let accs = selected.get_untracked();
+        if accs.is_empty() { return }
+        config.update(|cf| {
+            let privacy = cf.privacy.entry(user).or_default();
+            for acc in accs {
+                change(privacy, acc);
+            }
+        });
+        selected.set(HashSet::new());
+    };
+
+    (
+        t_label("privacy.title").style(|s| s.font_bold().font_size(18.)),
+        t_label("privacy.blocked"),
+        privacy_list(user, |p| p.blocked.clone(), "privacy.unblock", |p, acc| p.toggle_block(acc)),
+        t_label("privacy.muted"),
+        privacy_list(user, |p| p.muted.clone(), "privacy.unmute", |p, acc| p.toggle_mute(acc)),
+        t_label("privacy.accounts"),
+        accounts_picker(selected, move |acc| config.with(|cf| {
+            let privacy = cf.privacy_of(&user);
+            !privacy.is_blocked(&acc.acc_id.id) && !privacy.is_muted(&acc.acc_id.id)
+        })),
+        (
+            button(t_label("privacy.mute_selected")).action(move || apply(|p, acc| p.toggle_mute(acc))),
+            button(t_label("privacy.block_selected")).action(move || apply(|p, acc| p.toggle_block(acc))),
+            button(t_label("common.close")).action(move || dialog.set(Dialog::None))
+        )
+            .h_stack()
+            .style(|s| s.gap(5.).justify_end().width_full())
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
+
+
+/// Accounts from one of the [Privacy] lists, each with a button removing it from the list
+/// (titled with the `remove_key` text).
+fn privacy_list(
+    user: Ulid,
+    list: fn(&Privacy) -> Vec<Ulid>,
+    remove_key: &'static str,
+    remove: fn(&mut Privacy, Ulid)
+) -> impl IntoView {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let is_empty = move || config.with(|cf| list(&cf.privacy_of(&user)).is_empty());
+    (
+        dyn_stack(
+            move || config.with(|cf| list(&cf.privacy_of(&user))),
+            |acc| *acc,
+            move |acc| {
+                // -- Account may be unknown (e.g. no longer a contact)
+                let account = APP.with(|app| app.get_account(&acc));
+                let name = account.as_ref().map(|a| a.username.clone()).unwrap_or_else(|| acc.to_string());
+                let av = account.as_ref().map(avatar_of).unwrap_or_default();
+                (
+                    img(move || av.to_vec()).style(|s| s.size(20., 20.).border_radius(10.)),
+                    label(move || name.clone()).style(|s| s.flex_grow(1.)),
+                    button(t_label(remove_key)).action(move || config.update(|cf| {
+                        remove(cf.privacy.entry(user).or_default(), acc)
+                    }))
+                )
+                    .h_stack()
+                    .style(|s| s.gap(5.).items_center().width_full())
+            }
+        )
+            .style(|s| s.flex_col().gap(3.).width_full()),
+        t_label("privacy.nobody").style(move |s| s.color(colors().text_muted).apply_if(!is_empty(), |s| s.hide()))
+    )
+        .v_stack()
+        .style(|s| s.width_full())
+}
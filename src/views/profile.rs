@@ -0,0 +1,133 @@
+use std::rc::Rc;
+
+use floem::prelude::*;
+use floem::reactive::use_context;
+use tracing_lite::{error, info};
+
+use crate::avatar::{initials_avatar, square_avatar, AvatarCrop, AVATAR_SIZE};
+use crate::i18n::{t, t_label};
+use crate::view_data::session::APP;
+use crate::theme::colors;
+use super::dialogs::{dialog_buttons, pick_image, Dialog};
+
+
+
+/// Dialog editing username, avatar and status of the session user.  
+/// Confirmed profile is propagated to every msg and room showing the account.
+pub fn profile_dialog() -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let user = APP.with(|app| app.user.get_untracked());
+    let user_id = user.acc_id.id;
+    let username = RwSignal::new(user.username.clone());
+    let (backend, token) = APP.with(|app| (app.backend(), app.token.get_untracked()));
+    let old_status = token
+        .as_deref()
+        .and_then(|token| backend.status(token).ok().flatten())
+        .unwrap_or_default();
+    let status = RwSignal::new(old_status.clone());
+    // -- Picked picture (before crop) and the avatar made out of it
+    let source = RwSignal::new(None::<Rc<Vec<u8>>>);
+    let crop = RwSignal::new(AvatarCrop::default());
+    let avatar = RwSignal::new(user.av.clone());
+    let error_msg = RwSignal::new(None::<String>);
+
+    let recrop = move || {
+        let Some(data) = source.get_untracked() else { return };
+        match square_avatar(&data, &crop.get_untracked()) {
+            Ok(av) => avatar.set(Rc::new(av)),
+            Err(e) => error_msg.set(Some(e))
+        }
+    };
+    let adjust = move |change: fn(&mut AvatarCrop)| {
+        crop.update(change);
+        recrop();
+    };
+
+    let pick_avatar = move || pick_image(&t("profile.select_avatar"), move |res| match res {
+        Ok(data) => {
+            source.set(Some(Rc::new(data)));
+            crop.set(AvatarCrop::default());
+            recrop();
+        },
+        Err(e) => error_msg.set(Some(e))
+    });
+
+    let save = move || {
+        let Some(token) = token.as_deref() else { return };
+        let mut acc = user.as_ref().clone();
+        acc.username = username.get_untracked().trim().to_string();
+        acc.av = avatar.get_untracked();
+        let new_status = status.get_untracked();
+        let res = match new_status == old_status {
+            true => Ok(()),
+            false => backend.set_status(token, Some(new_status))
+        };
+        match res.and_then(|_| backend.update_profile(token, acc)) {
+            Ok(acc) => {
+                info!("Profile of {} updated", acc.username);
+                // -- Own status shown right away (not on the next presence poll)
+                if let Ok(own) = backend.statuses(&[acc.acc_id.clone()]) {
+                    APP.with(|app| app.statuses.update(|s| { s.remove(&user_id); s.extend(own); }));
+                }
+                APP.with(|app| app.update_profile(acc));
+                dialog.set(Dialog::None);
+            },
+            Err(e) => {
+                error!("Failed to update profile: {e}");
+                error_msg.set(Some(e.to_string()));
+            }
+        }
+    };
+
+    let crop_controls = (
+        button("−").action(move || adjust(|c| c.zoom_by(-0.25))),
+        button("+").action(move || adjust(|c| c.zoom_by(0.25))),
+        button("←").action(move || adjust(|c| c.pan(-0.05, 0.))),
+        button("→").action(move || adjust(|c| c.pan(0.05, 0.))),
+        button("↑").action(move || adjust(|c| c.pan(0., -0.05))),
+        button("↓").action(move || adjust(|c| c.pan(0., 0.05)))
+    )
+        .h_stack()
+        .style(move |s| s
+            .gap(3.)
+            .apply_if(source.with(|s| s.is_none()), |s| s.hide())
+        );
+
+    (
+        t_label("profile.title").style(|s| s.font_bold().font_size(18.)),
+        text_input(username).placeholder(t("login.username")).style(|s| s.width_full()),
+        text_input(status).placeholder(t("profile.status")).style(|s| s.width_full()),
+        (
+            img(move || match avatar.with(|av| av.is_empty()) {
+                true => initials_avatar(&user_id, &username.get()).to_vec(),
+                false => avatar.get().to_vec()
+            })
+                .style(|s| s
+                    .size(AVATAR_SIZE as f64 / 2., AVATAR_SIZE as f64 / 2.)
+                    .border(1.)
+                    .border_color(colors().accent)
+                    .border_radius(5.)
+                ),
+            (
+                button(t_label("common.choose_avatar")).action(pick_avatar),
+                button(t_label("common.remove_avatar")).action(move || {
+                    source.set(None);
+                    avatar.set(Rc::new(Vec::new()));
+                }),
+                crop_controls
+            )
+                .v_stack()
+                .style(|s| s.gap(5.))
+        )
+            .h_stack()
+            .style(|s| s.gap(10.).items_center()),
+        label(move || error_msg.get().unwrap_or_default())
+            .style(move |s| s
+                .color(colors().danger)
+                .apply_if(error_msg.with(|e| e.is_none()), |s| s.hide())
+            ),
+        dialog_buttons("common.save", save)
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
@@ -0,0 +1,386 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use editor::id::EditorId;
+use editor::text::{Document, SimpleStyling, Styling};
+use editor::EditorStyle;
+use floem::context::PaintCx;
+use floem::event::{Event, EventListener, EventPropagation};
+use floem::kurbo::{Rect, Size};
+use floem::prelude::*;
+use floem::reactive::{create_memo, use_context};
+use floem::text::{Attrs, AttrsList, FamilyOwned, Style as FontStyle, TextLayout, Weight};
+use floem::views::canvas;
+use floem::{AnyView, Clipboard, Renderer};
+use tracing_lite::error;
+use ulid::Ulid;
+
+use crate::config::ChatConfig;
+use crate::highlight::highlight;
+use crate::i18n::{t, t_label, t_plural};
+use crate::links::{find_links, normalize_url, open_in_browser, LinkPreview};
+use crate::mentions::parse_mentions;
+use crate::markup::{parse_blocks, parse_inline, Block, SpanStyle};
+use crate::view_data::session::APP;
+use crate::theme::{colors, theme_rev};
+use super::dialogs::Dialog;
+
+
+const MONOSPACE: &[FamilyOwned] = &[FamilyOwned::Monospace];
+/// Longer code blocks are collapsed.
+pub const CODE_COLLAPSE_LINES: usize = 12;
+/// Space between the mention and its chip edge.
+const CHIP_PADDING: f64 = 3.;
+const CHIP_RADIUS: f64 = 4.;
+
+
+/// Msg text with the markup rendered as rich text (links are clickable).
+pub fn msg_text_view(text: &str) -> AnyView {
+    let on_link = msg_link_handler();
+    v_stack_from_iter(parse_blocks(text).into_iter().map(move |block| block_view(block, on_link)))
+        .style(|s| s.gap(3.))
+        .into_any()
+}
+
+/// Open the clicked link; links to not trusted domains need a confirmation.
+fn msg_link_handler() -> impl Fn(&str) + Copy + 'static {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    move |url: &str| {
+        let url = normalize_url(url);
+        if config.with_untracked(|cf| cf.is_trusted(&url)) {
+            open_in_browser(&url);
+        } else {
+            dialog.set(Dialog::OpenLink(url));
+        }
+    }
+}
+
+fn block_view(block: Block, on_link: impl Fn(&str) + Copy + 'static) -> AnyView {
+    match block {
+        Block::Text(text) => inline_view(text, on_link).into_any(),
+        Block::Quote(text) => inline_view(text, on_link)
+            .container()
+            .style(|s| s
+                .padding_left(6.)
+                .border_left(3.)
+                .border_color(colors().text_muted)
+            )
+            .into_any(),
+        Block::Item { bullet, text } => (
+            label(move || bullet.clone()).style(|s| s.min_width(14.)),
+            inline_view(text, on_link)
+        )
+            .h_stack()
+            .style(|s| s.gap(4.))
+            .into_any(),
+        Block::Code { lang, code } => code_block_view(lang, code)
+    }
+}
+
+/// Fenced code block: highlighted (if the language is known), scrolled
+/// horizontally and collapsed when longer than [CODE_COLLAPSE_LINES].
+pub fn code_block_view(lang: Option<String>, code: String) -> AnyView {
+    let total_lines = code.lines().count();
+    let collapsed = RwSignal::new(total_lines > CODE_COLLAPSE_LINES);
+    let short_end = code.match_indices('\n').nth(CODE_COLLAPSE_LINES - 1).map_or(code.len(), |(i, _)| i);
+    let (shown_lang, copied) = (lang.clone(), code.clone());
+    let header = (
+        label(move || shown_lang.clone().unwrap_or_default()).style(|s| s.color(colors().text_muted).flex_grow(1.)),
+        button(label(move || if collapsed.get() { t("code.show_all") } else { t("code.show_less") }))
+            .action(move || collapsed.update(|c| *c = !*c))
+            .style(move |s| s.apply_if(total_lines <= CODE_COLLAPSE_LINES, |s| s.hide())),
+        button(t_label("code.copy")).action(move || {
+            if let Err(e) = Clipboard::set_contents(copied.clone()) {
+                error!("Failed to copy code: {e:?}");
+            }
+        })
+    )
+        .h_stack()
+        .style(|s| s.gap(5.).items_center());
+    // -- Laid out again on theme change, the syntax colors depend on it
+    let body = rich_text(move || match collapsed.get() {
+        true => code_layout(&code[..short_end], lang.as_deref()),
+        false => code_layout(&code, lang.as_deref())
+    })
+        .style(|s| s.text_clip())
+        .scroll()
+        .style(|s| s.max_width_full());
+    let more = label(move || t_plural("code.more_lines", total_lines.saturating_sub(CODE_COLLAPSE_LINES) as u64, &[]))
+        .style(move |s| s.color(colors().text_muted).apply_if(!collapsed.get(), |s| s.hide()));
+    (header, body, more)
+        .v_stack()
+        .style(|s| s
+            .gap(3.)
+            .padding(5.)
+            .border_radius(3.)
+            .background(colors().code_bg)
+        )
+        .into_any()
+}
+
+/// Monospace layout of the code with syntax colors.
+fn code_layout(code: &str, lang: Option<&str>) -> TextLayout {
+    let palette = colors();
+    let default = Attrs::new().family(MONOSPACE).color(palette.text);
+    let mut attrs = AttrsList::new(default);
+    for token in lang.and_then(|lang| highlight(code, lang, palette.dark)).unwrap_or_default() {
+        let (r, g, b) = token.color;
+        let mut token_attrs = default.color(Color::rgb8(r, g, b));
+        if token.bold { token_attrs = token_attrs.weight(Weight::BOLD) }
+        if token.italic { token_attrs = token_attrs.style(FontStyle::Italic) }
+        attrs.add_span(token.range, token_attrs);
+    }
+    let mut layout = TextLayout::new();
+    layout.set_text(code, attrs);
+    layout
+}
+
+fn inline_view(text: String, on_link: impl Fn(&str) + 'static) -> impl IntoView {
+    let InlineLayout { layout: hit_layout, links, decorations } = inline_layout(&text, Attrs::new().color(colors().text));
+    // -- Painted under the text, on the layout wrapped to the same width
+    let painted_layout = RefCell::new(hit_layout.clone());
+    let painted = canvas(move |cx, size| paint_decorations(cx, size, &mut painted_layout.borrow_mut(), &decorations))
+        .style(|s| s.absolute().size_full());
+    // -- Clicks are hit-tested on the layout wrapped to the shown width
+    let hit_layout = Rc::new(RefCell::new(hit_layout));
+    let resized_layout = hit_layout.clone();
+    // -- Laid out again on theme change (colors only, so hit layout stays valid)
+    let text_view = rich_text(move || inline_layout(&text, Attrs::new().color(colors().text)).layout)
+        .on_event(EventListener::Click, move |e| {
+            if let Event::PointerUp(pe) = e {
+                let idx = hit_layout.borrow().hit_point(pe.pos).index;
+                if let Some((_, url)) = links.iter().find(|(range, _)| range.contains(&idx)) {
+                    on_link(url);
+                    return EventPropagation::Stop
+                }
+            }
+            EventPropagation::Continue
+        })
+        .on_resize(move |rect| resized_layout.borrow_mut().set_size(rect.width() as f32, f32::MAX));
+    stack((painted, text_view))
+}
+
+/// Inline markup laid out for the [rich_text], with what is clickable or painted around it.
+pub struct InlineLayout {
+    pub layout: TextLayout,
+    /// Ranges of the shown links (in the shown text) with their urls.
+    pub links: Vec<(Range<usize>, String)>,
+    /// Painted decorations with the line and the range in that line they cover.
+    decorations: Vec<(usize, Range<usize>, Decoration)>
+}
+
+/// What is painted around the text, the text layout has only font attributes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Decoration {
+    /// Mention chip (`true` for the mentioned session user).
+    Chip(bool),
+    /// Line through the `~~struck~~` text.
+    Strike
+}
+
+/// Text layout of the inline markup (markers removed) with the shown links.
+pub fn inline_layout(text: &str, default: Attrs) -> InlineLayout {
+    let mut shown = String::with_capacity(text.len());
+    let mut styled = Vec::new();
+    let mut chips = Vec::new();
+    let user = APP.with(|app| app.user.with_untracked(|u| u.acc_id.id));
+    for span in parse_inline(text) {
+        let start = shown.len();
+        let content = &text[span.range];
+        // -- Mention tokens are shown as `@username` chips
+        let mut pos = 0;
+        for (range, acc) in parse_mentions(content) {
+            shown.push_str(&content[pos..range.start]);
+            let chip_start = shown.len();
+            shown.push_str(&format!("@{}", mention_name(&acc.id)));
+            chips.push((chip_start..shown.len(), acc.id == user));
+            pos = range.end;
+        }
+        shown.push_str(&content[pos..]);
+        if !span.style.is_plain() {
+            styled.push((start..shown.len(), span.style));
+        }
+    }
+    let palette = colors();
+    let mut attrs = AttrsList::new(default);
+    let mut decorations = Vec::new();
+    for (range, style) in styled {
+        if style.strike {
+            decorations.extend(split_lines(&shown, range.clone(), Decoration::Strike));
+        }
+        attrs.add_span(range, span_attrs(default, style));
+    }
+    for (range, is_user) in chips {
+        let color = if is_user { palette.mention_self } else { palette.mention };
+        attrs.add_span(range.clone(), default.weight(Weight::BOLD).color(color));
+        decorations.extend(split_lines(&shown, range, Decoration::Chip(is_user)));
+    }
+    let links: Vec<_> = find_links(&shown)
+        .into_iter()
+        .map(|range| (range.clone(), shown[range].to_string()))
+        .collect();
+    for (range, _) in &links {
+        attrs.add_span(range.clone(), default.color(palette.link));
+    }
+    let mut layout = TextLayout::new();
+    layout.set_text(&shown, attrs);
+    InlineLayout { layout, links, decorations }
+}
+
+/// Split the range of the text into ranges of its lines (as laid out: `(line, range in line)`).
+fn split_lines(text: &str, range: Range<usize>, deco: Decoration) -> Vec<(usize, Range<usize>, Decoration)> {
+    let mut parts = Vec::new();
+    let mut line_start = 0;
+    for (line, content) in text.split('\n').enumerate() {
+        let line_end = line_start + content.len();
+        let (start, end) = (range.start.max(line_start), range.end.min(line_end));
+        if start < end {
+            parts.push((line, start - line_start..end - line_start, deco));
+        }
+        line_start = line_end + 1;
+    }
+    parts
+}
+
+/// Paint the decorations on the glyphs of the layout, wrapped to the `size` of the text.
+fn paint_decorations(cx: &mut PaintCx, size: Size, layout: &mut TextLayout, decorations: &[(usize, Range<usize>, Decoration)]) {
+    if decorations.is_empty() { return }
+    layout.set_size(size.width as f32, f32::MAX);
+    let palette = colors();
+    for run in layout.layout_runs() {
+        for (_, range, deco) in decorations.iter().filter(|(line, ..)| *line == run.line_i) {
+            // -- Part of the range on this visual line (wrapped lines have several runs)
+            let mut glyphs = run.glyphs.iter().filter(|g| range.contains(&g.start)).peekable();
+            let Some(font_size) = glyphs.peek().map(|g| g.font_size as f64) else { continue };
+            let (x0, x1) = glyphs
+                .map(|g| (g.x as f64, (g.x + g.w) as f64))
+                .fold((f64::MAX, f64::MIN), |(a0, a1), (b0, b1)| (a0.min(b0), a1.max(b1)));
+            let (top, height) = (run.line_top as f64, run.line_height as f64);
+            match deco {
+                Decoration::Chip(is_user) => {
+                    let color = if *is_user { palette.mention_self } else { palette.mention };
+                    let chip = Rect::new(x0 - CHIP_PADDING, top, x1 + CHIP_PADDING, top + height);
+                    cx.fill(&chip.to_rounded_rect(CHIP_RADIUS), color.with_alpha_factor(0.15), 0.);
+                },
+                Decoration::Strike => {
+                    // -- Around the middle of the lowercase letters
+                    let y = run.line_y as f64 - font_size * 0.3;
+                    let width = (font_size / 14.).max(1.);
+                    cx.fill(&Rect::new(x0, y - width / 2., x1, y + width / 2.), palette.text, 0.);
+                }
+            }
+        }
+    }
+}
+
+/// Name shown for the mentioned account.
+pub fn mention_name(acc: &Ulid) -> String {
+    APP.with(|app| app.get_account(acc))
+        .map(|acc| acc.username)
+        .unwrap_or_else(|| t("common.unknown"))
+}
+
+fn span_attrs(default: Attrs, style: SpanStyle) -> Attrs {
+    let mut attrs = default;
+    if style.bold { attrs = attrs.weight(Weight::BOLD) }
+    if style.italic { attrs = attrs.style(FontStyle::Italic) }
+    if style.code { attrs = attrs.family(MONOSPACE).color(colors().code_text) }
+    attrs
+}
+
+
+/// Preview card of the first link in the msg text (if enabled in the config).
+pub fn link_preview_view(text: &str) -> AnyView {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let Some(url) = find_links(text).first().map(|range| normalize_url(&text[range.clone()])) else {
+        return empty().into_any()
+    };
+    // -- Nothing is fetched while previews are off
+    let enabled = create_memo(move |_| config.with(|cf| cf.link_previews));
+    dyn_container(
+        move || enabled.get(),
+        move |enabled| match enabled {
+            true => link_preview_card(url.clone()),
+            false => empty().into_any()
+        }
+    )
+        .into_any()
+}
+
+/// Preview card of the url (empty if there is no preview).
+fn link_preview_card(url: String) -> AnyView {
+    let Some(preview) = APP.with(|app| app.previews().preview(&url)) else {
+        return empty().into_any()
+    };
+    let LinkPreview { site, title, description, .. } = preview;
+    let on_link = msg_link_handler();
+    (
+        label(move || site.clone()).style(|s| s.color(colors().text_muted).font_size(11.)),
+        label(move || title.clone()).style(|s| s.font_bold().color(colors().link)),
+        label(move || description.clone().unwrap_or_default())
+            .style(|s| s.text_ellipsis().max_width_full())
+    )
+        .v_stack()
+        .on_click_stop(move |_| on_link(&url))
+        .style(move |s| s
+            .gap(2.)
+            .padding(5.)
+            .border_left(3.)
+            .border_color(colors().link)
+            .background(colors().code_bg)
+        )
+        .into_any()
+}
+
+
+// MARK: Editor
+
+/// Editor styling which highlights the markup of the typed msg.
+pub struct MarkupStyling {
+    inner: SimpleStyling,
+    doc: RwSignal<Rc<dyn Document>>
+}
+
+impl MarkupStyling {
+    pub fn new(doc: RwSignal<Rc<dyn Document>>) -> Self {
+        Self { inner: SimpleStyling::new(), doc }
+    }
+}
+
+impl Styling for MarkupStyling {
+    /// Changes with the palette too, so the markup colors are restyled on theme change.
+    fn id(&self) -> u64 {
+        (self.inner.id() << 32) | theme_rev()
+    }
+
+    fn font_size(&self, edid: EditorId, line: usize) -> usize {
+        self.inner.font_size(edid, line)
+    }
+
+    fn font_family(&self, edid: EditorId, line: usize) -> Cow<[FamilyOwned]> {
+        self.inner.font_family(edid, line)
+    }
+
+    fn apply_attr_styles(&self, edid: EditorId, style: &EditorStyle, line: usize, default: Attrs, attrs: &mut AttrsList) {
+        self.inner.apply_attr_styles(edid, style, line, default, attrs);
+        let content = self.doc.with_untracked(|doc| doc.rope_text().line_content(line).to_string());
+        // -- Markers are dimmed, styled text keeps its style (also in the editor)
+        let mut pos = 0;
+        for span in parse_inline(&content) {
+            if span.range.start > pos {
+                attrs.add_span(pos..span.range.start, default.color(colors().text_muted));
+            }
+            pos = span.range.end;
+            if !span.style.is_plain() {
+                attrs.add_span(span.range, span_attrs(default, span.style));
+            }
+        }
+        let line_end = content.trim_end_matches(['\n', '\r']).len();
+        if line_end > pos {
+            attrs.add_span(pos..line_end, default.color(colors().text_muted));
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use chrono_lite::Datetime;
+use floem::prelude::*;
+use floem::reactive::use_context;
+use tracing_lite::{error, info, trace};
+use ulid::Ulid;
+
+use crate::avatar::avatar_of;
+use crate::cont::acc::{Account, Member};
+use crate::cont::room::Room;
+use crate::i18n::{t, t_label, t_with};
+use crate::util::{Id, Tb};
+use crate::view_data::session::APP;
+use crate::theme::colors;
+use super::dialogs::{accounts_picker, dialog_buttons, pick_image, Dialog};
+use super::invites::room_invites_view;
+use super::presence::{presence_label, with_presence};
+
+
+
+/// Room settings dialog.
+/// Owner can edit and manage members, others get read-only view with option to leave.
+pub fn room_settings_dialog(room_id: Ulid) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let rooms = APP.with(|app| app.rooms);
+    let user_id = APP.with(|app| app.user.with_untracked(|u| u.acc_id.id));
+    let room_idx = APP.with(|app| app.rooms_tabs.with_untracked(|tabs| tabs.get(&room_id).map(|t| t.0)));
+    // -- Rebuild when ownership changes
+    dyn_container(
+        move || room_idx.and_then(|idx| rooms.with(|r| r.get(&idx).map(|r| r.is_owner(&user_id)))),
+        move |is_owner| match (room_idx, is_owner) {
+            (Some(idx), Some(true)) => owner_settings(room_id, idx).into_any(),
+            (Some(idx), Some(false)) => member_settings(room_id, idx).into_any(),
+            _ => (
+                t_label("room.not_found"),
+                button(t_label("common.close")).action(move || dialog.set(Dialog::None))
+            ).v_stack().style(|s| s.gap(8.)).into_any()
+        }
+    )
+}
+
+
+/// Take current room state, apply `change` on it and send it to the backend.
+/// Confirmed state is propagated to the sidebar and the room tab.
+fn update_room(room_idx: usize, error_msg: RwSignal<Option<String>>, change: impl FnOnce(&mut Room)) -> bool {
+    let Some(mut room) = APP.with(|app| app.rooms.with_untracked(|r| r.get(&room_idx).map(|r| r.to_room()))) else {
+        error_msg.set(Some(t("room.not_found")));
+        return false
+    };
+    change(&mut room);
+    match APP.with(|app| app.backend().update_room(room)) {
+        Ok(room) => {
+            trace!("fn: update_room: confirmed {}", room.room_id);
+            APP.with(|app| app.apply_room(room));
+            error_msg.set(None);
+            true
+        },
+        Err(e) => {
+            error!("Failed to update room: {e}");
+            error_msg.set(Some(e.to_string()));
+            false
+        }
+    }
+}
+
+/// Room members (owner first) as reactive list.
+fn members_list(room_idx: usize) -> impl Fn() -> Vec<(Account, bool)> {
+    let rooms = APP.with(|app| app.rooms);
+    move || rooms.with(|r| {
+        let Some(room) = r.get(&room_idx) else { return Vec::new() };
+        let mut members = room.members.values().cloned().map(|acc| (acc, false)).collect::<Vec<_>>();
+        members.sort_by(|a, b| a.0.username.cmp(&b.0.username));
+        members.insert(0, (room.owner.clone(), true));
+        members
+    })
+}
+
+fn error_label(error_msg: RwSignal<Option<String>>) -> impl IntoView {
+    label(move || error_msg.get().unwrap_or_default())
+        .style(move |s| s
+            .color(colors().danger)
+            .apply_if(error_msg.with(|e| e.is_none()), |s| s.hide())
+        )
+}
+
+
+// MARK: owner
+
+fn owner_settings(room_id: Ulid, room_idx: usize) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let rooms = APP.with(|app| app.rooms);
+    let Some(room) = rooms.with_untracked(|r| r.get(&room_idx).cloned()) else {
+        return empty().into_any()
+    };
+    let name = RwSignal::new(room.name.get_untracked());
+    let description = RwSignal::new(room.description.get_untracked().unwrap_or_default());
+    let avatar = RwSignal::new(room.avatar.get_untracked());
+    let to_add = RwSignal::new(HashSet::<Ulid>::new());
+    let error_msg = RwSignal::new(None::<String>);
+
+    let pick_avatar = move || pick_image(&t("room.select_avatar"), move |res| match res {
+        Ok(data) => avatar.set(Some(Rc::new(data))),
+        Err(e) => error_msg.set(Some(e))
+    });
+
+    let members = dyn_stack(
+        members_list(room_idx),
+        |(acc, is_owner)| (acc.acc_id.id, *is_owner),
+        move |(acc, is_owner)| {
+            let acc_id = acc.acc_id.clone();
+            let remove_id = acc.acc_id.clone();
+            let av = avatar_of(&acc);
+            let id = acc.acc_id.id;
+            (
+                with_presence(img(move || av.to_vec()).style(|s| s.size(20., 20.).border_radius(10.)), id),
+                label(move || acc.username.clone()),
+                presence_label(id).style(|s| s.flex_grow(1.)),
+                t_label("room.owner").style(move |s| s.color(colors().text_muted).apply_if(!is_owner, |s| s.hide())),
+                button(t_label("room.make_owner")).action(move || {
+                    info!("Transfer room {room_id} ownership to {acc_id}");
+                    update_room(room_idx, error_msg, |room| room.owner = acc_id.clone());
+                }).style(move |s| s.apply_if(is_owner, |s| s.hide())),
+                button(t_label("common.remove")).action(move || {
+                    update_room(room_idx, error_msg, |room| room.members.retain(|m| m.member_id != remove_id));
+                }).style(move |s| s.apply_if(is_owner, |s| s.hide()))
+            )
+                .h_stack()
+                .style(|s| s.gap(5.).items_center().width_full())
+        }
+    )
+        .style(|s| s.flex_col().gap(3.).width_full())
+        .scroll()
+        .style(|s| s.max_height(150.).width_full());
+
+    let add_members = move || {
+        let new = to_add.get_untracked();
+        if new.is_empty() { return }
+        let since = Datetime::default();
+        if update_room(room_idx, error_msg, |room| room.members.extend(new.into_iter().map(|id| Member {
+            member_id: Id { tb: Tb::Acc, id },
+            since: since.clone()
+        }))) {
+            to_add.set(HashSet::new());
+        }
+    };
+
+    let save = move || {
+        let description = description.get_untracked();
+        let saved = update_room(room_idx, error_msg, |room| {
+            room.name = name.get_untracked().trim().to_string();
+            room.description = (!description.trim().is_empty()).then_some(description);
+            room.avatar = avatar.get_untracked().map(|av| av.to_vec());
+        });
+        if saved { dialog.set(Dialog::None) }
+    };
+
+    (
+        t_label("room.settings_title").style(|s| s.font_bold().font_size(18.)),
+        text_input(name).placeholder(t("room.name")).style(|s| s.width_full()),
+        text_input(description).placeholder(t("room.description")).style(|s| s.width_full()),
+        (
+            img(move || avatar.get().map(|av| av.to_vec()).unwrap_or_default())
+                .style(|s| s.size(50., 50.).border(1.).border_color(colors().accent).border_radius(5.)),
+            button(t_label("common.choose_avatar")).action(pick_avatar),
+            button(t_label("common.remove_avatar")).action(move || avatar.set(None))
+        )
+            .h_stack()
+            .style(|s| s.gap(10.).items_center()),
+        t_label("room.members"),
+        members,
+        t_label("room.add_members"),
+        accounts_picker(to_add, move |acc| rooms.with(|r| r.get(&room_idx).is_some_and(|r| {
+            !r.is_owner(&acc.acc_id.id) && !r.members.contains_key(&acc.acc_id.id)
+        }))),
+        button(t_label("room.add_selected")).action(add_members),
+        room_invites_view(room_id, room_idx, error_msg),
+        error_label(error_msg),
+        (
+            button(t_label("room.delete"))
+                .action(move || dialog.set(Dialog::DeleteRoom(room_id)))
+                .style(|s| s.color(colors().danger)),
+            dialog_buttons("common.save", save)
+        )
+            .h_stack()
+            .style(|s| s.width_full().justify_between())
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+        .into_any()
+}
+
+
+/// Confirmation of deleting the room (with its msgs and invites), back to its settings on cancel.
+pub fn delete_room_dialog(room_id: Ulid) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let error_msg = RwSignal::new(None::<String>);
+    let name = APP.with(|app| app.rooms_tabs.with_untracked(|tabs| tabs.get(&room_id).map(|t| t.0)))
+        .and_then(|idx| APP.with(|app| app.rooms.with_untracked(|r| r.get(&idx).map(|r| r.name.get_untracked()))))
+        .unwrap_or_default();
+
+    let delete = move || {
+        let id = Id { tb: Tb::Room, id: room_id };
+        match APP.with(|app| app.backend().delete_room(&id)) {
+            Ok(()) => {
+                APP.with(|app| app.remove_room(&room_id));
+                dialog.set(Dialog::None);
+            },
+            Err(e) => error_msg.set(Some(e.to_string()))
+        }
+    };
+
+    (
+        label(move || t_with("room.delete_title", &[("name", &name)])).style(|s| s.font_bold().font_size(18.)),
+        t_label("room.delete_info").style(|s| s.color(colors().text_muted)),
+        error_label(error_msg),
+        (
+            button(t_label("common.cancel")).action(move || dialog.set(Dialog::RoomSettings(room_id))),
+            button(t_label("room.delete")).action(delete).style(|s| s.color(colors().danger))
+        )
+            .h_stack()
+            .style(|s| s.gap(5.).justify_end().width_full())
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
+
+
+// MARK: member
+
+fn member_settings(room_id: Ulid, room_idx: usize) -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let rooms = APP.with(|app| app.rooms);
+    let Some(room) = rooms.with_untracked(|r| r.get(&room_idx).cloned()) else {
+        return empty().into_any()
+    };
+    let name = room.name;
+    let description = room.description;
+    let error_msg = RwSignal::new(None::<String>);
+
+    let leave = move || {
+        let (room, user) = APP.with(|app| (
+            Id { tb: Tb::Room, id: room_id },
+            app.user.with_untracked(|u| u.acc_id.clone())
+        ));
+        match APP.with(|app| app.backend().leave_room(&room, &user)) {
+            Ok(()) => {
+                APP.with(|app| app.remove_room(&room_id));
+                dialog.set(Dialog::None);
+            },
+            Err(e) => error_msg.set(Some(e.to_string()))
+        }
+    };
+
+    (
+        label(move || name.get()).style(|s| s.font_bold().font_size(18.)),
+        label(move || description.get().unwrap_or_else(|| t("room.no_description")))
+            .style(|s| s.color(colors().text_muted)),
+        t_label("room.members"),
+        dyn_stack(
+            members_list(room_idx),
+            |(acc, is_owner)| (acc.acc_id.id, *is_owner),
+            |(acc, is_owner)| {
+                let av = avatar_of(&acc);
+                let id = acc.acc_id.id;
+                (
+                    with_presence(img(move || av.to_vec()).style(|s| s.size(20., 20.).border_radius(10.)), id),
+                    label(move || if is_owner {
+                        t_with("room.owner_name", &[("name", &acc.username)])
+                    } else {
+                        acc.username.clone()
+                    }),
+                    presence_label(id)
+                )
+                    .h_stack()
+                    .style(|s| s.gap(5.).items_center())
+            }
+        )
+            .style(|s| s.flex_col().gap(3.))
+            .scroll()
+            .style(|s| s.max_height(150.).width_full()),
+        error_label(error_msg),
+        (
+            button(t_label("room.leave")).action(leave).style(|s| s.color(colors().danger)),
+            button(t_label("common.close")).action(move || dialog.set(Dialog::None))
+        )
+            .h_stack()
+            .style(|s| s.width_full().justify_between())
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+        .into_any()
+}
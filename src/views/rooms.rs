@@ -1,10 +1,18 @@
+use std::collections::{BTreeMap, HashMap};
+
 use floem::prelude::*;
-use floem::reactive::create_effect;
+use floem::reactive::{create_effect, create_memo, use_context};
 use floem::taffy::prelude::TaffyGridLine;
 use floem::taffy::{GridPlacement, Line};
-use tracing_lite::{debug, info};
+use tracing_lite::{debug, info, trace};
+use ulid::Ulid;
 
+use crate::config::{ChatConfig, RoomSort, RoomsOrder};
+use crate::i18n::t;
+use crate::view_data::room::RoomViewData;
 use crate::view_data::session::APP;
+use crate::theme::colors;
+use super::panes::Panes;
 
 
 
@@ -24,6 +32,10 @@ pub fn rooms_view() -> impl IntoView {
     // -- Needed elements
     let rooms = APP.with(|a| a.rooms);
     let active = APP.with(|a| a.active_room);
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let panes = use_context::<Panes>().unwrap();
+    // -- Only changes of the rooms order (not the whole config) re-sort the list
+    let order = create_memo(move |_| config.with(|cf| cf.rooms_order.clone()));
     
     // -- Effects and derives needed for the view
     create_effect(move |_| {
@@ -43,10 +55,37 @@ pub fn rooms_view() -> impl IntoView {
         })
     });
     
+    // -- Animate rooms moved up the list (eg. on new msg)
+    create_effect(move |prev: Option<Vec<Ulid>>| {
+        let sorted = order.with(|order| rooms.with(|rooms| sorted_rooms(rooms, order)));
+        if let Some(prev) = prev {
+            for (pos, (_, room)) in sorted.iter().enumerate() {
+                if prev.iter().position(|id| *id == room.room_id.id).is_some_and(|old| old > pos) {
+                    room.moved.notify();
+                }
+            }
+        }
+        sorted.into_iter().map(|(_, room)| room.room_id.id).collect()
+    });
+    
     // -- View stack
-    stack((
+    let sort_button = button(label(move || {
+        match order.with(|order| order.sort) {
+            RoomSort::Activity => t("rooms.sort_activity"),
+            RoomSort::Manual => t("rooms.sort_manual")
+        }
+    })).action(move || {
+        config.update(|cf| cf.rooms_order.sort = match cf.rooms_order.sort {
+            RoomSort::Activity => RoomSort::Manual,
+            RoomSort::Manual => RoomSort::Activity
+        })
+    });
+
+    v_stack((
+        sort_button.style(|s| s.margin(5.)),
         dyn_stack(
-            move || rooms.get(),
+            // -- Tracks last msg of each room, so list is re-sorted on activity
+            move || order.with(|order| rooms.with(|rooms| sorted_rooms(rooms, order))),
             move |(id, _)| *id,
             move |(_, room)| {
                 room
@@ -64,9 +103,9 @@ pub fn rooms_view() -> impl IntoView {
             .padding_right(7.)
         ).scroll_style(|s| s.handle_thickness(6.).shrink_to_fit()),
     ))
-        .style(|s| s
-            .background(Color::LIGHT_BLUE)
-            .border_color(Color::BLACK)
+        .style(move |s| s
+            .background(colors().sidebar_bg)
+            .border_color(colors().border)
             .border(1.)
             .grid_column(Line {
                 start: GridPlacement::from_line_index(1),
@@ -76,5 +115,36 @@ pub fn rooms_view() -> impl IntoView {
                 start: GridPlacement::from_line_index(2),
                 end: GridPlacement::Span(3)
             })
+            .apply_if(!panes.sidebar_shown(), |s| s.hide())
         )
-}
\ No newline at end of file
+}
+
+
+/// Sort rooms according to the user [RoomsOrder].
+/// Keyed by room index, so existing views are moved instead of rebuilt.
+pub fn sorted_rooms(rooms: &BTreeMap<usize, RoomViewData>, order: &RoomsOrder) -> Vec<(usize, RoomViewData)> {
+    let by_id = rooms
+        .values()
+        .map(|room| (room.room_id.id, room))
+        .collect::<HashMap<Ulid, &RoomViewData>>();
+    let mut keys = rooms
+        .values()
+        .map(|room| (room.room_id.id, room.last_activity()))
+        .collect::<Vec<_>>();
+    order.sort(&mut keys);
+    trace!("fn: sorted_rooms: {} rooms", keys.len());
+    keys.into_iter()
+        .filter_map(|(id, _)| by_id.get(&id))
+        .map(|room| (room.idx(), (*room).clone()))
+        .collect()
+}
+
+/// Ids of the rooms in the order they are currently shown.
+pub fn visible_rooms_order(order: &RoomsOrder) -> Vec<Ulid> {
+    APP.with(|app| app.rooms.with_untracked(|rooms| {
+        sorted_rooms(rooms, order)
+            .into_iter()
+            .map(|(_, room)| room.room_id.id)
+            .collect()
+    }))
+}
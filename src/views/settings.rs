@@ -0,0 +1,289 @@
+use floem::action::set_window_scale;
+use floem::prelude::*;
+use floem::reactive::{create_effect, use_context};
+use floem::AnyView;
+use tracing_lite::{info, trace};
+
+use crate::commands::{commands, run_command};
+use crate::config::{config_path, enable_saving, save_debounced, ChatConfig, Keymap, KeyBinding, Lang, RoomSort, SettingsSection};
+use crate::drafts::{Drafts, DRAFTS_FILE};
+use crate::i18n::{t, t_label, t_with};
+use crate::storage::app_path;
+use crate::theme::{available_themes, colors, theme_title, THEMES_DIR};
+use crate::view_data::session::APP;
+use super::dialogs::Dialog;
+
+
+
+/// Settings window: sections bound to the [ChatConfig], changes apply live
+/// and are saved on every change.
+pub fn settings_dialog() -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let section = RwSignal::new(SettingsSection::Appearance);
+    // -- Bumped on reset, so the section inputs are rebuilt from the config
+    let resets = RwSignal::new(0_u32);
+
+    create_effect(move |prev: Option<()>| {
+        config.track();
+        if prev.is_some() {
+            trace!("effect: settings changed, saving config");
+            enable_saving();
+            save_debounced(config);
+        }
+    });
+
+    let sections = v_stack_from_iter(SettingsSection::ALL.into_iter().map(move |sec| {
+        button(label(move || sec.to_string()))
+            .action(move || section.set(sec))
+            .style(move |s| s
+                .width_full()
+                .apply_if(section.get() == sec, |s| s.font_bold().background(colors().selected))
+            )
+    }))
+        .style(|s| s.gap(3.).min_width(110.));
+
+    let content = dyn_container(
+        move || (section.get(), resets.get()),
+        |(sec, _)| section_view(sec)
+    )
+        .style(|s| s.flex_grow(1.).min_width(280.));
+
+    let reset = move || {
+        let sec = section.get_untracked();
+        info!("Settings: reset {sec} to defaults");
+        config.update(|cf| cf.reset_section(sec));
+        if sec == SettingsSection::Appearance {
+            set_window_scale(config.with_untracked(|cf| cf.scale));
+        }
+        resets.update(|r| *r += 1);
+    };
+
+    (
+        t_label("cmd.settings").style(|s| s.font_bold().font_size(18.)),
+        (sections, content).h_stack().style(|s| s.gap(15.)),
+        (
+            button(t_label("settings.reset"))
+                .action(reset)
+                .disabled(move || section.get() == SettingsSection::Storage),
+            button(t_label("common.close")).action(move || dialog.set(Dialog::None))
+        )
+            .h_stack()
+            .style(|s| s.gap(5.).justify_end().width_full())
+    )
+        .v_stack()
+        .style(|s| s.gap(10.).width_full())
+}
+
+fn section_view(section: SettingsSection) -> AnyView {
+    match section {
+        SettingsSection::Appearance => appearance_view().into_any(),
+        SettingsSection::Language => (
+            t_label("settings.language"),
+            h_stack_from_iter(Lang::ALL.map(|lang| {
+                choice_button(move || lang.native_name().to_string(), lang, |cf| cf.language, |cf, l| cf.language = l)
+            }))
+                .style(|s| s.gap(5.))
+        ).v_stack().style(|s| s.gap(8.)).into_any(),
+        SettingsSection::Notifications => (
+            toggle("settings.unread_badges", |cf| cf.notifications.unread_badges, |cf, on| {
+                cf.notifications.unread_badges = on
+            }),
+            toggle("settings.mentions_only", |cf| cf.notifications.mentions_only, |cf, on| {
+                cf.notifications.mentions_only = on
+            }),
+            toggle("settings.send_typing", |cf| cf.notifications.send_typing, |cf, on| {
+                cf.notifications.send_typing = on
+            })
+        ).v_stack().style(|s| s.gap(8.)).into_any(),
+        SettingsSection::Keybindings => keybindings_view().into_any(),
+        SettingsSection::Storage => storage_view().into_any(),
+        SettingsSection::Privacy => privacy_view().into_any()
+    }
+}
+
+
+// MARK: Sections
+
+fn appearance_view() -> impl IntoView {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    (
+        t_label("settings.theme"),
+        h_stack_from_iter(available_themes().into_iter().map(move |name| {
+            let theme = name.clone();
+            button(label(move || match config.with(|cf| cf.theme == theme) {
+                true => format!("✓ {}", theme_title(&theme)),
+                false => theme_title(&theme)
+            }))
+                .action(move || config.update(|cf| cf.theme = name.clone()))
+        }))
+            .style(|s| s.gap(5.)),
+        label(|| t_with("settings.themes_dir", &[("dir", &app_path(THEMES_DIR).display())]))
+            .style(|s| s.color(colors().text_muted).font_size(11.)),
+        t_label("settings.scale"),
+        (
+            button("−").action(|| { run_command("view.zoom_out"); }),
+            label(move || format!("{:.0}%", config.with(|cf| cf.scale) * 100.)).style(|s| s.min_width(45.)),
+            button("+").action(|| { run_command("view.zoom_in"); })
+        )
+            .h_stack()
+            .style(|s| s.gap(5.).items_center()),
+        t_label("settings.rooms_order"),
+        choice(
+            &[("settings.sort_activity", RoomSort::Activity), ("settings.sort_manual", RoomSort::Manual)],
+            |cf| cf.rooms_order.sort,
+            |cf, sort| cf.rooms_order.sort = sort
+        )
+    )
+        .v_stack()
+        .style(|s| s.gap(8.))
+}
+
+fn keybindings_view() -> impl IntoView {
+    let shortcuts = commands()
+        .into_iter()
+        .filter_map(|cmd| cmd.key.map(|key| (cmd.title_key, key.to_string())))
+        .map(|(title, key)| (
+            t_label(title).style(|s| s.flex_grow(1.)),
+            label(move || key.clone()).style(|s| s.color(colors().text_muted))
+        ).h_stack().style(|s| s.gap(10.).width_full()));
+    (
+        t_label("settings.editor_keys"),
+        binding_input("settings.key_send", |km| &km.send, |km, keys| km.send = keys),
+        binding_input("settings.key_new_line", |km| &km.new_line, |km, keys| km.new_line = keys),
+        binding_input("settings.key_edit_last", |km| &km.edit_last, |km, keys| km.edit_last = keys),
+        t_label("settings.shortcuts"),
+        v_stack_from_iter(shortcuts).style(|s| s.gap(3.).width_full())
+    )
+        .v_stack()
+        .style(|s| s.gap(8.))
+}
+
+/// Input of the editor action bindings, applied when they parse.
+fn binding_input(
+    title_key: &'static str,
+    get: fn(&Keymap) -> &Vec<KeyBinding>,
+    set: fn(&mut Keymap, Vec<KeyBinding>)
+) -> impl IntoView {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let text = RwSignal::new(config.with_untracked(|cf| Keymap::list_to_string(get(&cf.keymap))));
+    let error_msg = RwSignal::new(None::<String>);
+    create_effect(move |_| {
+        let typed = text.get();
+        match Keymap::parse_list(&typed) {
+            Ok(keys) => {
+                error_msg.set(None);
+                if config.with_untracked(|cf| *get(&cf.keymap) != keys) {
+                    config.update(|cf| set(&mut cf.keymap, keys));
+                }
+            },
+            Err(e) => error_msg.set(Some(e))
+        }
+    });
+    (
+        (
+            t_label(title_key).style(|s| s.min_width(100.)),
+            text_input(text).style(|s| s.flex_grow(1.))
+        ).h_stack().style(|s| s.gap(5.).items_center().width_full()),
+        label(move || error_msg.get().unwrap_or_default())
+            .style(move |s| s.color(colors().danger).apply_if(error_msg.with(|e| e.is_none()), |s| s.hide()))
+    )
+        .v_stack()
+        .style(|s| s.width_full())
+}
+
+fn storage_view() -> impl IntoView {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    let drafts = APP.with(|app| app.drafts);
+    let (config_path, drafts_path) = (config_path().display().to_string(), app_path(DRAFTS_FILE).display().to_string());
+    (
+        label(move || t_with("settings.config_path", &[("path", &config_path)])),
+        label(move || t_with("settings.drafts_path", &[("path", &drafts_path)])),
+        label(move || config.with(|cf| t_with("settings.window", &[
+            ("width", &cf.size.width.round()), ("height", &cf.size.height.round()),
+            ("x", &cf.position.x.round()), ("y", &cf.position.y.round())
+        ]))),
+        (
+            button(t_label("settings.save_now")).action(move || {
+                enable_saving();
+                config.with_untracked(|cf| cf.save_to_file());
+            }),
+            button(t_label("settings.forget_drafts")).action(move || {
+                drafts.set(Drafts::default());
+                drafts.with_untracked(|d| d.save());
+            })
+        ).h_stack().style(|s| s.gap(5.))
+    )
+        .v_stack()
+        .style(|s| s.gap(8.))
+}
+
+fn privacy_view() -> impl IntoView {
+    let dialog = use_context::<RwSignal<Dialog>>().unwrap();
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    (
+        toggle("settings.link_previews", |cf| cf.link_previews, |cf, on| cf.link_previews = on),
+        t_label("settings.trusted_domains"),
+        dyn_stack(
+            move || config.with(|cf| cf.trusted_domains.clone()),
+            |domain| domain.clone(),
+            move |domain| {
+                let remove = domain.clone();
+                (
+                    label(move || domain.clone()).style(|s| s.flex_grow(1.)),
+                    button(t_label("common.remove")).action(move || config.update(|cf| cf.trusted_domains.retain(|d| *d != remove)))
+                ).h_stack().style(|s| s.gap(5.).items_center().width_full())
+            }
+        ).style(|s| s.flex_col().gap(3.).width_full()),
+        t_label("settings.none").style(move |s| s
+            .color(colors().text_muted)
+            .apply_if(config.with(|cf| !cf.trusted_domains.is_empty()), |s| s.hide())
+        ),
+        button(t_label("settings.block_lists")).action(move || dialog.set(Dialog::Privacy))
+    )
+        .v_stack()
+        .style(|s| s.gap(8.).width_full())
+}
+
+
+// MARK: Controls
+
+/// Checkbox bound to the config value, titled with the `title_key` text.
+fn toggle(title_key: &'static str, get: fn(&ChatConfig) -> bool, set: fn(&mut ChatConfig, bool)) -> impl IntoView {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    (
+        label(move || if config.with(get) { "☑" } else { "☐" }),
+        t_label(title_key)
+    )
+        .h_stack()
+        .on_click_stop(move |_| config.update(|cf| set(cf, !get(cf))))
+        .style(|s| s.gap(5.).hover(|s| s.background(colors().hover)))
+}
+
+/// Buttons of the options (with the title keys), the one set in the config is marked.
+fn choice<T: Copy + PartialEq + 'static>(
+    options: &[(&'static str, T)],
+    get: fn(&ChatConfig) -> T,
+    set: fn(&mut ChatConfig, T)
+) -> impl IntoView {
+    h_stack_from_iter(options.iter().map(move |&(key, value)| choice_button(move || t(key), value, get, set)))
+        .style(|s| s.gap(5.))
+}
+
+/// Option button, marked if the option is set in the config.
+fn choice_button<T: Copy + PartialEq + 'static>(
+    title: impl Fn() -> String + 'static,
+    value: T,
+    get: fn(&ChatConfig) -> T,
+    set: fn(&mut ChatConfig, T)
+) -> impl IntoView {
+    let config = use_context::<RwSignal<ChatConfig>>().unwrap();
+    button(label(move || {
+        let text = title();
+        match config.with(get) == value {
+            true => format!("✓ {text}"),
+            false => text
+        }
+    }))
+        .action(move || config.update(|cf| set(cf, value)))
+}
@@ -1,238 +1,78 @@
+use std::sync::atomic::{AtomicU16, Ordering};
 use std::time::Duration;
 
 use chat_util::gen::gen_u64_in_range;
+use chrono_lite::Datetime;
 use floem::prelude::*;
 use floem::menu::{Menu, MenuItem};
-use floem::reactive::{batch, create_memo};
-use floem::reactive::create_effect;
-use floem::reactive::use_context;
+use floem::reactive::{create_memo, use_context};
 use floem::taffy::prelude::TaffyGridLine;
 use floem::taffy::{AlignContent, GridPlacement, Line};
-use tracing_lite::{debug, error, info, trace};
-use ulid::Ulid;
+use tracing_lite::{info, trace};
 
+use crate::commands::{menu_of, run_command, MenuGroup};
 use crate::cont::acc::Account;
+use crate::cont::msg::{Msg, Text};
+use crate::util::{Id, Tb};
+use crate::i18n::{t, t_label};
 use crate::view_data::msg::MsgViewData;
-use crate::view_data::room::RoomViewData;
 use crate::view_data::session::APP;
 use crate::view_data::MsgEvent;
+use crate::theme::colors;
 
-use super::msgs::RoomMsgUpt;
 
-
-#[derive(Clone, Debug)]
-pub enum EditList {
-    None,
-    Room,
-    Msg,
-    Account
-}
-
-#[derive(Clone, Debug)]
-pub enum NewList {
-    None,
-    Room,
-    Msg,
-    Msgs,
-    Account
-}
-
-impl std::fmt::Display for EditList {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            EditList::None => f.write_str("None"),
-            EditList::Room => f.write_str("Room"),
-            EditList::Msg => f.write_str("Msg"),
-            EditList::Account => f.write_str("Account"),
-        }
-    }
-}
+/// Number in the text of the next [test_msg].
+static TEST_MSG_COUNTER: AtomicU16 = AtomicU16::new(1);
 
 
-/// - [ ] reacts on clicked buttons/menus with actions.
+/// - [x] reacts on clicked buttons/menus with registered [commands](crate::commands).
 pub fn toolbar_view() -> impl IntoView {
-    let edit_list_signal = RwSignal::new(EditList::None);
-    let new_list_signal = RwSignal::new(NewList::None);
-    // -- Id is a room, that got an update
-    let msg_event = use_context::<RwSignal<MsgEvent>>().unwrap();
-    let new_room_editor_doc = use_context::<RwSignal<Option<Ulid>>>().unwrap();
     let show_load_more_button = use_context::<RwSignal<bool>>().unwrap();
     let show_load_memo = create_memo(move |_| show_load_more_button.get());
-    // -- Action to create test room on click
-    create_effect(move |_| {
-        let new = new_list_signal.get();
-        trace!("->> effect for `New Menu`: {new:#?}");
-        match new {
-            NewList::None => { trace!("Clicked NewList::None"); },
-            NewList::Room => {
-                let room_view = RoomViewData::new_from_click();
-                trace!("Clicked NewList::Room - idx: {} | room: {}", room_view.idx(), room_view.room_id.ulid());
-                
-                APP.with(|app| {
-                    batch(|| {
-                        app.accounts.update(|accs| { accs.insert(room_view.owner.acc_id.id, room_view.owner.clone());} );
-                        app.rooms.update(|rooms| {
-                            debug!("room: app.rooms.update");
-                            if let Some(ret) = rooms.insert(room_view.idx(), room_view.clone()) {
-                                error!("value returned when attempted to insert {}", ret.room_id)
-                            }
-                        });
-                        app.rooms_tabs.update(|tabs| {
-                            tabs.insert(
-                                room_view.room_id.id,
-                                (room_view.idx(), room_view.view_id, room_view.get_update)
-                            );
-                        });
-                    });
-                    new_room_editor_doc.set(Some(room_view.room_id.id));
-                    trace!("Created and inserted test RoomViewData")
-                });
-            },
-            NewList::Msg => {
-                trace!("Clicked NewList::Msg");
-                // -- Get active room
-                if let Some(active) = APP.with(|app| app.active_room.get_untracked()) {
-                    APP.with(|app| {
-                        app.rooms.with_untracked(|rooms| {
-                            // -- Get or create account (only in early faze)
-                            let acc = {
-                                let room = rooms.get(&active.idx).unwrap();
-                                let rand = gen_u64_in_range(0..3);
-                                if rand == 0 {
-                                    room.owner.clone()
-                                } else {
-                                    let keys_vec = room.members.keys().cloned().collect::<Vec<_>>();
-                                    println!("key_vec len: {}", keys_vec.len());
-                                    let key = keys_vec.get(rand as usize - 1).cloned().unwrap();
-                                    room.members.get(&key).unwrap().clone()
-                                }
-                            };
-                            // -- Create msg
-                            let msg = MsgViewData::new_from_click(active.id(), &acc);
-                            // -- Append msg onto `msgs` and `last_msg`
-                            if let Some(room) = rooms.get(&active.idx) {
-                                room.msgs.update(|msgs| {
-                                    msgs.append_new_msg(msg.clone());
-                                    info!("New msg appended!")
-                                });
-                                room.update_msg_count();
 
-                                room.last_msg.set(Some(msg));
-                                // -- Notify subscribers about new msg event
-                                msg_event.set(MsgEvent::NewFor(room.room_id.id));
-                                // msg_event
-                            }
-                        })
-                    });
-                }
-            },
-            NewList::Msgs => {
-                trace!("Clicked NewList::Msgs");
-                // -- Get active room
-                if let Some(active) = APP.with(|app| app.active_room.get_untracked()) {
-                    APP.with(|app| {
-                        app.rooms.with_untracked(|rooms| {
-                            // -- Get or create account (only in early faze)
-                            let acc = {
-                                let room = rooms.get(&active.idx).unwrap();
-                                let rand = gen_u64_in_range(0..3);
-                                if rand == 0 {
-                                    room.owner.clone()
-                                } else {
-                                    let keys_vec = room.members.keys().cloned().collect::<Vec<_>>();
-                                    // println!("key_vec len: {}", keys_vec.len());
-                                    let key = keys_vec.get(rand as usize - 1).cloned().unwrap();
-                                    room.members.get(&key).unwrap().clone()
-                                }
-                            };
-                            // -- Create msgs
-                            let mut msgs = vec!();
-                            for _ in 0..40 {
-                                std::thread::sleep(Duration::from_millis(2));
-                                msgs.push(MsgViewData::new_from_click(active.id(), &acc))
-                            }
-                            let last_msg = msgs.last().unwrap().clone();
-                            // -- Append msg onto `msgs` and `last_msg`
-                            if let Some(room) = rooms.get(&active.idx) {
-                                room.msgs.update(|chunks| {
-                                    for msg in msgs {
-                                        chunks.append_new_msg(msg);
-                                    }
-                                });
-                                room.update_msg_count();
-                                info!("{} new msgs appended!", room.msgs_count.get_untracked());
-                                room.last_msg.set(Some(last_msg));
-                                // -- Notify subscribers about new msg event
-                                msg_event.set(MsgEvent::NewManyFor(room.room_id.id));
-                            }
-                        })
-                    });
-                }
-            },
-            NewList::Account => {
-                trace!("Clicked NewList::Account");
-                if let Some(acc) = Account::new_from_click() {
-                    APP.with(|app| {
-                        app.accounts.update(|accs| { accs.insert(acc.acc_id.id.clone(), acc); })
-                    })
-                }
-            }
-        }
-    });
+    let new_menu = button(t_label("toolbar.new")).popout_menu(move || menu_of(MenuGroup::New));
+    let edit_menu = button(t_label("toolbar.edit")).popout_menu(move || menu_of(MenuGroup::Edit));
     
-    let new_menu = "New".button().popout_menu(move || {
-        Menu::new("")
-            .entry(MenuItem::new("Msg").action(move || {
-                new_list_signal.set(NewList::Msg);
-            }))
-            .entry(MenuItem::new("Msgs").action(move || {
-                new_list_signal.set(NewList::Msgs);
-            }))
-            .entry(MenuItem::new("Room").action(move || {
-                new_list_signal.set(NewList::Room);
-            }))
-            .separator()
-            .entry(MenuItem::new("Account").action(move || {
-                new_list_signal.set(NewList::Account);
-            }))
+    // -- Active account with unread msgs count across all accounts
+    let accounts_menu = button(label(move || {
+        let (user, total) = APP.with(|app| (app.user.with(|u| u.username.clone()), app.total_unread()));
+        match total {
+            0 => user,
+            n => format!("{user} ({n})")
+        }
+    })).popout_menu(move || {
+        let mut menu = Menu::new("");
+        for (i, (acc, unread)) in APP.with(|app| app.signed_in_accounts()).into_iter().enumerate() {
+            let acc_id = acc.acc_id.id;
+            let mark = if i == 0 { "✓ " } else { "" };
+            let title = match unread {
+                0 => format!("{mark}{}", acc.username),
+                n => format!("{mark}{} ({n})", acc.username)
+            };
+            menu = menu.entry(MenuItem::new(title).action(move || {
+                APP.with(|app| app.switch_account(&acc_id));
+            }));
+        }
+        menu.separator()
+            .entry(MenuItem::new(t("cmd.add_account")).action(move || { run_command("account.add"); }))
+            .entry(MenuItem::new(t("cmd.sign_out")).action(move || { run_command("account.sign_out"); }))
     });
 
-    let edit_menu = "Edit".button().popout_menu(move || {
-        Menu::new("")
-            .entry(MenuItem::new("Account").action(move || {
-                edit_list_signal.set(EditList::Account);
-            }))
-            .separator()
-            .entry(MenuItem::new("Room").action(move || {
-                edit_list_signal.set(EditList::Room);
-            }))
-            .entry(MenuItem::new("Msg").action(move || {
-                edit_list_signal.set(EditList::Msg);
-            }))
-    });
-    
     stack((
         h_stack((
             stack((
+                button("☰").action(move || { run_command("view.toggle_sidebar"); }),
                 new_menu,
                 edit_menu,
-                "Settings".button().action(move || {}),
-                "About".button().action(move || {}),
+                button(t_label("cmd.invitations")).action(move || { run_command("room.invitations"); }),
+                button(t_label("cmd.settings")).action(move || { run_command("app.settings"); }),
+                button(t_label("toolbar.about")).action(move || {}),
+                accounts_menu,
             )).style(|s| s
                 .padding(5.)
                 .row_gap(5.)
             ),
-            "load more".button().action(move || {
-                let act_room = APP.with(|app| {
-                    if let Some(ar) = app.active_room.get_untracked() {
-                        app.rooms_tabs.with_untracked(|rt| {
-                            if let Some(act_tab) = rt.get(&ar.id) {
-                                act_tab.2.set(RoomMsgUpt::LoadMore);
-                            }
-                        });
-                    }
-                }); 
-            })
+            button(t_label("toolbar.load_more")).action(move || { run_command("room.load_more"); })
                 .disabled(move || !show_load_memo.get())
                 .style(|s| s.disabled(|s| s
                     .background(Color::TRANSPARENT)
@@ -246,8 +86,8 @@ pub fn toolbar_view() -> impl IntoView {
         ),
     )).debug_name("menu toolbar")
     .style(|s| s
-        .background(Color::MEDIUM_ORCHID)
-        .border_color(Color::BLACK)
+        .background(colors().toolbar_bg)
+        .border_color(colors().border)
         .border(1.)
         .grid_column(Line {
             start: GridPlacement::from_line_index(1),
@@ -258,4 +98,73 @@ pub fn toolbar_view() -> impl IntoView {
             end: GridPlacement::Span(1)
         })
     )
-}
\ No newline at end of file
+}
+
+
+// MARK: Test data
+
+/// Append `count` msgs from random members to the active room (only in early faze).
+pub fn add_test_msgs(msg_event: RwSignal<MsgEvent>, count: usize) {
+    trace!("fn: add_test_msgs: {count}");
+    // -- Get active room
+    let Some(active) = APP.with(|app| app.active_room.get_untracked()) else { return };
+    APP.with(|app| {
+        app.rooms.with_untracked(|rooms| {
+            let Some(room) = rooms.get(&active.idx) else { return };
+            // -- Random author from the owner and members
+            let authors: Vec<&Account> = std::iter::once(&room.owner).chain(room.members.values()).collect();
+            let rand = gen_u64_in_range(0..authors.len() as u64) as usize;
+            let acc = authors.get(rand).copied().unwrap_or(&room.owner);
+            let owner = acc.acc_id.id == room.owner.acc_id.id;
+            // -- Create msgs (apart in time, so they have different ids)
+            let mut msgs = vec!();
+            for _ in 0..count {
+                if !msgs.is_empty() { std::thread::sleep(Duration::from_millis(2)) }
+                msgs.push(MsgViewData::new(test_msg(active.id(), acc), acc, owner))
+            }
+            let Some(last_msg) = msgs.last().cloned() else { return };
+            // -- Append msgs onto `msgs` (of every account in the room) and `last_msg`
+            app.receive_msgs(&room.room_id.id, msgs);
+            info!("{count} new msgs appended, {} in the room", room.msgs_count.get_untracked());
+            room.last_msg.set(Some(last_msg));
+            // -- Notify subscribers about new msg event
+            msg_event.set(match count {
+                1 => MsgEvent::NewFor(room.room_id.id),
+                _ => MsgEvent::NewManyFor(room.room_id.id)
+            });
+        })
+    });
+}
+
+/// Msg of the `author` with a numbered text, sent a moment ago.
+pub fn test_msg(room: Id, author: &Account) -> Msg {
+    Msg {
+        msg_id: Id::new(Tb::Msg),
+        room_id: room,
+        author: author.acc_id.clone(),
+        created: Datetime::default().sub_from(Duration::from_secs(5)),
+        sent: Some(Datetime::default()),
+        text: Text {
+            current: format!("Really important message no: {}", TEST_MSG_COUNTER.fetch_add(1, Ordering::Relaxed)),
+            edits: None,
+            last_edited: None
+        },
+        media: None,
+        edited: None,
+        comments: None,
+        reactions: None,
+        reply_to: None,
+        delivered_to_all: true,
+        viewed_by_all: true
+    }
+}
+
+/// Add randomly generated account.
+pub fn add_test_account() {
+    trace!("fn: add_test_account");
+    if let Some(acc) = Account::new_from_click() {
+        APP.with(|app| {
+            app.accounts.update(|accs| { accs.insert(acc.acc_id.id.clone(), acc); })
+        })
+    }
+}